@@ -1,51 +1,302 @@
 use chrono::NaiveDate;
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 use serde_with::{serde_as, DisplayFromStr};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use walkdir;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
 
-use verifier::instance::{flf64, Instance};
-use verifier::read;
+use verifier::error::VerifyError;
+use verifier::flf64;
+use verifier::instance::{Instance, InstanceFamily};
 use verifier::solution::Solution;
+use verifier::verify::report::csv_field;
 use verifier::verify::verify;
+use verifier::{binary_sidecar, read_binary_sidecar};
+
+use crate::events::Event;
+use crate::store::BksStore;
 
 pub type Instances = HashMap<String, Instance>;
 
-pub fn read_instances(instances_dir: &Path) -> Result<Instances, std::io::Error> {
-    let mut db = Instances::new();
+// instances are known by the names of their files
+pub fn read_named_instance(path: &Path) -> Result<Instance, VerifyError> {
+    let name = path.file_name().unwrap().to_str().unwrap();
+    let content = fs::read_to_string(path)
+        .map_err(|x| VerifyError::Io(format!("{}: {x}", path.display())))?;
+    Instance::from_str_with_name(&content, name)
+}
+
+// instance files that could not be read and why
+pub type LoadErrors = Vec<(PathBuf, String)>;
+
+// the file every instance was read from
+pub type Sources = HashMap<String, PathBuf>;
+
+// file watchers report absolute paths, the directories may be relative
+fn source_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// files of the instance directories in the order in which they are looked up
+fn instance_files(instances_dirs: &[PathBuf], recursive: bool) -> std::io::Result<Vec<PathBuf>> {
+    let depth = if recursive { usize::MAX } else { 1 };
+    let mut files = vec![];
 
-    for fd in instances_dir.read_dir()? {
-        let path = fd.unwrap().path();
-        match read::<Instance>(&path) {
-            Ok(instance) => {
-                let instance_name = path.file_name().unwrap().to_str().unwrap().to_string();
-                db.entry(instance_name).or_insert(instance);
+    for instances_dir in instances_dirs {
+        for entry in walkdir::WalkDir::new(instances_dir)
+            .min_depth(1)
+            .max_depth(depth)
+            .sort_by_file_name()
+        {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type().is_file() && path.extension().is_none_or(|ext| ext != "bin") {
+                files.push(path.to_path_buf());
             }
-            Err(err) => println!("{}: {err}", path.display()),
         }
     }
 
-    println!("read {} instances", db.len());
+    Ok(files)
+}
+
+pub fn read_instance_file(path: &Path) -> Result<Instance, VerifyError> {
+    match read_binary_sidecar(path) {
+        None => read_named_instance(path),
+        Some(Ok(instance)) => Ok(instance),
+        Some(Err(err)) => {
+            tracing::warn!("{}: {err}", binary_sidecar(path).display());
+            read_named_instance(path)
+        }
+    }
+}
+
+// instances and errors are kept with the positions of their files, so that
+// loading them in parallel ends up the same as one after another
+type Loaded = (HashMap<String, (usize, Instance)>, Vec<(usize, String)>);
+
+fn keep_first(files: &[PathBuf], db: &mut Loaded, name: String, i: usize, instance: Instance) {
+    match db.0.entry(name) {
+        Entry::Vacant(entry) => {
+            entry.insert((i, instance));
+        }
+        Entry::Occupied(mut entry) => {
+            let skipped = if i < entry.get().0 {
+                entry.insert((i, instance)).0
+            } else {
+                i
+            };
+            tracing::warn!(
+                "{}: instance `{}' is already loaded, skipping",
+                files[skipped].display(),
+                entry.key()
+            );
+        }
+    }
+}
+
+fn load(files: &[PathBuf], mut db: Loaded, (i, path): (usize, &PathBuf)) -> Loaded {
+    match read_instance_file(path) {
+        Ok(instance) => {
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            keep_first(files, &mut db, name, i, instance);
+        }
+        Err(err) => {
+            tracing::warn!("{}: {err}", path.display());
+            db.1.push((i, err.to_string()));
+        }
+    }
+    db
+}
 
-    Ok(db)
+#[cfg(feature = "parallel-load")]
+fn merge(files: &[PathBuf], mut db: Loaded, other: Loaded) -> Loaded {
+    for (name, (i, instance)) in other.0 {
+        keep_first(files, &mut db, name, i, instance);
+    }
+    db.1.extend(other.1);
+    db
+}
+
+// the first of the instances with the same file name is kept, files that can
+// not be read are skipped and returned with their errors
+pub fn read_instances(
+    instances_dirs: &[PathBuf],
+    recursive: bool,
+) -> Result<(Instances, Sources, LoadErrors), std::io::Error> {
+    let files = instance_files(instances_dirs, recursive)?;
+
+    #[cfg(not(feature = "parallel-load"))]
+    let (loaded, mut errors) = files
+        .iter()
+        .enumerate()
+        .fold(Loaded::default(), |db, file| load(&files, db, file));
+
+    #[cfg(feature = "parallel-load")]
+    let (loaded, mut errors) = {
+        use rayon::prelude::*;
+
+        files
+            .par_iter()
+            .enumerate()
+            .fold(Loaded::default, |db, file| load(&files, db, file))
+            .reduce(Loaded::default, |db, other| merge(&files, db, other))
+    };
+
+    let mut sources = Sources::new();
+    let db: Instances = loaded
+        .into_iter()
+        .map(|(name, (i, instance))| {
+            sources.insert(name.clone(), source_path(&files[i]));
+            (name, instance)
+        })
+        .collect();
+    errors.sort_by_key(|(i, _)| *i);
+    let errors = errors
+        .into_iter()
+        .map(|(i, err)| (files[i].clone(), err))
+        .collect();
+
+    tracing::info!("read {} instances", db.len());
+
+    Ok((db, sources, errors))
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct Bks {
     pub routes: usize,
     #[serde_as(as = "DisplayFromStr")]
+    #[schema(value_type = String)]
     pub distance: rug::Float,
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde(serialize_with = "serialize_date")]
     pub date: NaiveDate,
     pub solution: Option<Solution>,
-    // who
+    pub author: Option<String>,
+}
+
+fn serialize_date<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&date.format("%Y-%m-%d").to_string())
+}
+
+impl Bks {
+    pub fn history_line(&self, instance_name: &str) -> String {
+        let line = format!(
+            "{}  {}  routes={}  dist={:.3}",
+            self.date.format("%Y-%m-%d"),
+            instance_name,
+            self.routes,
+            self.distance.to_f64()
+        );
+        match &self.author {
+            None => line,
+            Some(author) => format!("{line}  author={author}"),
+        }
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        [
+            self.date.format("%Y-%m-%d").to_string(),
+            self.routes.to_string(),
+            self.distance.to_string(),
+        ]
+        .map(|f| csv_field(&f))
+        .join(",")
+    }
+}
+
+pub fn is_dominated(a: &Bks, b: &Bks) -> bool {
+    b.routes <= a.routes
+        && b.distance <= a.distance
+        && (b.routes < a.routes || b.distance < a.distance)
+}
+
+// distances differing by less than the tolerance are the same, so rounding in
+// the last decimal places does not produce new best known solutions
+pub fn same_distance(a: &rug::Float, b: &rug::Float, tolerance: f64) -> bool {
+    (a.clone() - b).abs() <= tolerance
+}
+
+pub fn pareto_front(history: &[Bks]) -> Vec<Bks> {
+    let mut front: Vec<Bks> = history
+        .iter()
+        .filter(|a| !history.iter().any(|b| is_dominated(a, b)))
+        .cloned()
+        .collect();
+
+    front.sort_by(|a, b| {
+        a.routes
+            .cmp(&b.routes)
+            .then(a.distance.total_cmp(&b.distance))
+    });
+    front.dedup_by(|a, b| a.routes == b.routes && a.distance == b.distance);
+    front
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub routes: usize,
+    #[serde_as(as = "DisplayFromStr")]
+    #[schema(value_type = String)]
+    pub distance: rug::Float,
+    #[serde(serialize_with = "serialize_date")]
+    pub date: NaiveDate,
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InstanceLeader {
+    pub instance: String,
+    #[serde(flatten)]
+    pub best: LeaderboardEntry,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FamilyStats {
+    #[schema(value_type = String)]
+    pub family: InstanceFamily,
+    pub count: usize,
+    pub avg_customers: f64,
+    pub avg_vehicles: f64,
+    pub instances_with_bks: usize,
+    pub bks_coverage_pct: f64,
+}
+
+// distinct solutions ranked by routes and distance, of equally good ones only
+// the earliest submission is kept
+pub fn leaderboard(history: &[Bks], n: usize) -> Vec<LeaderboardEntry> {
+    let mut ranked: Vec<&Bks> = history.iter().collect();
+    ranked.sort_by(|a, b| {
+        a.routes
+            .cmp(&b.routes)
+            .then(a.distance.total_cmp(&b.distance))
+            .then(a.date.cmp(&b.date))
+    });
+    ranked.dedup_by(|a, b| a.routes == b.routes && a.distance == b.distance);
+
+    ranked
+        .into_iter()
+        .take(n)
+        .enumerate()
+        .map(|(i, b)| LeaderboardEntry {
+            rank: i + 1,
+            routes: b.routes,
+            distance: b.distance.clone(),
+            date: b.date,
+            author: b.author.clone(),
+        })
+        .collect()
 }
 
-type BksDb = HashMap<String, Vec<Bks>>;
+pub type BksDb = HashMap<String, Vec<Bks>>;
 
 pub fn read_bks(instances: &Instances, bks_dir: &Option<PathBuf>) -> Result<BksDb, std::io::Error> {
     let mut bks: HashMap<String, Vec<Bks>> = HashMap::new();
@@ -56,13 +307,22 @@ pub fn read_bks(instances: &Instances, bks_dir: &Option<PathBuf>) -> Result<BksD
             .filter_map(|e| e.ok())
             .filter(|f| f.file_type().is_file())
         {
-            let date = get_date_from_parent_dir(&b);
-            let (name, best) = create_bks(&b, instances, date);
-            (*bks.entry(name).or_insert(vec![])).push(best);
+            let relative = b.path().strip_prefix(bks_dir).unwrap_or(b.path());
+            let date = date_from_dirs(relative).unwrap_or_else(|| {
+                tracing::warn!(
+                    "{}: not in a YYYY-MM-DD directory, dated 1970-01-01",
+                    b.path().display()
+                );
+                NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+            });
+            match create_bks(&b, instances, date) {
+                Ok((name, best)) => (*bks.entry(name).or_insert(vec![])).push(best),
+                Err(err) => tracing::warn!("{}: {err}, skipping", b.path().display()),
+            }
         }
     }
 
-    println!("read {} bks", bks.len());
+    tracing::info!("read {} bks", bks.len());
 
     // for (name, b) in bks.iter() {
     //     let bl = b.last().unwrap();
@@ -72,99 +332,728 @@ pub fn read_bks(instances: &Instances, bks_dir: &Option<PathBuf>) -> Result<BksD
     Ok(bks)
 }
 
-fn create_bks(b: &walkdir::DirEntry, instances: &Instances, date: NaiveDate) -> (String, Bks) {
-    let empty_file = fs::metadata(b.path()).unwrap().len() == 0;
+fn create_bks(
+    b: &walkdir::DirEntry,
+    instances: &Instances,
+    date: NaiveDate,
+) -> std::io::Result<(String, Bks)> {
+    let empty_file = fs::metadata(b.path())?.len() == 0;
 
     let (name, routes, distance, solution) = if empty_file {
-        extract_from_file_name(&b)
+        let file_name = b.file_name().to_string_lossy();
+        extract_from_file_name(&file_name)?
     } else {
-        calculate(&b, instances)
+        calculate(b, instances)?
     };
 
-    (
+    Ok((
         name,
         Bks {
             routes,
             distance,
             date,
             solution,
+            author: None,
         },
-    )
+    ))
 }
 
 fn calculate(
     b: &walkdir::DirEntry,
     instances: &Instances,
-) -> (String, usize, rug::Float, Option<Solution>) {
-    let sol = read::<Solution>(b.path()).unwrap();
-    let inst = instances.get(&sol.instance_name).unwrap();
+) -> std::io::Result<(String, usize, rug::Float, Option<Solution>)> {
+    // the solution was accepted before, possibly with --lenient-names
+    let sol = Solution::from_str_lenient(&fs::read_to_string(b.path())?)
+        .map_err(|err| std::io::Error::other(err.to_string()))?;
+    let inst = instances.get(&sol.instance_name).ok_or_else(|| {
+        std::io::Error::other(format!("no such instance: `{}'", sol.instance_name))
+    })?;
+    let distance = verify(inst, &sol)
+        .map_err(|err| std::io::Error::other(err.to_string()))?
+        .total_distance;
 
-    (
+    Ok((
         sol.instance_name.clone(),
         sol.routes.len(),
-        verify(&inst, &sol).unwrap(),
+        distance,
         Some(sol),
-    )
-}
-
-fn extract_from_file_name(b: &walkdir::DirEntry) -> (String, usize, rug::Float, Option<Solution>) {
-    let (inst, rest) = b
-        .path()
-        .file_name()
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .split_once('.')
-        .unwrap();
-
-    let (routes_quality, _) = rest.rsplit_once('.').unwrap();
-    let (routes, quality) = routes_quality.split_once('_').unwrap();
-
-    (
-        inst.to_string(),
-        routes.parse::<usize>().unwrap(),
-        flf64(quality.parse::<f64>().unwrap()),
-        None,
-    )
-}
-
-fn get_date_from_parent_dir(b: &walkdir::DirEntry) -> NaiveDate {
-    NaiveDate::from_str(
-        b.path()
-            .parent()
-            .unwrap()
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap(),
-    )
-    .unwrap()
+    ))
+}
+
+// empty files only have the name `{instance}.{routes}_{distance}.txt'
+fn extract_from_file_name(
+    file_name: &str,
+) -> std::io::Result<(String, usize, rug::Float, Option<Solution>)> {
+    let malformed = || {
+        std::io::Error::other(format!(
+            "`{file_name}' does not look like `{{instance}}.{{routes}}_{{distance}}.txt'"
+        ))
+    };
+    let (inst, rest) = file_name.split_once('.').ok_or_else(malformed)?;
+    let (routes_quality, _) = rest.rsplit_once('.').ok_or_else(malformed)?;
+    let (routes, quality) = routes_quality.split_once('_').ok_or_else(malformed)?;
+
+    let routes = routes
+        .parse::<usize>()
+        .map_err(|err| std::io::Error::other(format!("number of routes `{routes}': {err}")))?;
+    let quality = quality
+        .parse::<f64>()
+        .map_err(|err| std::io::Error::other(format!("distance `{quality}': {err}")))?;
+
+    Ok((inst.to_string(), routes, flf64(quality), None))
+}
+
+// solutions are kept in `{date}/` directories, which may be nested in others,
+// e.g. `2024/2024-01-15/`; the closest one is the date of the solution
+fn date_from_dirs(path: &Path) -> Option<NaiveDate> {
+    path.ancestors()
+        .skip(1)
+        .filter_map(|dir| dir.file_name()?.to_str())
+        .find_map(|name| NaiveDate::parse_from_str(name, "%Y-%m-%d").ok())
+}
+
+// events a client can fall behind before it starts missing them
+const EVENTS_CAPACITY: usize = 64;
+
+// checked solutions remembered per instance, the oldest are forgotten first
+const VERIFIED_CAPACITY: usize = 1024;
+
+// the routes in their order and the vehicles serving them, both can change the
+// distance or whether the solution is feasible
+type CheckedRoutes = (Vec<Vec<usize>>, Option<Vec<usize>>);
+
+fn checked_routes(sol: &Solution) -> CheckedRoutes {
+    (sol.routes.clone(), sol.vehicle_assignments.clone())
+}
+
+#[derive(Default)]
+struct Verified {
+    results: HashMap<CheckedRoutes, Result<rug::Float, String>>,
+    order: VecDeque<CheckedRoutes>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    // solutions with the same number of routes and distances closer than
+    // this are equal
+    pub bks_tolerance: f64,
+    // solutions may name any instance, not only the standard benchmarks
+    pub lenient_names: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            bks_tolerance: 0.001,
+            lenient_names: false,
+        }
+    }
 }
 
-#[derive(Clone)]
 pub struct Db {
     instances: Instances,
+    sources: Sources,
     bks: BksDb,
+    store: Arc<dyn BksStore>,
+    deleted: HashSet<String>,
+    seen: HashMap<String, HashSet<u64>>,
+    verified: HashMap<String, Verified>,
+    events: broadcast::Sender<Event>,
+    loaded: bool,
+    load_errors: LoadErrors,
+    settings: Settings,
+    // checks that were verified, not answered from the cache
+    verifications: AtomicU64,
+    started: Instant,
 }
 
 impl Db {
     pub fn instance(&self, name: &String) -> Result<&Instance, String> {
         match self.instances.get(name) {
             None => Err(format!("No such instance: `{}'", name)),
-            Some(instance) => Ok(&instance),
+            Some(instance) => Ok(instance),
+        }
+    }
+
+    pub fn add_instance(&mut self, name: String, instance: Instance) -> Result<(), String> {
+        if self.instances.contains_key(&name) {
+            return Err(format!("Instance `{}' already exists", name));
+        }
+        self.deleted.remove(&name);
+        self.instances.insert(name, instance);
+        Ok(())
+    }
+
+    pub fn replace_instance(&mut self, name: &String, instance: Instance) -> Result<(), String> {
+        match self.instances.get_mut(name) {
+            None => Err(format!("No such instance: `{}'", name)),
+            Some(old) => {
+                *old = instance;
+                self.seen.remove(name);
+                self.verified.remove(name);
+                Ok(())
+            }
+        }
+    }
+
+    // whether changes of the file reach its instance, a file shadowed by one
+    // with the same name that was loaded first does not
+    pub fn is_source(&self, name: &str, path: &Path) -> bool {
+        self.sources
+            .get(name)
+            .is_none_or(|source| *source == source_path(path))
+    }
+
+    // instances changed on disk replace the ones in memory, even deleted ones
+    pub fn reload_instance(&mut self, name: String, path: &Path, instance: Instance) {
+        self.sources.insert(name.clone(), source_path(path));
+        self.deleted.remove(&name);
+        // solutions of the previous version were checked against other data
+        self.seen.remove(&name);
+        self.verified.remove(&name);
+        let change = match self.instances.insert(name.clone(), instance) {
+            None => "added",
+            Some(_) => "updated",
+        };
+        let _ = self.events.send(Event::Instance {
+            instance: name,
+            change,
+        });
+    }
+
+    pub fn remove_instance(&mut self, name: &String) -> Result<(), String> {
+        match self.instances.remove(name) {
+            None => Err(format!("No such instance: `{}'", name)),
+            Some(_) => {
+                self.bks.remove(name);
+                self.seen.remove(name);
+                self.verified.remove(name);
+                self.deleted.insert(name.clone());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn bks_tolerance(&self) -> f64 {
+        self.settings.bks_tolerance
+    }
+
+    pub fn parse_solution(&self, text: &str) -> Result<Solution, String> {
+        if self.settings.lenient_names {
+            Solution::from_str_lenient(text)
+        } else {
+            Solution::from_str(text)
+        }
+        .map_err(|err| err.to_string())
+    }
+
+    pub fn pareto(&self, name: &String) -> Vec<Bks> {
+        self.bks
+            .get(name)
+            .map(|history| pareto_front(history))
+            .unwrap_or_default()
+    }
+
+    pub fn leaderboard(&self, name: &String, n: usize) -> Result<Vec<LeaderboardEntry>, String> {
+        self.instance(name)?;
+        Ok(self
+            .bks
+            .get(name)
+            .map(|history| leaderboard(history, n))
+            .unwrap_or_default())
+    }
+
+    pub fn leaders(&self, family: Option<InstanceFamily>) -> Vec<InstanceLeader> {
+        let mut leaders: Vec<InstanceLeader> = self
+            .bks
+            .iter()
+            .filter(|(name, _)| self.in_family(name, family))
+            .filter_map(|(name, history)| {
+                leaderboard(history, 1).pop().map(|best| InstanceLeader {
+                    instance: name.clone(),
+                    best,
+                })
+            })
+            .collect();
+        leaders.sort_by(|a, b| a.instance.cmp(&b.instance));
+        leaders
+    }
+
+    pub fn record_bks(&mut self, name: &str, solution_text: &str, bks: Bks) -> std::io::Result<()> {
+        self.store.save(name, solution_text, &bks)?;
+        // there is nobody to notify when no client listens
+        let _ = self.events.send(Event::bks(name, &bks));
+        self.bks.entry(name.to_string()).or_default().push(bks);
+        Ok(())
+    }
+
+    // returns whether a solution with this hash was not seen before
+    pub fn record_solution(&mut self, name: &str, hash: u64) -> bool {
+        self.seen.entry(name.to_string()).or_default().insert(hash)
+    }
+
+    // the distance (or the error) of the same solution checked before
+    pub fn verified(&self, name: &str, sol: &Solution) -> Option<&Result<rug::Float, String>> {
+        self.verified.get(name)?.results.get(&checked_routes(sol))
+    }
+
+    pub fn record_verified(
+        &mut self,
+        name: &str,
+        sol: &Solution,
+        result: Result<rug::Float, String>,
+    ) {
+        if !self.instances.contains_key(name) {
+            return;
         }
+        let verified = self.verified.entry(name.to_string()).or_default();
+        let key = checked_routes(sol);
+        if verified.results.insert(key.clone(), result).is_none() {
+            verified.order.push_back(key);
+        }
+        if verified.order.len() > VERIFIED_CAPACITY {
+            if let Some(oldest) = verified.order.pop_front() {
+                verified.results.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    pub fn was_deleted(&self, name: &String) -> bool {
+        self.deleted.contains(name)
+    }
+
+    // every instance is in the family None
+    fn in_family(&self, name: &String, family: Option<InstanceFamily>) -> bool {
+        family.is_none_or(|f| self.instances.get(name).and_then(Instance::family) == Some(f))
+    }
+
+    pub fn instance_names(
+        &self,
+        prefix: Option<&str>,
+        family: Option<InstanceFamily>,
+    ) -> Vec<&String> {
+        let mut names: Vec<&String> = self
+            .instances
+            .keys()
+            .filter(|name| prefix.is_none_or(|p| name.starts_with(p)))
+            .filter(|name| self.in_family(name, family))
+            .collect();
+        names.sort();
+        names
     }
 
     pub fn bks(&self, name: &String) -> Result<&Vec<Bks>, String> {
         match self.bks.get(name) {
             None => Err(format!("No such instance: `{}'", name)),
-            Some(b) => Ok(&b),
+            Some(b) => Ok(b),
+        }
+    }
+
+    pub fn new(
+        instances_dirs: &[PathBuf],
+        recursive: bool,
+        store: Arc<dyn BksStore>,
+        settings: Settings,
+    ) -> std::io::Result<Self> {
+        let (instances, sources, load_errors) = read_instances(instances_dirs, recursive)?;
+        let bks = store.load(&instances)?;
+        Ok(Self {
+            instances,
+            sources,
+            bks,
+            store,
+            deleted: HashSet::new(),
+            seen: HashMap::new(),
+            verified: HashMap::new(),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            loaded: true,
+            load_errors,
+            settings,
+            verifications: AtomicU64::new(0),
+            started: Instant::now(),
+        })
+    }
+
+    pub fn empty(store: Arc<dyn BksStore>, settings: Settings) -> Self {
+        Self {
+            instances: Instances::new(),
+            sources: Sources::new(),
+            bks: BksDb::new(),
+            store,
+            deleted: HashSet::new(),
+            seen: HashMap::new(),
+            verified: HashMap::new(),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            loaded: false,
+            load_errors: LoadErrors::new(),
+            settings,
+            verifications: AtomicU64::new(0),
+            started: Instant::now(),
+        }
+    }
+
+    // instances added or deleted while loading in the background take precedence
+    pub fn absorb(&mut self, loaded: Db) {
+        for (name, source) in loaded.sources {
+            self.sources.entry(name).or_insert(source);
+        }
+        for (name, instance) in loaded.instances {
+            if !self.deleted.contains(&name) {
+                self.instances.entry(name).or_insert(instance);
+            }
+        }
+        for (name, mut history) in loaded.bks {
+            if self.instances.contains_key(&name) {
+                let entry = self.bks.entry(name).or_default();
+                history.append(entry);
+                *entry = history;
+            }
+        }
+        self.load_errors = loaded.load_errors;
+        self.loaded = true;
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    pub fn load_errors(&self) -> &[(PathBuf, String)] {
+        &self.load_errors
+    }
+
+    pub fn instances_loaded(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn bks_loaded(&self) -> usize {
+        self.bks.len()
+    }
+
+    pub fn bks_entries(&self) -> usize {
+        self.bks.values().map(Vec::len).sum()
+    }
+
+    pub fn count_verification(&self) {
+        self.verifications.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn verifications(&self) -> u64 {
+        self.verifications.load(Ordering::Relaxed)
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // instances without a recognized name are in the Unknown family
+    pub fn family_stats(&self) -> Vec<FamilyStats> {
+        let mut families: HashMap<InstanceFamily, Vec<(&String, &Instance)>> = HashMap::new();
+        for (name, inst) in self.instances.iter() {
+            let family = inst.family().unwrap_or(InstanceFamily::Unknown);
+            families.entry(family).or_default().push((name, inst));
+        }
+
+        let mut stats: Vec<FamilyStats> = families
+            .into_iter()
+            .map(|(family, instances)| {
+                let count = instances.len();
+                let avg = |f: &dyn Fn(&Instance) -> usize| {
+                    instances.iter().map(|(_, inst)| f(inst)).sum::<usize>() as f64 / count as f64
+                };
+                let instances_with_bks = instances
+                    .iter()
+                    .filter(|(name, _)| self.bks.get(*name).is_some_and(|h| !h.is_empty()))
+                    .count();
+                FamilyStats {
+                    family,
+                    count,
+                    avg_customers: avg(&|inst| inst.pts.len() - inst.depots().len()),
+                    avg_vehicles: avg(&|inst| inst.vehicles as usize),
+                    instances_with_bks,
+                    bks_coverage_pct: 100.0 * instances_with_bks as f64 / count as f64,
+                }
+            })
+            .collect();
+        stats.sort_by_key(|s| s.family.to_string());
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bks(routes: usize, distance: f64) -> Bks {
+        Bks {
+            routes,
+            distance: flf64(distance),
+            date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            solution: None,
+            author: None,
+        }
+    }
+
+    #[test]
+    fn domination() {
+        assert!(is_dominated(&bks(10, 100.0), &bks(9, 100.0)));
+        assert!(is_dominated(&bks(10, 100.0), &bks(10, 99.0)));
+        assert!(!is_dominated(&bks(10, 100.0), &bks(10, 100.0)));
+        assert!(!is_dominated(&bks(10, 100.0), &bks(9, 101.0)));
+        assert!(!is_dominated(&bks(9, 101.0), &bks(10, 100.0)));
+    }
+
+    #[test]
+    fn bks_file_names() {
+        let (name, routes, distance, solution) =
+            extract_from_file_name("c1_2_1.20_2704.57.txt").unwrap();
+        assert_eq!(
+            (name.as_str(), routes, distance.to_f64(), solution),
+            ("c1_2_1", 20, 2704.57, None)
+        );
+
+        for malformed in [
+            "c1_2_1",
+            "c1_2_1.txt",
+            "c1_2_1.20.txt",
+            "c1_2_1.x_1.0.txt",
+            "c1_2_1.20_x.txt",
+        ] {
+            assert!(extract_from_file_name(malformed).is_err(), "{malformed}");
         }
     }
 
-    pub fn new(instances_dir: &PathBuf, bks_dir: &Option<PathBuf>) -> std::io::Result<Self> {
-        let instances = read_instances(instances_dir)?;
-        let bks = read_bks(&instances, bks_dir)?;
-        Ok(Self { instances, bks })
+    #[test]
+    fn bks_dates() {
+        let date = |path: &str| date_from_dirs(Path::new(path));
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15);
+        assert_eq!(date("2024-01-15/c1_2_1.txt"), day);
+        assert_eq!(date("2024/2024-01-15/c1_2_1.txt"), day);
+        assert_eq!(date("2024-01-15/extra/c1_2_1.txt"), day);
+        assert_eq!(date("2024/c1_2_1.txt"), None);
+        assert_eq!(date("c1_2_1.txt"), None);
+    }
+
+    #[test]
+    fn first_duplicate_is_kept() {
+        let files = [PathBuf::from("a/c101.txt"), PathBuf::from("b/c101.txt")];
+        let instance = |seed| {
+            verifier::generator::InstanceGenerator {
+                seed,
+                ..Default::default()
+            }
+            .generate()
+            .unwrap()
+        };
+
+        let mut db = Loaded::default();
+        keep_first(&files, &mut db, "c101.txt".to_string(), 1, instance(2));
+        keep_first(&files, &mut db, "c101.txt".to_string(), 0, instance(1));
+        assert_eq!(db.0["c101.txt"], (0, instance(1)));
+    }
+
+    #[test]
+    fn distances_within_tolerance() {
+        assert!(same_distance(&flf64(100.0), &flf64(100.0005), 0.001));
+        assert!(same_distance(&flf64(100.0005), &flf64(100.0), 0.001));
+        assert!(!same_distance(&flf64(100.0), &flf64(100.002), 0.001));
+        assert!(!same_distance(&flf64(100.0), &flf64(100.0005), 0.0));
+    }
+
+    #[test]
+    fn front_is_sorted_by_routes() {
+        let history = vec![
+            bks(11, 90.0),
+            bks(10, 100.0),
+            bks(10, 95.0),
+            bks(12, 95.0),
+            bks(9, 120.0),
+            bks(10, 95.0),
+        ];
+
+        let front: Vec<(usize, f64)> = pareto_front(&history)
+            .iter()
+            .map(|b| (b.routes, b.distance.to_f64()))
+            .collect();
+
+        assert_eq!(front, vec![(9, 120.0), (10, 95.0), (11, 90.0)]);
+    }
+
+    #[test]
+    fn leaderboard_ranks_distinct_solutions() {
+        let on = |day, b: Bks| Bks {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            author: Some(format!("author{day}")),
+            ..b
+        };
+        let history = vec![
+            on(5, bks(10, 95.0)),
+            on(1, bks(11, 90.0)),
+            on(3, bks(10, 95.0)),
+            on(2, bks(10, 100.0)),
+            on(4, bks(9, 120.0)),
+        ];
+
+        let board: Vec<(usize, usize, f64, Option<String>)> = leaderboard(&history, 3)
+            .into_iter()
+            .map(|e| (e.rank, e.routes, e.distance.to_f64(), e.author))
+            .collect();
+
+        assert_eq!(
+            board,
+            vec![
+                (1, 9, 120.0, Some("author4".to_string())),
+                (2, 10, 95.0, Some("author3".to_string())),
+                (3, 10, 100.0, Some("author2".to_string())),
+            ]
+        );
+        assert_eq!(leaderboard(&history, 10).len(), 4);
+    }
+
+    #[test]
+    fn history_formatting() {
+        let b = Bks {
+            routes: 12,
+            distance: flf64(1234.5671),
+            ..bks(0, 0.0)
+        };
+
+        assert_eq!(
+            b.history_line("rc1_4_10"),
+            "2024-01-15  rc1_4_10  routes=12  dist=1234.567"
+        );
+        assert_eq!(
+            serde_json::to_value(&b).unwrap()["date"],
+            serde_json::json!("2024-01-15")
+        );
+        assert_eq!(
+            Bks {
+                distance: flf64(10.5),
+                ..b
+            }
+            .to_csv_row(),
+            format!("2024-01-15,12,{}", flf64(10.5))
+        );
+    }
+
+    #[test]
+    fn statistics_of_families() {
+        let store = Arc::new(crate::store::FileStore { dir: None });
+        let mut db = Db::empty(store, Settings::default());
+        let instance = |name: &str, num_customers| {
+            let mut inst = verifier::generator::InstanceGenerator {
+                num_customers,
+                ..Default::default()
+            }
+            .generate()
+            .unwrap();
+            inst.name = name.to_string();
+            inst
+        };
+        for (name, customers) in [("c101", 4), ("c102", 6), ("r201", 5), ("mine", 3)] {
+            db.add_instance(name.to_string(), instance(name, customers))
+                .unwrap();
+        }
+        db.bks.insert("c102".to_string(), vec![bks(1, 10.0)]);
+
+        let stats: Vec<_> = db
+            .family_stats()
+            .into_iter()
+            .map(|s| {
+                (
+                    s.family.to_string(),
+                    s.count,
+                    s.avg_customers,
+                    s.instances_with_bks,
+                )
+            })
+            .collect();
+        assert_eq!(
+            stats,
+            vec![
+                ("C1".to_string(), 2, 5.0, 1),
+                ("R2".to_string(), 1, 5.0, 0),
+                ("Unknown".to_string(), 1, 3.0, 0),
+            ]
+        );
+        assert_eq!(db.family_stats()[0].bks_coverage_pct, 50.0);
+        assert_eq!(db.bks_entries(), 1);
+    }
+
+    #[test]
+    fn verified_solutions() {
+        let store = Arc::new(crate::store::FileStore { dir: None });
+        let mut db = Db::empty(store, Settings::default());
+        let inst = verifier::generator::InstanceGenerator::default()
+            .generate()
+            .unwrap();
+        db.add_instance("c101".to_string(), inst).unwrap();
+        let name = "c101".to_string();
+        let sol = |routes: Vec<Vec<usize>>, vehicle_assignments| Solution {
+            routes,
+            vehicle_assignments,
+            ..Default::default()
+        };
+
+        let checked = sol(vec![vec![1], vec![2, 3]], None);
+        db.record_verified(&name, &checked, Ok(flf64(10.0)));
+        assert_eq!(db.verified(&name, &checked), Some(&Ok(flf64(10.0))));
+        assert_eq!(
+            db.verified(&name, &sol(vec![vec![2, 3], vec![1]], None)),
+            None
+        );
+        assert_eq!(
+            db.verified(&name, &sol(vec![vec![1], vec![2, 3]], Some(vec![1, 0]))),
+            None
+        );
+
+        for i in 0..VERIFIED_CAPACITY {
+            db.record_verified(&name, &sol(vec![vec![i + 10]], None), Ok(flf64(1.0)));
+        }
+        assert_eq!(db.verified(&name, &checked), None);
+        assert_eq!(db.verified[&name].results.len(), VERIFIED_CAPACITY);
+    }
+
+    #[test]
+    fn shadowed_files_are_not_reloaded() {
+        let store = Arc::new(crate::store::FileStore { dir: None });
+        let mut db = Db::empty(store, Settings::default());
+        let inst = verifier::generator::InstanceGenerator::default()
+            .generate()
+            .unwrap();
+        let (first, shadowed) = (Path::new("a/c101.txt"), Path::new("b/c101.txt"));
+
+        assert!(db.is_source("c101.txt", shadowed));
+        db.reload_instance("c101.txt".to_string(), first, inst.clone());
+        assert!(db.record_solution("c101.txt", 1));
+        assert!(!db.record_solution("c101.txt", 1));
+        db.reload_instance("c101.txt".to_string(), first, inst);
+        assert!(db.record_solution("c101.txt", 1));
+        assert!(db.is_source("c101.txt", first));
+        assert!(!db.is_source("c101.txt", shadowed));
+        assert!(db.is_source("c102.txt", shadowed));
+    }
+    #[test]
+    fn replaced_instances_forget_solutions() {
+        let store = Arc::new(crate::store::FileStore { dir: None });
+        let mut db = Db::empty(store, Settings::default());
+        let inst = verifier::generator::InstanceGenerator::default()
+            .generate()
+            .unwrap();
+        let name = "c101".to_string();
+        let sol = Solution {
+            routes: vec![vec![1]],
+            ..Default::default()
+        };
+        db.add_instance(name.clone(), inst.clone()).unwrap();
+        db.record_verified(&name, &sol, Ok(flf64(10.0)));
+        assert!(db.record_solution(&name, sol.stable_hash()));
+
+        db.replace_instance(&name, inst).unwrap();
+        assert_eq!(db.verified(&name, &sol), None);
+        assert!(db.record_solution(&name, sol.stable_hash()));
     }
 }
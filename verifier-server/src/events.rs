@@ -0,0 +1,97 @@
+use actix_web::web::Bytes;
+use futures_util::Stream;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::{interval_at, Instant};
+
+use crate::data::Bks;
+
+const HEARTBEAT: Duration = Duration::from_secs(30);
+
+// the variant is the name of the server-sent event, the fields are its data
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum Event {
+    Bks {
+        instance: String,
+        routes: usize,
+        distance: String,
+    },
+    Instance {
+        instance: String,
+        // `added' or `updated'
+        change: &'static str,
+    },
+}
+
+impl Event {
+    pub fn bks(instance: &str, bks: &Bks) -> Self {
+        Event::Bks {
+            instance: instance.to_string(),
+            routes: bks.routes,
+            distance: bks.distance.to_string(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Event::Bks { .. } => "bks",
+            Event::Instance { .. } => "instance",
+        }
+    }
+
+    pub fn to_sse(&self) -> String {
+        format!(
+            "event: {}\ndata: {}\n\n",
+            self.name(),
+            serde_json::to_string(self).unwrap()
+        )
+    }
+}
+
+// events as they are broadcast, with a heartbeat keeping idle connections open
+pub fn stream(rx: Receiver<Event>) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let heartbeat = interval_at(Instant::now() + HEARTBEAT, HEARTBEAT);
+    futures_util::stream::unfold((rx, heartbeat), |(mut rx, mut heartbeat)| async move {
+        let text = loop {
+            tokio::select! {
+                _ = heartbeat.tick() => break "event: heartbeat\ndata: {}\n\n".to_string(),
+                event = rx.recv() => match event {
+                    Ok(event) => break event.to_sse(),
+                    // a slow client misses some events rather than the whole stream
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                },
+            }
+        };
+        Some((Ok(Bytes::from(text)), (rx, heartbeat)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use verifier::flf64;
+
+    #[test]
+    fn bks_event_format() {
+        let bks = Bks {
+            routes: 10,
+            distance: flf64(95.5),
+            date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            solution: None,
+            author: None,
+        };
+
+        assert_eq!(
+            Event::bks("c1_2_1", &bks).to_sse(),
+            format!(
+                "event: bks\ndata: {{\"instance\":\"c1_2_1\",\"routes\":10,\"distance\":\"{}\"}}\n\n",
+                flf64(95.5)
+            )
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::CONTENT_LENGTH;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+use clap::ValueEnum;
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+pub fn init(format: LogFormat) {
+    let builder = tracing_subscriber::fmt().with_target(false);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().flatten_event(true).init(),
+    }
+}
+
+// attached by the check endpoints to their responses, so that the logger can
+// report what has been checked
+struct CheckOutcome {
+    instance_name: String,
+    ok: bool,
+}
+
+pub fn with_outcome(mut resp: HttpResponse, instance_name: &str, ok: bool) -> HttpResponse {
+    resp.extensions_mut().insert(CheckOutcome {
+        instance_name: instance_name.to_string(),
+        ok,
+    });
+    resp
+}
+
+pub async fn log_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let request_size = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok()?.parse::<u64>().ok())
+        .unwrap_or(0);
+
+    let res = next.call(req).await?;
+
+    let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let status_code = res.status().as_u16();
+    let response_size = match res.response().body().size() {
+        BodySize::Sized(size) => size,
+        BodySize::None | BodySize::Stream => 0,
+    };
+
+    match res.response().extensions().get::<CheckOutcome>() {
+        Some(outcome) => tracing::info!(
+            method,
+            path,
+            status_code,
+            duration_ms,
+            request_size_bytes = request_size,
+            response_size_bytes = response_size,
+            instance_name = outcome.instance_name,
+            result = if outcome.ok { "ok" } else { "error" },
+            "request"
+        ),
+        None => tracing::info!(
+            method,
+            path,
+            status_code,
+            duration_ms,
+            request_size_bytes = request_size,
+            response_size_bytes = response_size,
+            "request"
+        ),
+    }
+
+    Ok(res)
+}
@@ -1,24 +1,88 @@
-use actix_web::http::header::ContentType;
-use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use actix_cors::Cors;
+use actix_web::dev::ServiceResponse;
+use actix_web::http::header::{ContentType, AUTHORIZATION, CACHE_CONTROL, CONTENT_TYPE};
+use actix_web::http::StatusCode;
+use actix_web::middleware::{from_fn, Condition, ErrorHandlerResponse, ErrorHandlers};
+use actix_web::{
+    delete, get, post, put, web, App, HttpRequest, HttpResponse, HttpServer, Responder,
+};
 use clap::Parser;
-use rug;
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::fmt::Display;
 use std::ops::Sub;
 use std::path::PathBuf;
 use std::str::FromStr;
-use verifier::instance::flf64;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use verifier::instance::InstanceStats;
+use verifier::instance::{DistanceMode, Instance, InstanceFamily, Precision};
 use verifier::solution::Solution;
-use verifier::verify::verify;
+use verifier::verify::diff::{solution_diff, SolutionDiff};
+use verifier::verify::{verify, verify_with, RouteResult};
 
 mod data;
-use data::{Bks, Db};
+mod events;
+mod logging;
+mod metrics;
+mod openapi;
+mod ratelimit;
+mod store;
+mod version;
+mod watch;
+mod webhook;
+use data::{
+    is_dominated, same_distance, Bks, Db, FamilyStats, InstanceLeader, LeaderboardEntry, Settings,
+};
+use logging::{with_outcome, LogFormat};
+use metrics::Metrics;
+use ratelimit::RateLimit;
+use store::{BksStore, FileStore, SqliteStore};
+use webhook::{BksImproved, Webhooks};
 
-#[derive(Debug)]
+type SharedDb = web::Data<RwLock<Db>>;
+
+struct AdminToken(Option<String>);
+
+#[derive(Debug, ToSchema)]
 struct Verification {
     instance_name: String,
     routes: usize,
+    #[schema(value_type = String)]
     distance: rug::Float,
+    route_slacks: Option<Vec<RouteSlack>>,
+}
+
+// rug floats have no default, the distance of an empty verification is zero
+impl Default for Verification {
+    fn default() -> Self {
+        Self {
+            instance_name: String::new(),
+            routes: 0,
+            distance: rug::Float::new(Precision::STANDARD.0),
+            route_slacks: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RouteSlack {
+    route_id: usize,
+    slack: Vec<String>,
+    backward_slack: Vec<String>,
+}
+
+impl From<&RouteResult> for RouteSlack {
+    fn from(r: &RouteResult) -> Self {
+        let strings = |v: &[rug::Float]| v.iter().map(|s| s.to_string()).collect();
+        RouteSlack {
+            route_id: r.route_id,
+            slack: strings(&r.slack_times),
+            backward_slack: strings(&r.backward_slack_times),
+        }
+    }
 }
 
 impl Serialize for Verification {
@@ -26,32 +90,46 @@ impl Serialize for Verification {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("Verification", 3)?;
+        let mut state = serializer.serialize_struct("Verification", 4)?;
         state.serialize_field("instance_name", &self.instance_name)?;
         state.serialize_field("routes", &self.routes)?;
         state.serialize_field("distance", &self.distance.to_string())?;
+        if let Some(route_slacks) = &self.route_slacks {
+            state.serialize_field("route_slacks", route_slacks)?;
+        } else {
+            state.skip_field("route_slacks")?;
+        }
         state.end()
     }
 }
 
-impl ToString for Verification {
-    fn to_string(&self) -> String {
-        format!("{}, {}, {}", self.instance_name, self.routes, self.distance)
+impl Display for Verification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}, {}, {}",
+            self.instance_name, self.routes, self.distance
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, ToSchema)]
 struct VerificationWithComparison {
     verification: Verification,
+    // `better than', `equal to' or `ok'
+    #[schema(value_type = String, rename = "comparision")]
     comparison: Ordering,
     bks: Option<Bks>,
+    // the same solution was checked before, the verification is not repeated
+    duplicate: bool,
 }
 
-impl ToString for VerificationWithComparison {
-    fn to_string(&self) -> String {
-        format!(
+impl Display for VerificationWithComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "{} {} {}",
-            self.verification.to_string(),
+            self.verification,
             format_comparison(self.comparison),
             match &self.bks {
                 None => "None".to_string(),
@@ -60,7 +138,7 @@ impl ToString for VerificationWithComparison {
                     "{} {} {}",
                     b.routes,
                     b.distance,
-                    b.date.to_string(),
+                    b.date,
                     // match &b.solution {
                     //     None => "None".to_string(),
                     //     Some(sol) => sol.to_string(),
@@ -85,56 +163,122 @@ impl Serialize for VerificationWithComparison {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("VerificationWithComparison", 3)?;
+        let mut state = serializer.serialize_struct("VerificationWithComparison", 4)?;
         state.serialize_field("verification", &self.verification)?;
         state.serialize_field("comparision", &format_comparison(self.comparison))?;
         state.serialize_field("bks", &self.bks)?;
+        state.serialize_field("duplicate", &self.duplicate)?;
         state.end()
     }
 }
 
-fn check(db: &web::Data<Db>, sol: &Solution) -> Result<VerificationWithComparison, String> {
+#[derive(Deserialize, ToSchema)]
+struct CheckRequest {
+    #[serde(flatten)]
+    solution: Solution,
+    distance_mode: Option<DistanceMode>,
+    precision: Option<Precision>,
+    // adds the slacks of every route to the response
+    #[serde(default)]
+    verbose: bool,
+}
+
+fn check(
+    db: &Db,
+    sol: &Solution,
+    mode: Option<DistanceMode>,
+    prec: Option<Precision>,
+    verbose: bool,
+) -> Result<VerificationWithComparison, String> {
     let inst = db.instance(&sol.instance_name)?;
-    let best = db.bks(&sol.instance_name).map(|bs| bs.last().cloned())?;
+    let front = db.pareto(&sol.instance_name);
 
-    let verification = verify(inst, &sol).map(|dist| Verification {
-        instance_name: inst.name.clone(),
-        routes: sol.routes.len(),
-        distance: dist,
-    })?;
-
-    Ok(compare(verification, best))
-}
-
-fn compare(verification: Verification, best: Option<Bks>) -> VerificationWithComparison {
-    let ord = match &best {
-        None => Ordering::Less,
-        Some(best) => {
-            match verification.routes.cmp(&best.routes) {
-                Ordering::Equal => {
-                    let diff = best.distance.clone().sub(&verification.distance);
-                    if diff < flf64(-0.001) {
-                        Ordering::Less
-                    } else if diff.abs() < flf64(0.001) {
-                        Ordering::Equal
-                    } else {
-                        Ordering::Greater
-                    }
-                }
-                // Less => Less,
-                // Greater => Greater,
-                less_or_greater => less_or_greater,
-            }
-        }
+    let mode = mode.unwrap_or(inst.distance_mode());
+    db.count_verification();
+    let verification = verify_with(inst, sol, mode, prec.unwrap_or_default())
+        .map(|report| Verification {
+            instance_name: inst.name.clone(),
+            routes: sol.routes.len(),
+            distance: report.total_distance,
+            route_slacks: verbose
+                .then(|| report.route_results.iter().map(RouteSlack::from).collect()),
+        })
+        .map_err(|e| e.in_context(sol))?;
+
+    Ok(compare(
+        verification,
+        front,
+        inst.min_vehicles,
+        db.bks_tolerance(),
+    ))
+}
+
+// solutions with fewer routes than the instance requires are infeasible, so
+// they can not make the candidate look worse; a candidate within the tolerance
+// of a solution with the same number of routes is equal to it, it has to be
+// better by more than the tolerance to become a new best known solution
+fn compare(
+    verification: Verification,
+    front: Vec<Bks>,
+    min_vehicles: Option<i32>,
+    tolerance: f64,
+) -> VerificationWithComparison {
+    let front: Vec<Bks> = front
+        .into_iter()
+        .filter(|b| min_vehicles.is_none_or(|min| b.routes >= min as usize))
+        .collect();
+    let candidate = Bks {
+        routes: verification.routes,
+        distance: verification.distance.clone(),
+        date: chrono::Local::now().date_naive(),
+        solution: None,
+        author: None,
+    };
+
+    let ord = if front.iter().any(|b| {
+        b.routes == candidate.routes && same_distance(&b.distance, &candidate.distance, tolerance)
+    }) {
+        Ordering::Equal
+    } else if front.iter().any(|b| is_dominated(&candidate, b)) {
+        Ordering::Greater
+    } else {
+        Ordering::Less
     };
 
     VerificationWithComparison {
         verification,
         comparison: ord,
-        bks: best,
+        bks: front.into_iter().next(),
+        duplicate: false,
     }
 }
 
+// the distance of a solution seen before is compared with the current front,
+// which might have changed in the meantime
+fn check_duplicate(
+    db: &Db,
+    sol: &Solution,
+    distance: rug::Float,
+) -> Result<VerificationWithComparison, String> {
+    let inst = db.instance(&sol.instance_name)?;
+    let verification = Verification {
+        instance_name: inst.name.clone(),
+        routes: sol.routes.len(),
+        distance,
+        route_slacks: None,
+    };
+
+    Ok(VerificationWithComparison {
+        duplicate: true,
+        ..compare(
+            verification,
+            db.pareto(&sol.instance_name),
+            inst.min_vehicles,
+            db.bks_tolerance(),
+        )
+    })
+}
+
 fn resp(resp: Result<String, String>) -> HttpResponse {
     match resp {
         Err(err) => HttpResponse::BadRequest().body(err),
@@ -142,7 +286,7 @@ fn resp(resp: Result<String, String>) -> HttpResponse {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 struct Error {
     err: String,
 }
@@ -158,78 +302,1180 @@ fn resp_json<T: Serialize>(resp: Result<T, String>) -> HttpResponse {
     }
 }
 
+// a check holds the read lock for its whole duration, so an instance can not
+// disappear in the middle of one; a check sent for an instance that has been
+// deleted in the meantime gets 409 Conflict instead of the generic 400
+fn deleted_conflict(db: &Db, name: &String) -> Option<HttpResponse> {
+    db.was_deleted(name)
+        .then(|| HttpResponse::Conflict().body(format!("Instance `{}' has been deleted", name)))
+}
+
+#[utoipa::path(
+    request_body(content = String, description = "solution in the SINTEF format", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "the distance of the solution and how it compares with the best known one", body = String, content_type = "text/plain"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain"),
+        (status = 409, description = "the instance has been deleted", body = String, content_type = "text/plain")
+    )
+)]
 #[post("/check")]
-async fn checker(db: web::Data<Db>, req_body: String) -> impl Responder {
-    match Solution::from_str(&req_body) {
+async fn checker(db: SharedDb, metrics: web::Data<Metrics>, req_body: String) -> impl Responder {
+    let _timer = metrics.verification_duration.start_timer();
+    let db = db.read().unwrap();
+    match db.parse_solution(&req_body) {
         Err(err) => HttpResponse::BadRequest().body(err),
-        Ok(sol) => resp(check(&db, &sol).map(|x| x.to_string())),
+        Ok(sol) => match deleted_conflict(&db, &sol.instance_name) {
+            Some(conflict) => conflict,
+            None => {
+                let result = check(&db, &sol, None, None, false);
+                metrics.verified(&result);
+                let ok = result.is_ok();
+                with_outcome(resp(result.map(|x| x.to_string())), &sol.instance_name, ok)
+            }
+        },
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "the instance in its text format", body = String, content_type = "text/plain"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain")
+    )
+)]
 #[get("/instance/{instance}")]
-async fn get_instance(db: web::Data<Db>, path: web::Path<String>) -> impl Responder {
+async fn get_instance(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
     let name = path.into_inner();
     resp(db.instance(&name).map(|inst| inst.to_string()))
 }
 
+#[serde_with::serde_as]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct InstancesQuery {
+    prefix: Option<String>,
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    family: Option<InstanceFamily>,
+}
+
+#[utoipa::path(
+    params(InstancesQuery),
+    responses(
+        (status = 200, description = "names of the instances, one per line", body = String, content_type = "text/plain")
+    )
+)]
+#[get("/instances")]
+async fn get_instances(db: SharedDb, query: web::Query<InstancesQuery>) -> impl Responder {
+    let db = db.read().unwrap();
+    let names = db.instance_names(query.prefix.as_deref(), query.family);
+    resp(Ok(names
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<String>>()
+        .join("\n")))
+}
+
+#[utoipa::path(
+    params(InstancesQuery),
+    responses(
+        (status = 200, description = "names of the instances", body = Vec<String>)
+    )
+)]
+#[get("/json/instances")]
+async fn get_json_instances(db: SharedDb, query: web::Query<InstancesQuery>) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(Ok(db.instance_names(query.prefix.as_deref(), query.family)))
+}
+
+#[derive(Serialize, ToSchema)]
+struct Count {
+    count: usize,
+}
+
+#[utoipa::path(
+    params(InstancesQuery),
+    responses(
+        (status = 200, description = "the number of instances", body = Count)
+    )
+)]
+#[get("/json/instances/count")]
+async fn get_json_instances_count(
+    db: SharedDb,
+    query: web::Query<InstancesQuery>,
+) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(Ok(Count {
+        count: db
+            .instance_names(query.prefix.as_deref(), query.family)
+            .len(),
+    }))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "every best known solution, one per line", body = String, content_type = "text/plain"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain")
+    )
+)]
 #[get("/history/{instance}")]
-async fn get_bks_history(db: web::Data<Db>, path: web::Path<String>) -> impl Responder {
+async fn get_bks_history(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
     let name = path.into_inner();
     resp(db.bks(&name).map(|bks| {
         bks.iter()
-            .map(|x| format!("{:?}", x))
+            .map(|x| x.history_line(&name))
             .collect::<Vec<String>>()
             .join("\n")
     }))
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "every best known solution", body = String, content_type = "text/csv"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain")
+    )
+)]
+#[get("/csv/history/{instance}")]
+async fn csv_bks_history(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
+    match db.bks(&path.into_inner()) {
+        Err(err) => HttpResponse::BadRequest().body(err),
+        Ok(bks) => HttpResponse::Ok().content_type("text/csv").body(
+            std::iter::once("date,routes,distance".to_string())
+                .chain(bks.iter().map(Bks::to_csv_row))
+                .map(|row| row + "\r\n")
+                .collect::<String>(),
+        ),
+    }
+}
+
+#[utoipa::path(
+    request_body = CheckRequest,
+    responses(
+        (status = 200, description = "the distance of the solution and how it compares with the best known one", body = VerificationWithComparison),
+        (status = 400, description = "the request is invalid", body = Error),
+        (status = 409, description = "the instance has been deleted", body = String, content_type = "text/plain")
+    )
+)]
 #[post("/json/check")]
-async fn json_checker(db: web::Data<Db>, req_body: web::Json<Solution>) -> impl Responder {
-    resp_json(check(&db, &req_body))
+async fn json_checker(
+    db: SharedDb,
+    metrics: web::Data<Metrics>,
+    req_body: web::Json<CheckRequest>,
+) -> impl Responder {
+    let _timer = metrics.verification_duration.start_timer();
+    let sol = &req_body.solution;
+    let name = &sol.instance_name;
+    // only checks with the default settings are remembered
+    let cached =
+        req_body.distance_mode.is_none() && req_body.precision.is_none() && !req_body.verbose;
+
+    let result = {
+        let db = db.read().unwrap();
+        if let Some(conflict) = deleted_conflict(&db, name) {
+            return conflict;
+        }
+
+        if let Some(previous) = db.verified(name, sol).filter(|_| cached) {
+            let result = previous
+                .clone()
+                .and_then(|distance| check_duplicate(&db, sol, distance));
+            let ok = result.is_ok();
+            return with_outcome(resp_json(result), name, ok);
+        }
+
+        check(
+            &db,
+            sol,
+            req_body.distance_mode,
+            req_body.precision,
+            req_body.verbose,
+        )
+    };
+    metrics.verified(&result);
+
+    if cached {
+        db.write().unwrap().record_verified(
+            name,
+            sol,
+            result
+                .as_ref()
+                .map(|r| r.verification.distance.clone())
+                .map_err(Clone::clone),
+        );
+    }
+
+    let ok = result.is_ok();
+    with_outcome(resp_json(result), name, ok)
+}
+
+#[derive(Serialize, ToSchema)]
+struct Uniqueness {
+    hash: u64,
+    unique: bool,
+}
+
+#[utoipa::path(
+    request_body = Solution,
+    responses(
+        (status = 200, description = "whether the solution was seen before", body = Uniqueness),
+        (status = 400, description = "the request is invalid", body = Error),
+        (status = 409, description = "the instance has been deleted", body = String, content_type = "text/plain")
+    )
+)]
+#[post("/json/check-unique")]
+async fn json_check_unique(db: SharedDb, req_body: web::Json<Solution>) -> impl Responder {
+    // hashed before taking the lock so that a panic cannot poison it
+    let hash = req_body.stable_hash();
+    let mut db = db.write().unwrap();
+    let name = &req_body.instance_name;
+    if let Some(conflict) = deleted_conflict(&db, name) {
+        return conflict;
+    }
+    let hash = db.instance(name).map(|_| hash);
+    resp_json(hash.map(|hash| Uniqueness {
+        hash,
+        unique: db.record_solution(name, hash),
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct DiffRequest {
+    solution_a: String,
+    solution_b: String,
+    instance: String,
+}
+
+#[utoipa::path(
+    request_body = DiffRequest,
+    responses(
+        (status = 200, description = "differences between the routes of the solutions", body = SolutionDiff),
+        (status = 400, description = "the request is invalid", body = Error)
+    )
+)]
+#[post("/json/diff")]
+async fn json_diff(db: SharedDb, req_body: web::Json<DiffRequest>) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json((|| {
+        let inst = db.instance(&req_body.instance)?;
+        let sol_a = db.parse_solution(&req_body.solution_a)?;
+        let sol_b = db.parse_solution(&req_body.solution_b)?;
+        Ok(solution_diff(inst, &sol_a, &sol_b))
+    })())
+}
+
+#[derive(Serialize, ToSchema)]
+struct Comparison {
+    a: Verification,
+    b: Verification,
+    #[schema(value_type = String)]
+    winner: &'static str,
+    // b minus a
+    distance_delta: String,
+    routes_delta: i64,
+}
+
+// solutions with fewer routes win, distances within the tolerance are a tie
+fn winner(a: &Verification, b: &Verification, tolerance: f64) -> &'static str {
+    match a.routes.cmp(&b.routes) {
+        Ordering::Less => "a",
+        Ordering::Greater => "b",
+        Ordering::Equal if same_distance(&a.distance, &b.distance, tolerance) => "tie",
+        Ordering::Equal if a.distance < b.distance => "a",
+        Ordering::Equal => "b",
+    }
+}
+
+fn verify_text(db: &Db, inst: &Instance, text: &str) -> Result<Verification, String> {
+    let sol = db.parse_solution(text)?;
+    let report = verify(inst, &sol).map_err(|err| err.in_context(&sol))?;
+    Ok(Verification {
+        instance_name: inst.name.clone(),
+        routes: sol.routes.len(),
+        distance: report.total_distance,
+        route_slacks: None,
+    })
 }
 
+// the body is the same as for /json/diff; when a solution is infeasible the
+// error is returned in its field
+#[utoipa::path(
+    request_body = DiffRequest,
+    responses(
+        (status = 200, description = "both verifications and the better solution", body = Comparison),
+        (status = 400, description = "the errors of the solutions that are infeasible", body = Object)
+    )
+)]
+#[post("/json/compare")]
+async fn json_compare(db: SharedDb, req_body: web::Json<DiffRequest>) -> impl Responder {
+    let db = db.read().unwrap();
+    let inst = match db.instance(&req_body.instance) {
+        Err(err) => return resp_json::<()>(Err(err)),
+        Ok(inst) => inst,
+    };
+
+    match (
+        verify_text(&db, inst, &req_body.solution_a),
+        verify_text(&db, inst, &req_body.solution_b),
+    ) {
+        (Ok(a), Ok(b)) => resp_json(Ok(Comparison {
+            winner: winner(&a, &b, db.bks_tolerance()),
+            distance_delta: b.distance.clone().sub(&a.distance).to_string(),
+            routes_delta: b.routes as i64 - a.routes as i64,
+            a,
+            b,
+        })),
+        (a, b) => {
+            let side = |r: Result<Verification, String>| match r {
+                Ok(v) => serde_json::to_value(v).unwrap(),
+                Err(err) => serde_json::to_value(Error { err }).unwrap(),
+            };
+            HttpResponse::BadRequest()
+                .content_type(ContentType::json())
+                .body(serde_json::json!({ "a": side(a), "b": side(b) }).to_string())
+        }
+    }
+}
+
+// the body is a solution in the text format, the response has the load after
+// every customer of every route
+#[utoipa::path(
+    request_body(content = String, description = "solution in the SINTEF format", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "the load after every customer of every route", body = Vec<Vec<i32>>),
+        (status = 400, description = "the request is invalid", body = Error)
+    )
+)]
+#[get("/json/route-profile/{instance}")]
+async fn json_route_profile(
+    db: SharedDb,
+    path: web::Path<String>,
+    req_body: String,
+) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json((|| {
+        let inst = db.instance(&path.into_inner())?;
+        let sol = db.parse_solution(&req_body)?;
+        let report = verify(inst, &sol).map_err(|err| err.in_context(&sol))?;
+        Ok(report
+            .route_results
+            .into_iter()
+            .map(|r| r.load_profile)
+            .collect::<Vec<_>>())
+    })())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "every best known solution", body = Vec<Bks>),
+        (status = 400, description = "the request is invalid", body = Error)
+    )
+)]
 #[get("/json/history/{instance}")]
-async fn json_bks_history(db: web::Data<Db>, path: web::Path<String>) -> impl Responder {
+async fn json_bks_history(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
     let name = path.into_inner();
     resp_json(db.bks(&name))
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "best known solutions not dominated by another", body = Vec<Bks>),
+        (status = 400, description = "the request is invalid", body = Error)
+    )
+)]
+#[get("/json/pareto/{instance}")]
+async fn json_pareto(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
+    let name = path.into_inner();
+    resp_json(db.instance(&name).map(|_| db.pareto(&name)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LeaderboardQuery {
+    #[serde(default = "default_leaderboard_size")]
+    n: usize,
+}
+
+fn default_leaderboard_size() -> usize {
+    10
+}
+
+#[utoipa::path(
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, description = "the best distinct solutions", body = Vec<LeaderboardEntry>),
+        (status = 400, description = "the request is invalid", body = Error)
+    )
+)]
+#[get("/json/leaderboard/{instance}")]
+async fn json_leaderboard(
+    db: SharedDb,
+    path: web::Path<String>,
+    query: web::Query<LeaderboardQuery>,
+) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(db.leaderboard(&path.into_inner(), query.n))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "the best solution of every instance", body = Vec<InstanceLeader>)
+    )
+)]
+#[get("/json/leaderboard")]
+async fn json_leaders(db: SharedDb) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(Ok(db.leaders(None)))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "the best solution of every instance in the family", body = Vec<InstanceLeader>),
+        (status = 400, description = "the request is invalid", body = Error)
+    )
+)]
+#[get("/json/leaderboard/family/{family}")]
+async fn json_family_leaders(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(
+        InstanceFamily::from_str(&path.into_inner())
+            .map(|family| db.leaders(Some(family)))
+            .map_err(|err| err.to_string()),
+    )
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "the instance", body = Instance),
+        (status = 400, description = "the request is invalid", body = Error)
+    )
+)]
 #[get("/json/instance/{instance}")]
-async fn get_json_instance(db: web::Data<Db>, path: web::Path<String>) -> impl Responder {
+async fn get_json_instance(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
     resp_json(db.instance(&path.into_inner()))
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "statistics of the instance", body = InstanceStats),
+        (status = 400, description = "the request is invalid", body = Error)
+    )
+)]
+#[get("/json/instance/{instance}/stats")]
+async fn get_json_instance_stats(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(db.instance(&path.into_inner()).map(Instance::statistics))
+}
+
+fn svg(svg: Result<String, String>) -> HttpResponse {
+    match svg {
+        Err(err) => HttpResponse::BadRequest().body(err),
+        Ok(svg) => HttpResponse::Ok().content_type("image/svg+xml").body(svg),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "a drawing of the instance", body = String, content_type = "image/svg+xml"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain")
+    )
+)]
+#[get("/svg/instance/{instance}")]
+async fn svg_instance(db: SharedDb, path: web::Path<String>) -> impl Responder {
+    let db = db.read().unwrap();
+    svg(db
+        .instance(&path.into_inner())
+        .map(|inst| inst.to_svg(None)))
+}
+
+#[utoipa::path(
+    request_body(content = String, description = "solution in the SINTEF format", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "a drawing of the solution", body = String, content_type = "image/svg+xml"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain")
+    )
+)]
+#[post("/svg/solution/{instance}")]
+async fn svg_solution(db: SharedDb, path: web::Path<String>, req_body: String) -> impl Responder {
+    let db = db.read().unwrap();
+    svg(db
+        .parse_solution(&req_body)
+        .and_then(|sol| Ok(db.instance(&path.into_inner())?.to_svg(Some(&sol)))))
+}
+
+// the token is compared in constant time, so that the response time does not
+// tell how much of a guess was right
+fn authorized(req: &HttpRequest, token: &AdminToken) -> bool {
+    match &token.0 {
+        None => true,
+        Some(token) => req.headers().get(AUTHORIZATION).is_some_and(|h| {
+            let expected = format!("Bearer {token}");
+            h.as_bytes().ct_eq(expected.as_bytes()).into()
+        }),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AddInstanceQuery {
+    name: Option<String>,
+}
+
+#[utoipa::path(
+    request_body(content = String, description = "instance in the text format", content_type = "text/plain"),
+    params(AddInstanceQuery),
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "the name of the added instance", body = String, content_type = "text/plain"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain"),
+        (status = 401, description = "invalid or missing admin token", body = String, content_type = "text/plain"),
+        (status = 409, description = "an instance with this name exists", body = String, content_type = "text/plain")
+    )
+)]
+#[post("/instance")]
+async fn add_instance(
+    db: SharedDb,
+    token: web::Data<AdminToken>,
+    req: HttpRequest,
+    query: web::Query<AddInstanceQuery>,
+    req_body: String,
+) -> impl Responder {
+    if !authorized(&req, &token) {
+        return HttpResponse::Unauthorized().body("invalid or missing admin token");
+    }
+
+    let inst = match Instance::from_str(&req_body) {
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        Ok(inst) => inst,
+    };
+
+    insert_instance(&db, query.name.clone(), inst)
+}
+
+fn insert_instance(db: &SharedDb, name: Option<String>, inst: Instance) -> HttpResponse {
+    let name = name.unwrap_or(inst.name.clone());
+    if name.is_empty() {
+        return HttpResponse::BadRequest()
+            .body("the instance has no name, pass it with the `name' query parameter");
+    }
+
+    let mut db = db.write().unwrap();
+    match db.add_instance(name.clone(), inst) {
+        Err(err) => HttpResponse::Conflict().body(err),
+        Ok(()) => HttpResponse::Ok().body(name),
+    }
+}
+
+#[utoipa::path(
+    request_body = Instance,
+    params(AddInstanceQuery),
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "the name of the added instance", body = String, content_type = "text/plain"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain"),
+        (status = 401, description = "invalid or missing admin token", body = String, content_type = "text/plain"),
+        (status = 409, description = "an instance with this name exists", body = String, content_type = "text/plain")
+    )
+)]
+#[post("/json/instance")]
+async fn add_json_instance(
+    db: SharedDb,
+    token: web::Data<AdminToken>,
+    req: HttpRequest,
+    query: web::Query<AddInstanceQuery>,
+    req_body: String,
+) -> impl Responder {
+    if !authorized(&req, &token) {
+        return HttpResponse::Unauthorized().body("invalid or missing admin token");
+    }
+
+    match Instance::from_json(&req_body) {
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+        Ok(inst) => insert_instance(&db, query.name.clone(), inst),
+    }
+}
+
+#[serde_with::serde_as]
+#[derive(Deserialize, ToSchema)]
+struct BksSubmission {
+    solution: String,
+    author: String,
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    date: Option<chrono::NaiveDate>,
+}
+
+#[utoipa::path(
+    request_body = BksSubmission,
+    responses(
+        (status = 200, description = "the verification, a better solution is recorded as the best known one", body = VerificationWithComparison),
+        (status = 400, description = "the request is invalid", body = Error),
+        (status = 409, description = "the instance has been deleted", body = String, content_type = "text/plain"),
+        (status = 500, description = "the solution could not be saved", body = Error)
+    )
+)]
+#[post("/json/bks/{instance}")]
+async fn submit_bks(
+    db: SharedDb,
+    metrics: web::Data<Metrics>,
+    webhooks: web::Data<Webhooks>,
+    path: web::Path<String>,
+    req_body: web::Json<BksSubmission>,
+) -> impl Responder {
+    let name = path.into_inner();
+    let parsed = db.read().unwrap().parse_solution(&req_body.solution);
+    let sol = match parsed {
+        Err(err) => return resp_json::<()>(Err(err)),
+        Ok(sol) => sol,
+    };
+
+    if sol.instance_name != name {
+        return resp_json::<()>(Err(format!(
+            "solution is for instance `{}', not `{}'",
+            sol.instance_name, name
+        )));
+    }
+
+    let mut db = db.write().unwrap();
+    if let Some(conflict) = deleted_conflict(&db, &name) {
+        return conflict;
+    }
+
+    let _timer = metrics.verification_duration.start_timer();
+    let result = check(&db, &sol, None, None, false);
+    metrics.verified(&result);
+    let verification = match result {
+        Err(err) => return resp_json::<()>(Err(err)),
+        Ok(verification) => verification,
+    };
+
+    if verification.comparison == Ordering::Less {
+        let bks = Bks {
+            routes: sol.routes.len(),
+            distance: verification.verification.distance.clone(),
+            date: req_body
+                .date
+                .unwrap_or_else(|| chrono::Local::now().date_naive()),
+            solution: Some(sol),
+            author: Some(req_body.author.clone()),
+        };
+        let event = BksImproved::new(&name, verification.bks.as_ref(), &bks);
+
+        if let Err(err) = db.record_bks(&name, &req_body.solution, bks) {
+            return HttpResponse::InternalServerError()
+                .content_type(ContentType::json())
+                .body(
+                    serde_json::to_string(&Error {
+                        err: format!("could not save the bks: {err}"),
+                    })
+                    .unwrap(),
+                );
+        }
+        metrics.bks_improvements.inc();
+        webhooks.notify(event);
+    }
+
+    resp_json(Ok(verification))
+}
+
+#[utoipa::path(
+    request_body(content = String, description = "instance in the text format", content_type = "text/plain"),
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "the name of the replaced instance", body = String, content_type = "text/plain"),
+        (status = 400, description = "the request is invalid", body = String, content_type = "text/plain"),
+        (status = 401, description = "invalid or missing admin token", body = String, content_type = "text/plain"),
+        (status = 404, description = "there is no such instance", body = String, content_type = "text/plain")
+    )
+)]
+#[put("/instance/{name}")]
+async fn replace_instance(
+    db: SharedDb,
+    token: web::Data<AdminToken>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    req_body: String,
+) -> impl Responder {
+    if !authorized(&req, &token) {
+        return HttpResponse::Unauthorized().body("invalid or missing admin token");
+    }
+
+    let inst = match Instance::from_str(&req_body) {
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        Ok(inst) => inst,
+    };
+
+    let name = path.into_inner();
+    let mut db = db.write().unwrap();
+    match db.replace_instance(&name, inst) {
+        Err(err) => HttpResponse::NotFound().body(err),
+        Ok(()) => HttpResponse::Ok().body(name),
+    }
+}
+
+#[utoipa::path(
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "the name of the deleted instance", body = String, content_type = "text/plain"),
+        (status = 401, description = "invalid or missing admin token", body = String, content_type = "text/plain"),
+        (status = 404, description = "there is no such instance", body = String, content_type = "text/plain")
+    )
+)]
+#[delete("/instance/{name}")]
+async fn delete_instance(
+    db: SharedDb,
+    token: web::Data<AdminToken>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    if !authorized(&req, &token) {
+        return HttpResponse::Unauthorized().body("invalid or missing admin token");
+    }
+
+    let name = path.into_inner();
+    let mut db = db.write().unwrap();
+    match db.remove_instance(&name) {
+        Err(err) => HttpResponse::NotFound().body(err),
+        Ok(()) => HttpResponse::Ok().body(name),
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+struct Health {
+    #[schema(value_type = String)]
+    status: &'static str,
+    instances_loaded: usize,
+    bks_loaded: usize,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "the server is running", body = Health)
+    )
+)]
+#[get("/health")]
+async fn health(db: SharedDb) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(Ok(Health {
+        status: "ok",
+        instances_loaded: db.instances_loaded(),
+        bks_loaded: db.bks_loaded(),
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+struct Stats {
+    instances: usize,
+    bks_entries: usize,
+    verifications: u64,
+    uptime_secs: u64,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "totals over all instances and the time the server is running", body = Stats)
+    )
+)]
+#[get("/json/stats")]
+async fn json_stats(db: SharedDb) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(Ok(Stats {
+        instances: db.instances_loaded(),
+        bks_entries: db.bks_entries(),
+        verifications: db.verifications(),
+        uptime_secs: db.uptime().as_secs(),
+    }))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "statistics of every instance family", body = Vec<FamilyStats>)
+    )
+)]
+#[get("/json/stats/family")]
+async fn json_family_stats(db: SharedDb) -> impl Responder {
+    let db = db.read().unwrap();
+    resp_json(Ok(db.family_stats()))
+}
+
+// server-sent events announcing every new best known solution
+#[utoipa::path(
+    responses(
+        (status = 200, description = "server-sent bks and instance events", body = String, content_type = "text/event-stream")
+    )
+)]
+#[get("/events")]
+async fn event_stream(db: SharedDb) -> impl Responder {
+    let rx = db.read().unwrap().subscribe();
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .streaming(events::stream(rx))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "the instances are loaded", body = String, content_type = "text/plain"),
+        (status = 503, description = "the instances are still loading", body = String, content_type = "text/plain")
+    )
+)]
+#[get("/ready")]
+async fn ready(db: SharedDb) -> impl Responder {
+    let db = db.read().unwrap();
+    if db.is_loaded() {
+        HttpResponse::Ok().body("ready")
+    } else {
+        HttpResponse::ServiceUnavailable().body("loading")
+    }
+}
+
+// served at a configurable path, so it is registered with `route' in main
+async fn get_metrics(db: SharedDb, metrics: web::Data<Metrics>) -> impl Responder {
+    let db = db.read().unwrap();
+    HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(metrics.render(&db))
+}
+
+// every endpoint is served under /v1 and, deprecated, without the prefix
+fn api(cfg: &mut web::ServiceConfig) {
+    cfg.service(checker)
+        .service(json_checker)
+        .service(get_instance)
+        .service(get_json_instance)
+        .service(get_json_instance_stats)
+        .service(svg_instance)
+        .service(svg_solution)
+        .service(get_instances)
+        .service(get_json_instances)
+        .service(get_json_instances_count)
+        .service(add_instance)
+        .service(add_json_instance)
+        .service(submit_bks)
+        .service(replace_instance)
+        .service(delete_instance)
+        .service(get_bks_history)
+        .service(json_bks_history)
+        .service(json_diff)
+        .service(json_compare)
+        .service(json_check_unique)
+        .service(json_route_profile)
+        .service(csv_bks_history)
+        .service(json_pareto)
+        .service(json_leaderboard)
+        .service(json_leaders)
+        .service(json_family_leaders)
+        .service(json_stats)
+        .service(json_family_stats)
+        .service(health)
+        .service(ready)
+        .service(event_stream);
+}
+
+#[get("/version")]
+async fn get_version() -> impl Responder {
+    resp_json(Ok(version::Version::current()))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "VRP verifier"),
+    servers((url = "/v1")),
+    paths(
+        checker,
+        json_checker,
+        get_instance,
+        get_json_instance,
+        get_json_instance_stats,
+        svg_instance,
+        svg_solution,
+        get_instances,
+        get_json_instances,
+        get_json_instances_count,
+        add_instance,
+        add_json_instance,
+        submit_bks,
+        replace_instance,
+        delete_instance,
+        get_bks_history,
+        json_bks_history,
+        json_diff,
+        json_compare,
+        json_check_unique,
+        json_route_profile,
+        csv_bks_history,
+        json_pareto,
+        json_leaderboard,
+        json_leaders,
+        json_family_leaders,
+        json_stats,
+        json_family_stats,
+        health,
+        ready,
+        event_stream,
+    ),
+    components(schemas(
+        Verification,
+        RouteSlack,
+        VerificationWithComparison,
+        CheckRequest,
+        Error,
+        Count,
+        Uniqueness,
+        DiffRequest,
+        Comparison,
+        BksSubmission,
+        Health,
+        Stats,
+        FamilyStats,
+        Bks,
+        LeaderboardEntry,
+        InstanceLeader,
+        Solution,
+        Instance,
+        verifier::instance::Point,
+        InstanceStats,
+        SolutionDiff,
+        DistanceMode,
+        Precision,
+    )),
+    modifiers(&openapi::AdminTokenScheme)
+)]
+struct ApiDoc;
+
+#[get("/openapi.json")]
+async fn openapi_json() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(ApiDoc::openapi().to_json().unwrap())
+}
+
+#[get("/swagger-ui")]
+async fn swagger_ui() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(openapi::SWAGGER_UI)
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// path to the directory containing instances
-    #[arg(short, long)]
-    instances_dir: PathBuf,
+    /// path to a directory containing instances, can be repeated
+    #[arg(short, long, required = true, action = clap::ArgAction::Append)]
+    instances_dir: Vec<PathBuf>,
+
+    /// read instances from the subdirectories of the instances directories too
+    #[arg(long)]
+    recursive: bool,
 
     /// path to the directory containing best known solutions
     #[arg(short, long)]
     bks_dir: Option<PathBuf>,
 
+    /// SQLite database keeping the best known solutions instead of the bks directory
+    #[arg(long, conflicts_with = "bks_dir")]
+    bks_db: Option<PathBuf>,
+
+    /// solutions with the same number of routes and distances closer than this are equal
+    #[arg(long, default_value_t = 0.001)]
+    bks_tolerance: f64,
+
+    /// accept solutions for instances with any name, not only names like the ones of the standard benchmarks
+    #[arg(long)]
+    lenient_names: bool,
+
+    /// exit with an error when any of the instance files can not be loaded
+    #[arg(long)]
+    strict: bool,
+
     /// port to bind to
     #[arg(short, long, default_value_t = 8080)]
     port: u16,
+
+    /// token required in the `Authorization: Bearer' header of requests modifying the database
+    #[arg(long)]
+    admin_token: Option<String>,
+
+    /// start listening immediately and load the instances in the background
+    #[arg(long)]
+    lazy_load: bool,
+
+    /// reload instance files added to or changed in the instances directory
+    #[arg(long)]
+    watch: bool,
+
+    /// path at which the Prometheus metrics are exposed
+    #[arg(long, default_value = "/metrics")]
+    metrics_path: String,
+
+    /// format of the request log written to stdout
+    #[arg(long, value_enum, default_value_t)]
+    log_format: LogFormat,
+
+    /// maximal number of requests a single address can make per rate window, 0
+    /// turns rate limiting off
+    #[arg(long)]
+    rate_limit: Option<usize>,
+
+    /// length of the rate limiting window in seconds
+    #[arg(long, default_value_t = 60)]
+    rate_window: u64,
+
+    /// origin allowed to make cross-origin requests (`*' allows any), can be repeated
+    #[arg(long)]
+    cors_origin: Vec<String>,
+
+    /// maximal size of a request body in bytes
+    #[arg(long, default_value_t = 1024 * 1024)]
+    max_body_size: usize,
+
+    /// url notified with a POST whenever a new best known solution is recorded, can be repeated
+    #[arg(long)]
+    webhook_url: Vec<String>,
+}
+
+// both the json and the plain text extractors report an overflow with a
+// terse message, replace it with one stating the limit
+fn payload_too_large<B>(
+    limit: usize,
+) -> impl Fn(ServiceResponse<B>) -> actix_web::Result<ErrorHandlerResponse<B>> {
+    move |res| {
+        let (req, _) = res.into_parts();
+        let resp = HttpResponse::PayloadTooLarge().body(format!(
+            "request body is larger than the limit of {limit} bytes"
+        ));
+        Ok(ErrorHandlerResponse::Response(
+            ServiceResponse::new(req, resp).map_into_right_body(),
+        ))
+    }
+}
+
+// the failures were logged one by one while loading
+fn check_load_errors(db: &Db, strict: bool) -> std::io::Result<()> {
+    let errors = db.load_errors();
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    tracing::warn!("{} instance files failed to load", errors.len());
+    if strict {
+        let paths = errors.iter().map(|(path, _)| path.display().to_string());
+        return Err(std::io::Error::other(format!(
+            "instance files failed to load with --strict: {}",
+            paths.collect::<Vec<_>>().join(", ")
+        )));
+    }
+    Ok(())
+}
+
+// preflight requests are answered by the middleware itself
+fn cors(origins: &[String]) -> Cors {
+    origins
+        .iter()
+        .fold(Cors::default(), |cors, origin| match origin.as_str() {
+            "*" => cors.allow_any_origin(),
+            origin => cors.allowed_origin(origin),
+        })
+        .allowed_methods(["GET", "POST", "PUT", "DELETE"])
+        .allowed_headers([AUTHORIZATION, CONTENT_TYPE])
+        .max_age(3600)
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let args = Args::parse();
+    logging::init(args.log_format);
 
-    println!("starting, listening on {}", args.port);
-    let db = Db::new(&args.instances_dir, &args.bks_dir)?;
+    tracing::info!("starting, listening on {}", args.port);
+    let store: Arc<dyn BksStore> = match &args.bks_db {
+        Some(path) => Arc::new(SqliteStore::open(path)?),
+        None => Arc::new(FileStore {
+            dir: args.bks_dir.clone(),
+        }),
+    };
+    let instances_dir = args.instances_dir.clone();
+    let recursive = args.recursive;
+    let settings = Settings {
+        bks_tolerance: args.bks_tolerance,
+        lenient_names: args.lenient_names,
+    };
+    let db = if args.lazy_load {
+        let db = web::Data::new(RwLock::new(Db::empty(store.clone(), settings)));
+        let background = db.clone();
+        let strict = args.strict;
+        std::thread::spawn(
+            move || match Db::new(&instances_dir, recursive, store, settings) {
+                Ok(loaded) => {
+                    if let Err(err) = check_load_errors(&loaded, strict) {
+                        tracing::error!("{err}");
+                        std::process::exit(1);
+                    }
+                    background.write().unwrap().absorb(loaded)
+                }
+                Err(err) => tracing::error!("loading failed: {err}"),
+            },
+        );
+        db
+    } else {
+        let db = Db::new(&args.instances_dir, recursive, store, settings)?;
+        check_load_errors(&db, args.strict)?;
+        web::Data::new(RwLock::new(db))
+    };
+    let _watcher = if args.watch {
+        let watcher = watch::watch(&args.instances_dir, recursive, db.clone());
+        Some(watcher.map_err(std::io::Error::other)?)
+    } else {
+        None
+    };
+    let token = web::Data::new(AdminToken(args.admin_token));
+    let metrics = web::Data::new(Metrics::new().map_err(std::io::Error::other)?);
+    let webhooks = web::Data::new(Webhooks::new(args.webhook_url).map_err(std::io::Error::other)?);
+    let limiter = RateLimit::new(
+        args.rate_limit.unwrap_or_default(),
+        Duration::from_secs(args.rate_window),
+        vec![
+            "/health".to_string(),
+            "/v1/health".to_string(),
+            args.metrics_path.clone(),
+        ],
+    );
+    let limited = args.rate_limit.is_some_and(|limit| limit > 0);
+    let metrics_path = args.metrics_path;
+    let cors_origins = args.cors_origin;
+    let max_body_size = args.max_body_size;
     HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(db.clone()))
-            .service(checker)
-            .service(json_checker)
-            .service(get_instance)
-            .service(get_json_instance)
-            .service(get_bks_history)
-            .service(json_bks_history)
+            .wrap(Condition::new(limited, limiter.clone()))
+            .wrap(Condition::new(
+                !cors_origins.is_empty(),
+                cors(&cors_origins),
+            ))
+            .wrap(ErrorHandlers::new().handler(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                payload_too_large(max_body_size),
+            ))
+            .wrap(from_fn(logging::log_request))
+            .app_data(web::JsonConfig::default().limit(max_body_size))
+            .app_data(web::PayloadConfig::new(max_body_size))
+            .app_data(db.clone())
+            .app_data(token.clone())
+            .app_data(metrics.clone())
+            .app_data(webhooks.clone())
+            .service(web::scope("/v1").configure(api))
+            .service(get_version)
+            .service(openapi_json)
+            .service(swagger_ui)
+            .route(&metrics_path, web::get().to(get_metrics))
+            .service(
+                web::scope("")
+                    .wrap(from_fn(version::negotiate))
+                    .configure(api),
+            )
     })
     .bind(("127.0.0.1", args.port))?
     .run()
@@ -0,0 +1,100 @@
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::data::Db;
+
+pub struct Metrics {
+    registry: Registry,
+    verifications: IntCounterVec,
+    pub bks_improvements: IntCounter,
+    pub verification_duration: Histogram,
+    instances_loaded: IntGauge,
+    bks_loaded: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+
+        let verifications = IntCounterVec::new(
+            Opts::new("vrp_verifications_total", "Number of verified solutions"),
+            &["result"],
+        )?;
+        let bks_improvements = IntCounter::new(
+            "vrp_bks_improvements_total",
+            "Number of submitted solutions improving the best known ones",
+        )?;
+        let verification_duration = Histogram::with_opts(HistogramOpts::new(
+            "vrp_verification_duration_seconds",
+            "Time from receiving a verification request to responding to it",
+        ))?;
+        let instances_loaded = IntGauge::new("vrp_instances_loaded", "Number of loaded instances")?;
+        let bks_loaded = IntGauge::new(
+            "vrp_bks_loaded",
+            "Number of instances with a best known solution",
+        )?;
+
+        registry.register(Box::new(verifications.clone()))?;
+        registry.register(Box::new(bks_improvements.clone()))?;
+        registry.register(Box::new(verification_duration.clone()))?;
+        registry.register(Box::new(instances_loaded.clone()))?;
+        registry.register(Box::new(bks_loaded.clone()))?;
+
+        Ok(Self {
+            registry,
+            verifications,
+            bks_improvements,
+            verification_duration,
+            instances_loaded,
+            bks_loaded,
+        })
+    }
+
+    pub fn verified<T>(&self, result: &Result<T, String>) {
+        let label = if result.is_ok() { "ok" } else { "error" };
+        self.verifications.with_label_values(&[label]).inc();
+    }
+
+    pub fn render(&self, db: &Db) -> String {
+        self.instances_loaded.set(db.instances_loaded() as i64);
+        self.bks_loaded.set(db.bks_loaded() as i64);
+
+        let mut buf = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Settings;
+    use crate::store::FileStore;
+    use std::sync::Arc;
+
+    #[test]
+    fn exposition_contains_all_metrics() {
+        let metrics = Metrics::new().unwrap();
+        metrics.verified::<()>(&Ok(()));
+        metrics.verified::<()>(&Err("infeasible".to_string()));
+        metrics.verified::<()>(&Err("infeasible".to_string()));
+        metrics.bks_improvements.inc();
+        metrics.verification_duration.observe(0.01);
+
+        let text = metrics.render(&Db::empty(
+            Arc::new(FileStore { dir: None }),
+            Settings::default(),
+        ));
+
+        assert!(text.contains("vrp_verifications_total{result=\"ok\"} 1"));
+        assert!(text.contains("vrp_verifications_total{result=\"error\"} 2"));
+        assert!(text.contains("vrp_bks_improvements_total 1"));
+        assert!(text.contains("vrp_verification_duration_seconds_count 1"));
+        assert!(text.contains("vrp_instances_loaded 0"));
+        assert!(text.contains("vrp_bks_loaded 0"));
+    }
+}
@@ -0,0 +1,161 @@
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::{Error, HttpResponse};
+use std::collections::{HashMap, VecDeque};
+use std::future::{ready, Future, Ready};
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// requests of every client within the window, clients without any are
+// forgotten once per window
+struct Clients {
+    requests: HashMap<IpAddr, VecDeque<Instant>>,
+    last_sweep: Instant,
+}
+
+// sliding window limiter shared by all the workers
+#[derive(Clone)]
+pub struct RateLimit {
+    limit: usize,
+    window: Duration,
+    exempt: Arc<Vec<String>>,
+    clients: Arc<Mutex<Clients>>,
+}
+
+impl RateLimit {
+    pub fn new(limit: usize, window: Duration, exempt: Vec<String>) -> Self {
+        Self {
+            limit,
+            window,
+            exempt: Arc::new(exempt),
+            clients: Arc::new(Mutex::new(Clients {
+                requests: HashMap::new(),
+                last_sweep: Instant::now(),
+            })),
+        }
+    }
+
+    // on rejection returns the time after which the client can retry
+    fn acquire(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut clients = self.clients.lock().unwrap();
+        if now.saturating_duration_since(clients.last_sweep) >= self.window {
+            clients.requests.retain(|_, requests| {
+                requests
+                    .back()
+                    .is_some_and(|&t| now.duration_since(t) < self.window)
+            });
+            clients.last_sweep = now;
+        }
+        let requests = clients.requests.entry(ip).or_default();
+
+        while requests
+            .front()
+            .is_some_and(|&t| now.duration_since(t) >= self.window)
+        {
+            requests.pop_front();
+        }
+
+        match requests.front() {
+            Some(&oldest) if requests.len() >= self.limit => {
+                Err(self.window - now.duration_since(oldest))
+            }
+            _ => {
+                requests.push_back(now);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            limiter: self.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    limiter: RateLimit,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let exempt = self.limiter.exempt.iter().any(|p| p == req.path());
+        let ip = req.peer_addr().map(|addr| addr.ip());
+
+        if let (false, Some(ip)) = (exempt, ip) {
+            if let Err(retry_after) = self.limiter.acquire(ip, Instant::now()) {
+                let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                let resp = HttpResponse::TooManyRequests()
+                    .insert_header((RETRY_AFTER, secs))
+                    .body("too many requests");
+                return Box::pin(ready(Ok(req.into_response(resp).map_into_right_body())));
+            }
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_slides() {
+        let limiter = RateLimit::new(2, Duration::from_secs(10), vec![]);
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let b: IpAddr = "10.0.0.2".parse().unwrap();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(limiter.acquire(a, at(0)), Ok(()));
+        assert_eq!(limiter.acquire(a, at(4)), Ok(()));
+        assert_eq!(limiter.acquire(a, at(6)), Err(Duration::from_secs(4)));
+        assert_eq!(limiter.acquire(b, at(6)), Ok(()));
+        assert_eq!(limiter.acquire(a, at(10)), Ok(()));
+        assert_eq!(limiter.acquire(a, at(12)), Err(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn idle_clients_are_forgotten() {
+        let limiter = RateLimit::new(2, Duration::from_secs(10), vec![]);
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let b: IpAddr = "10.0.0.2".parse().unwrap();
+        let start = limiter.clients.lock().unwrap().last_sweep;
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(limiter.acquire(a, at(1)), Ok(()));
+        assert_eq!(limiter.acquire(b, at(5)), Ok(()));
+        assert_eq!(limiter.acquire(b, at(12)), Ok(()));
+        let clients = limiter.clients.lock().unwrap();
+        assert_eq!(clients.requests.keys().collect::<Vec<_>>(), vec![&b]);
+    }
+}
@@ -0,0 +1,198 @@
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use verifier::instance::Precision;
+use verifier::solution::Solution;
+
+use crate::data::{read_bks, Bks, BksDb, Instances};
+
+// where the best known solutions survive server restarts
+pub trait BksStore: Send + Sync {
+    fn load(&self, instances: &Instances) -> std::io::Result<BksDb>;
+    fn save(&self, name: &str, solution_text: &str, bks: &Bks) -> std::io::Result<()>;
+}
+
+// solutions kept as `{date}/{instance}.{routes}_{distance}.txt` files, without
+// a directory they are only kept in memory
+pub struct FileStore {
+    pub dir: Option<PathBuf>,
+}
+
+impl BksStore for FileStore {
+    fn load(&self, instances: &Instances) -> std::io::Result<BksDb> {
+        read_bks(instances, &self.dir)
+    }
+
+    fn save(&self, name: &str, solution_text: &str, bks: &Bks) -> std::io::Result<()> {
+        if let Some(bks_dir) = &self.dir {
+            let dir = bks_dir.join(bks.date.to_string());
+            fs::create_dir_all(&dir)?;
+            let file_name = format!("{}.{}_{}.txt", name, bks.routes, bks.distance.to_f64());
+            fs::write(dir.join(file_name), solution_text)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+fn sql_error(err: rusqlite::Error) -> Error {
+    Error::other(format!("bks database: {err}"))
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let conn = Connection::open(path).map_err(sql_error)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS bks (
+                id INTEGER PRIMARY KEY,
+                instance_name TEXT,
+                routes INTEGER,
+                distance TEXT,
+                date TEXT,
+                author TEXT,
+                solution_text TEXT,
+                submitted_at TEXT
+            )",
+            (),
+        )
+        .map_err(sql_error)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+type Row = (String, i64, String, String, Option<String>, Option<String>);
+
+fn bks_from_row(row: Row) -> Result<(String, Bks), String> {
+    let (name, routes, distance, date, author, solution_text) = row;
+    let distance = rug::Float::parse(&distance)
+        .map(|d| rug::Float::with_val(Precision::STANDARD.0, d))
+        .map_err(|err| format!("distance `{distance}' of {name}: {err}"))?;
+    let date =
+        NaiveDate::from_str(&date).map_err(|err| format!("date `{date}' of {name}: {err}"))?;
+    let solution = solution_text
+        .filter(|text| !text.is_empty())
+        .map(|text| Solution::from_str_lenient(&text))
+        .transpose()
+        .map_err(|err| format!("solution of {name}: {err}"))?;
+
+    Ok((
+        name,
+        Bks {
+            routes: routes as usize,
+            distance,
+            date,
+            solution,
+            author,
+        },
+    ))
+}
+
+impl BksStore for SqliteStore {
+    // only solutions of the loaded instances are read, their names are passed
+    // as one json array so that any number of them fits in a single parameter
+    fn load(&self, instances: &Instances) -> std::io::Result<BksDb> {
+        let names = serde_json::to_string(&instances.keys().collect::<Vec<_>>())?;
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT instance_name, routes, distance, date, author, solution_text
+                 FROM bks WHERE instance_name IN (SELECT value FROM json_each(?1))
+                 ORDER BY id",
+            )
+            .map_err(sql_error)?;
+        let rows = stmt
+            .query_map([names], |r| {
+                Ok((
+                    r.get(0)?,
+                    r.get(1)?,
+                    r.get(2)?,
+                    r.get(3)?,
+                    r.get(4)?,
+                    r.get(5)?,
+                ))
+            })
+            .map_err(sql_error)?;
+
+        let mut bks = BksDb::new();
+        for row in rows {
+            match bks_from_row(row.map_err(sql_error)?) {
+                Ok((name, best)) => bks.entry(name).or_default().push(best),
+                Err(err) => tracing::warn!("bks database: {err}"),
+            }
+        }
+
+        tracing::info!("read {} bks", bks.len());
+
+        Ok(bks)
+    }
+
+    fn save(&self, name: &str, solution_text: &str, bks: &Bks) -> std::io::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(sql_error)?;
+        tx.execute(
+            "INSERT INTO bks (instance_name, routes, distance, date, author, solution_text, submitted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                name,
+                bks.routes as i64,
+                bks.distance.to_string(),
+                bks.date.format("%Y-%m-%d").to_string(),
+                bks.author,
+                solution_text,
+                chrono::Local::now().to_rfc3339(),
+            ],
+        )
+        .map_err(sql_error)?;
+        tx.commit().map_err(sql_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use verifier::flf64;
+
+    #[test]
+    fn sqlite_round_trip() {
+        let store = SqliteStore::open(Path::new(":memory:")).unwrap();
+        let solution_text = "Instance name: c1\nAuthors: a\nDate: 2024-01-15\nReference: r\nSolution\nRoute 1: 1 2\n";
+        let bks = |routes, distance| Bks {
+            routes,
+            distance: flf64(distance),
+            date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            solution: Some(Solution::from_str(solution_text).unwrap()),
+            author: Some("a".to_string()),
+        };
+
+        store.save("c1", solution_text, &bks(2, 100.5)).unwrap();
+        store.save("c1", solution_text, &bks(1, 120.25)).unwrap();
+        store.save("c2", solution_text, &bks(1, 90.0)).unwrap();
+
+        assert!(store.load(&Instances::new()).unwrap().is_empty());
+        let inst = verifier::generator::InstanceGenerator::default()
+            .generate()
+            .unwrap();
+        let instances = Instances::from([("c1".to_string(), inst)]);
+        let loaded = store.load(&instances).unwrap();
+        assert_eq!(loaded.keys().collect::<Vec<_>>(), vec!["c1"]);
+        let history: Vec<(usize, f64)> = loaded["c1"]
+            .iter()
+            .map(|b| (b.routes, b.distance.to_f64()))
+            .collect();
+
+        assert_eq!(history, vec![(2, 100.5), (1, 120.25)]);
+        assert_eq!(loaded["c1"][0].author, Some("a".to_string()));
+        assert_eq!(loaded["c1"][0].solution, bks(2, 100.5).solution);
+    }
+}
@@ -0,0 +1,84 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, ACCEPT};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+use serde::Serialize;
+
+pub const API_VERSION: &str = "1";
+
+#[derive(Serialize)]
+pub struct Version {
+    api_version: &'static str,
+    app_version: &'static str,
+}
+
+impl Version {
+    pub fn current() -> Self {
+        Self {
+            api_version: API_VERSION,
+            app_version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+}
+
+// the version asked for with `Accept: application/vnd.vrp.v1+json'
+fn requested_version(accept: &str) -> Option<&str> {
+    accept.split(',').find_map(|media| {
+        media
+            .split(';')
+            .next()?
+            .trim()
+            .strip_prefix("application/vnd.vrp.v")?
+            .strip_suffix("+json")
+    })
+}
+
+// unversioned paths serve the current version; a client asking for it in the
+// Accept header gets it as is, the others are told that the paths are
+// deprecated, and unknown versions are refused
+pub async fn negotiate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let requested = req
+        .headers()
+        .get(ACCEPT)
+        .and_then(|h| h.to_str().ok())
+        .and_then(requested_version)
+        .map(str::to_string);
+
+    match requested.as_deref() {
+        Some(API_VERSION) => Ok(next.call(req).await?.map_into_left_body()),
+        Some(version) => {
+            let resp = HttpResponse::NotAcceptable().body(format!(
+                "api version {version} is not supported, the current version is {API_VERSION}"
+            ));
+            Ok(req.into_response(resp).map_into_right_body())
+        }
+        None => {
+            let mut res = next.call(req).await?;
+            res.headers_mut().insert(
+                HeaderName::from_static("deprecation"),
+                HeaderValue::from_static("true"),
+            );
+            Ok(res.map_into_left_body())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_header_versions() {
+        assert_eq!(requested_version("application/vnd.vrp.v1+json"), Some("1"));
+        assert_eq!(
+            requested_version("text/html, application/vnd.vrp.v2+json; q=0.9"),
+            Some("2")
+        );
+        assert_eq!(requested_version("application/json"), None);
+        assert_eq!(requested_version("*/*"), None);
+    }
+}
@@ -0,0 +1,72 @@
+use actix_web::web;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::data::{read_instance_file, Db};
+
+fn reload(db: &web::Data<RwLock<Db>>, path: &Path) {
+    // a changed binary sidecar reloads the instance it belongs to
+    let path = if path.extension().is_some_and(|ext| ext == "bin") {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    };
+    if !path.is_file() {
+        return;
+    }
+
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return;
+    };
+    if !db.read().unwrap().is_source(name, &path) {
+        tracing::debug!(
+            "{}: instance `{name}' is loaded from another file",
+            path.display()
+        );
+        return;
+    }
+
+    // files are often seen half written, the next event brings the rest
+    match read_instance_file(&path) {
+        Ok(instance) => {
+            tracing::info!("reloaded {}", path.display());
+            db.write()
+                .unwrap()
+                .reload_instance(name.to_string(), &path, instance);
+        }
+        Err(err) => tracing::warn!("{}: {err}", path.display()),
+    }
+}
+
+// the instances are updated for as long as the returned watcher lives
+pub fn watch(
+    dirs: &[PathBuf],
+    recursive: bool,
+    db: web::Data<RwLock<Db>>,
+) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        match event {
+            Ok(event) => match event.kind {
+                EventKind::Create(_)
+                | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any) => {
+                    event.paths.iter().for_each(|path| reload(&db, path))
+                }
+                _ => {}
+            },
+            Err(err) => tracing::warn!("watching instances: {err}"),
+        }
+    })?;
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    for dir in dirs {
+        watcher.watch(dir, mode)?;
+        tracing::info!("watching {}", dir.display());
+    }
+
+    Ok(watcher)
+}
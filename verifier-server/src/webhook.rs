@@ -0,0 +1,104 @@
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::data::Bks;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BksImproved {
+    event: &'static str,
+    instance: String,
+    old_routes: Option<usize>,
+    old_distance: Option<String>,
+    new_routes: usize,
+    new_distance: String,
+    author: Option<String>,
+    date: String,
+}
+
+impl BksImproved {
+    pub fn new(instance: &str, old: Option<&Bks>, new: &Bks) -> Self {
+        Self {
+            event: "bks_improved",
+            instance: instance.to_string(),
+            old_routes: old.map(|b| b.routes),
+            old_distance: old.map(|b| b.distance.to_string()),
+            new_routes: new.routes,
+            new_distance: new.distance.to_string(),
+            author: new.author.clone(),
+            date: new.date.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+// notifications are sent in the background, a failed delivery is only logged
+#[derive(Clone)]
+pub struct Webhooks {
+    urls: Arc<Vec<String>>,
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    pub fn new(urls: Vec<String>) -> reqwest::Result<Self> {
+        Ok(Self {
+            urls: Arc::new(urls),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()?,
+        })
+    }
+
+    pub fn notify(&self, event: BksImproved) {
+        for url in self.urls.iter() {
+            let request = self.client.post(url).json(&event);
+            let url = url.clone();
+            tokio::spawn(async move {
+                if let Err(err) = request.send().await.and_then(|r| r.error_for_status()) {
+                    tracing::warn!("webhook {url}: {err}");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use verifier::flf64;
+
+    #[test]
+    fn notification_format() {
+        let new = Bks {
+            routes: 10,
+            distance: flf64(95.5),
+            date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            solution: None,
+            author: Some("a".to_string()),
+        };
+        let old = Bks {
+            routes: 11,
+            distance: flf64(90.0),
+            author: None,
+            ..new.clone()
+        };
+
+        assert_eq!(
+            serde_json::to_value(BksImproved::new("c1_2_1", Some(&old), &new)).unwrap(),
+            serde_json::json!({
+                "event": "bks_improved",
+                "instance": "c1_2_1",
+                "old_routes": 11,
+                "old_distance": flf64(90.0).to_string(),
+                "new_routes": 10,
+                "new_distance": flf64(95.5).to_string(),
+                "author": "a",
+                "date": "2024-01-15",
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(BksImproved::new("c1_2_1", None, &new)).unwrap()["old_routes"],
+            serde_json::Value::Null
+        );
+    }
+}
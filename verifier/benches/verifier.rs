@@ -0,0 +1,104 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::str::FromStr;
+use verifier::generator::InstanceGenerator;
+use verifier::instance::{DistanceMode, Instance};
+use verifier::solution::Solution;
+use verifier::verify::{calc_route_distance, verify};
+
+const SIZES: [usize; 3] = [25, 100, 400];
+
+// a vehicle per customer, so that visiting every customer alone is feasible
+fn instance(num_customers: usize) -> Instance {
+    InstanceGenerator {
+        num_customers,
+        num_vehicles: num_customers,
+        seed: 42,
+        ..Default::default()
+    }
+    .generate()
+    .unwrap()
+}
+
+fn solution(inst: &Instance) -> Solution {
+    Solution {
+        instance_name: inst.name.clone(),
+        routes: (1..inst.pts.len()).map(|c| vec![c]).collect(),
+        ..Default::default()
+    }
+}
+
+fn parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for n in SIZES {
+        let inst = instance(n);
+        let inst_text = inst.to_string();
+        let sol_text = solution(&inst).to_string();
+
+        group.bench_with_input(BenchmarkId::new("instance", n), &inst_text, |b, text| {
+            b.iter(|| Instance::from_str(black_box(text)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("solution", n), &sol_text, |b, text| {
+            b.iter(|| Solution::from_str(black_box(text)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn verification(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify");
+    for n in SIZES {
+        let inst = instance(n);
+        let sol = solution(&inst);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(n),
+            &(inst, sol),
+            |b, (inst, sol)| b.iter(|| verify(black_box(inst), black_box(sol)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn distances(c: &mut Criterion) {
+    let inst = instance(SIZES[SIZES.len() - 1]);
+    let route: Vec<usize> = (1..inst.pts.len()).collect();
+
+    c.bench_function("route distance", |b| {
+        b.iter(|| calc_route_distance(black_box(&inst), black_box(&route), DistanceMode::Euclidean))
+    });
+    c.bench_function("distance matrix", |b| {
+        b.iter(|| black_box(&inst).distance_matrix())
+    });
+}
+
+// what the server gains by reading its instance files with `parallel-load`
+#[cfg(feature = "parallel")]
+fn parallel_parsing(c: &mut Criterion) {
+    use rayon::prelude::*;
+
+    let texts: Vec<String> = (0..64).map(|_| instance(SIZES[1]).to_string()).collect();
+    let mut group = c.benchmark_group("parse 64 instances");
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            black_box(&texts)
+                .iter()
+                .map(|text| Instance::from_str(text).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            black_box(&texts)
+                .par_iter()
+                .map(|text| Instance::from_str(text).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "parallel"))]
+criterion_group!(benches, parsing, verification, distances);
+#[cfg(feature = "parallel")]
+criterion_group!(benches, parsing, verification, distances, parallel_parsing);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+#![no_main]
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use std::fmt::Write;
+use std::str::FromStr;
+use verifier::instance::Instance;
+use verifier::solution::Solution;
+use verifier::verify::verify;
+
+#[derive(Arbitrary, Debug)]
+struct Row {
+    x: i16,
+    y: i16,
+    demand: i16,
+    start: i16,
+    due: i16,
+    service: i16,
+}
+
+// instances are built as text, so that they are always syntactically valid
+// and go through the same sanity checks as files do
+#[derive(Arbitrary, Debug)]
+struct Input {
+    vehicles: u8,
+    capacity: i16,
+    rows: Vec<Row>,
+    routes: Vec<Vec<u8>>,
+}
+
+fuzz_target!(|input: Input| {
+    let mut text = format!("{} {}\n", input.vehicles, input.capacity);
+    for (id, r) in input.rows.iter().enumerate() {
+        writeln!(
+            text,
+            "{id} {} {} {} {} {} {}",
+            r.x, r.y, r.demand, r.start, r.due, r.service
+        )
+        .unwrap();
+    }
+
+    let Ok(inst) = Instance::from_str(&text) else {
+        return;
+    };
+
+    let sol = Solution {
+        routes: input
+            .routes
+            .iter()
+            .map(|r| r.iter().map(|&p| p as usize).collect())
+            .collect(),
+        ..Default::default()
+    };
+
+    if let Err(err) = verify(&inst, &sol) {
+        assert!(!err.to_string().is_empty(), "{err:?} has no message");
+    }
+});
@@ -0,0 +1,3 @@
+// readers of instances and solutions published in formats other than the
+// solomon, li&lim and sintef text files
+pub mod vrprep;
@@ -0,0 +1,283 @@
+// instances and solutions of the VRP-REP repository (vrp-rep.org)
+//
+// values are given either as attributes or as child elements, so both
+// `<node id="1" cx="45" cy="68">` and `<node id="1"><cx>45</cx><cy>68</cy></node>`
+// are read, the same goes for `<time_window start=".." end=".."/>` (or `<tw>`)
+use roxmltree::{Document, Node};
+
+use crate::error::VerifyError;
+use crate::instance::{DistanceSource, Instance, Point};
+use crate::solution::Solution;
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|c| c.has_tag_name(name))
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |c| c.has_tag_name(name))
+}
+
+fn find<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Result<Node<'a, 'input>, VerifyError> {
+    node.descendants()
+        .find(|c| c.has_tag_name(name))
+        .ok_or_else(|| VerifyError::ParseError(format!("missing <{name}> element")))
+}
+
+fn value<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attribute(name)
+        .or_else(|| child(node, name).and_then(|c| c.text()))
+        .map(str::trim)
+}
+
+fn float(node: Node, name: &str) -> Result<Option<f64>, VerifyError> {
+    value(node, name)
+        .map(|v| {
+            v.parse().map_err(|e| {
+                VerifyError::ParseError(format!(
+                    "can't parse {name} `{v}' of <{}>: {e}",
+                    node.tag_name().name()
+                ))
+            })
+        })
+        .transpose()
+}
+
+// demands and times are integers, but they are often written as `10.0`
+fn int(node: Node, name: &str) -> Result<Option<i32>, VerifyError> {
+    match float(node, name)? {
+        Some(v) if v.fract() != 0.0 => Err(VerifyError::ParseError(format!(
+            "{name} of <{}> has to be an integer, it is {v}",
+            node.tag_name().name()
+        ))),
+        v => Ok(v.map(|v| v as i32)),
+    }
+}
+
+fn required<T>(node: Node, name: &str, v: Option<T>) -> Result<T, VerifyError> {
+    v.ok_or_else(|| {
+        VerifyError::ParseError(format!("missing {name} of <{}>", node.tag_name().name()))
+    })
+}
+
+fn time_window(node: Node) -> Result<Option<(i32, i32)>, VerifyError> {
+    let Some(tw) = child(node, "time_window").or_else(|| child(node, "tw")) else {
+        return Ok(None);
+    };
+    let start = int(tw, "start")?;
+    let end = int(tw, "end")?;
+    Ok(Some((
+        required(tw, "start", start)?,
+        required(tw, "end", end)?,
+    )))
+}
+
+fn parse_node(node: Node, horizon: i32) -> Result<Point, VerifyError> {
+    let id = int(node, "id")?;
+    let x = float(node, "cx")?;
+    let y = float(node, "cy")?;
+    let (start, due) = time_window(node)?.unwrap_or((0, horizon));
+    Ok(Point {
+        id: required(node, "id", id)?,
+        x: required(node, "cx", x)?,
+        y: required(node, "cy", y)?,
+        demand: 0,
+        start,
+        due,
+        service: 0,
+        pickup_delivery: None,
+        pickup_delivery_chain: None,
+    })
+}
+
+// demands, service times and time windows of customers are usually given by
+// `<request node="..">` elements and the fleet by a single `<vehicle_profile>`,
+// without a `number` the fleet is unlimited, so every customer may get its own
+// vehicle, `max_travel_time` closes the time windows that are not given
+pub fn parse_instance(xml: &str) -> Result<Instance, VerifyError> {
+    let doc = Document::parse(xml)
+        .map_err(|e| VerifyError::ParseError(format!("Instance parsing problem: {e}")))?;
+    let root = doc.root_element();
+
+    let name = find(root, "info")
+        .ok()
+        .and_then(|info| value(info, "name"))
+        .unwrap_or_default()
+        .to_string();
+
+    let profile = find(root, "vehicle_profile")?;
+    let capacity = int(profile, "capacity")?;
+    let max_capacity = required(profile, "capacity", capacity)?;
+    let horizon = int(profile, "max_travel_time")?.unwrap_or(i32::MAX);
+
+    let mut pts = children(find(find(root, "network")?, "nodes")?, "node")
+        .map(|node| parse_node(node, horizon))
+        .collect::<Result<Vec<_>, _>>()?;
+    pts.sort_by_key(|pt| pt.id);
+
+    if let Ok(requests) = find(root, "requests") {
+        for request in children(requests, "request") {
+            let node = int(request, "node")?;
+            let node = required(request, "node", node)?;
+            let pt = pts.iter_mut().find(|pt| pt.id == node).ok_or_else(|| {
+                VerifyError::ParseError(format!("request for unknown node {node}"))
+            })?;
+            pt.demand = int(request, "quantity")?.unwrap_or(pt.demand);
+            pt.service = int(request, "service_time")?.unwrap_or(pt.service);
+            if let Some((start, due)) = time_window(request)? {
+                pt.start = start;
+                pt.due = due;
+            }
+        }
+    }
+
+    let vehicles = int(profile, "number")?.unwrap_or(pts.len().saturating_sub(1) as i32);
+
+    let inst = Instance {
+        name,
+        vehicles,
+        max_capacity,
+        pts,
+        is_pdp: false,
+        distances: DistanceSource::Euclidean,
+        max_route_duration: None,
+        min_vehicles: None,
+        open_routes: false,
+        vehicle_capacities: vec![],
+        depots: vec![],
+        depot_capacities: vec![],
+        vehicle_depot_assignment: None,
+        soft_windows: false,
+        penalty_per_unit: 0.0,
+    };
+    inst.check_sanity()?;
+    Ok(inst)
+}
+
+// routes list their nodes as `<node id=".."/>` or `<node>..</node>`, visits of
+// the depot at their ends are dropped like in or-tools solutions
+pub fn parse_solution(xml: &str) -> Result<Solution, VerifyError> {
+    let doc = Document::parse(xml)
+        .map_err(|e| VerifyError::ParseError(format!("Solution parsing error: {e}")))?;
+    let root = doc.root_element();
+
+    let instance_name = find(root, "info")
+        .ok()
+        .and_then(|info| value(info, "instance"))
+        .unwrap_or_default()
+        .to_string();
+
+    let routes = children(find(root, "routes")?, "route")
+        .map(|route| {
+            children(route, "node")
+                .map(|node| {
+                    let id = node.attribute("id").or(node.text()).unwrap_or_default();
+                    id.trim().parse::<usize>().map_err(|e| {
+                        VerifyError::ParseError(format!("can't parse node `{id}' of a route: {e}"))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|route| strip_depot(&route).to_vec())
+        })
+        .filter(|route| !matches!(route, Ok(r) if r.is_empty()))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Solution {
+        instance_name,
+        routes,
+        ..Default::default()
+    })
+}
+
+// only the first and the last visit can be the depot, one in between is left
+// for the verifier to report
+fn strip_depot(route: &[usize]) -> &[usize] {
+    let route = route.strip_prefix(&[0]).unwrap_or(route);
+    route.strip_suffix(&[0]).unwrap_or(route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::verify;
+
+    const INSTANCE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<instance>
+  <info><dataset>Solomon</dataset><name>tiny</name></info>
+  <network>
+    <nodes>
+      <node id="0" type="0" cx="0" cy="0"/>
+      <node id="2" type="1"><cx>0.0</cx><cy>4.0</cy></node>
+      <node id="1" type="1" cx="3" cy="0">
+        <time_window start="0" end="10"/>
+      </node>
+    </nodes>
+    <euclidean/>
+  </network>
+  <fleet>
+    <vehicle_profile type="0" number="2">
+      <capacity>10.0</capacity>
+      <max_travel_time>100</max_travel_time>
+    </vehicle_profile>
+  </fleet>
+  <requests>
+    <request id="1" node="1"><quantity>4</quantity><service_time>1</service_time></request>
+    <request id="2" node="2">
+      <tw><start>5</start><end>20</end></tw>
+      <quantity>6.0</quantity>
+    </request>
+  </requests>
+</instance>"#;
+
+    #[test]
+    fn instance() {
+        let inst = parse_instance(INSTANCE).unwrap();
+        assert_eq!(inst.name, "tiny");
+        assert_eq!((inst.vehicles, inst.max_capacity), (2, 10));
+        let pts = inst
+            .pts
+            .iter()
+            .map(|p| (p.id, p.x, p.y, p.demand, p.start, p.due, p.service))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pts,
+            vec![
+                (0, 0.0, 0.0, 0, 0, 100, 0),
+                (1, 3.0, 0.0, 4, 0, 10, 1),
+                (2, 0.0, 4.0, 6, 5, 20, 0),
+            ]
+        );
+
+        assert!(parse_instance("<instance>").is_err());
+        assert!(parse_instance(
+            &INSTANCE.replace("<quantity>4</quantity>", "<quantity>4.5</quantity>")
+        )
+        .is_err());
+        assert!(parse_instance(&INSTANCE.replace("node=\"2\"", "node=\"7\"")).is_err());
+    }
+
+    #[test]
+    fn solution() {
+        let xml = r#"<solution>
+  <info><instance>tiny</instance></info>
+  <routes>
+    <route id="1"><node id="0"/><node id="1"/><node id="2"/><node id="0"/></route>
+    <route id="2"><node>0</node><node>0</node></route>
+  </routes>
+</solution>"#;
+        let sol = parse_solution(xml).unwrap();
+        assert_eq!(sol.instance_name, "tiny");
+        assert_eq!(sol.routes, vec![vec![1, 2]]);
+
+        let inst = parse_instance(INSTANCE).unwrap();
+        assert_eq!(verify(&inst, &sol).unwrap().total_distance.to_f64(), 12.0);
+
+        let inner = xml.replace("<node id=\"2\"/>", "<node id=\"0\"/><node id=\"2\"/>");
+        assert_eq!(parse_solution(&inner).unwrap().routes, vec![vec![1, 0, 2]]);
+
+        assert!(parse_solution("<solution/>").is_err());
+        assert!(parse_solution(&xml.replace("id=\"2\"/>", "id=\"x\"/>")).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use crate::instance::{Instance, Point};
+use crate::verify::VerifyError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstanceGenerator {
+    pub num_customers: usize,
+    pub num_vehicles: usize,
+    pub capacity: i32,
+    pub coordinate_range: (i32, i32),
+    pub time_horizon: i32,
+    pub avg_window_width: i32,
+    pub window_tightness: f64,
+    pub seed: u64,
+    pub pdp: bool,
+}
+
+impl Default for InstanceGenerator {
+    fn default() -> Self {
+        Self {
+            num_customers: 100,
+            num_vehicles: 25,
+            capacity: 200,
+            coordinate_range: (0, 100),
+            time_horizon: 1000,
+            avg_window_width: 60,
+            window_tightness: 0.5,
+            seed: 0,
+            pdp: false,
+        }
+    }
+}
+
+const MAX_SERVICE: i32 = 10;
+const MAX_TRIES: usize = 1000;
+
+// distances rounded up, so that windows built on them stay reachable
+fn ceil_dist(a: &Point, b: &Point) -> i32 {
+    a.dist(b).ceil().to_i32_saturating().unwrap()
+}
+
+impl InstanceGenerator {
+    fn point(&self, rng: &mut StdRng, id: usize) -> Point {
+        let (lo, hi) = self.coordinate_range;
+        Point {
+            id: id as i32,
+            x: rng.gen_range(lo..=hi) as f64,
+            y: rng.gen_range(lo..=hi) as f64,
+            demand: 0,
+            start: 0,
+            due: 0,
+            service: rng.gen_range(0..=MAX_SERVICE),
+            pickup_delivery: None,
+            pickup_delivery_chain: None,
+        }
+    }
+
+    // tightness moves the width from the whole feasible interval to the average width
+    fn window(&self, rng: &mut StdRng, earliest: i32, latest: i32) -> (i32, i32) {
+        let full = (latest - earliest) as f64;
+        let target = full + (self.avg_window_width as f64 - full) * self.window_tightness;
+        let width = (target.round() as i32).clamp(0, latest - earliest);
+        let start = rng.gen_range(earliest..=latest - width);
+        (start, start + width)
+    }
+
+    // returns false when the point can not be served from the depot within the horizon
+    fn set_window(&self, rng: &mut StdRng, depot: &Point, pt: &mut Point, earliest: i32) -> bool {
+        let d = ceil_dist(depot, pt);
+        let latest = self.time_horizon - pt.service - d;
+        let earliest = earliest.max(d);
+        if earliest > latest {
+            return false;
+        }
+        (pt.start, pt.due) = self.window(rng, earliest, latest);
+        true
+    }
+
+    fn customer(
+        &self,
+        rng: &mut StdRng,
+        depot: &Point,
+        id: usize,
+        earliest: impl Fn(&Point) -> i32,
+    ) -> Result<Point, VerifyError> {
+        for _ in 0..MAX_TRIES {
+            let mut pt = self.point(rng, id);
+            let earliest = earliest(&pt);
+            if self.set_window(rng, depot, &mut pt, earliest) {
+                return Ok(pt);
+            }
+        }
+        Err(VerifyError::InvalidInstance(format!(
+            "could not place customer {id}, the time horizon {} is too short for the coordinate range",
+            self.time_horizon
+        )))
+    }
+
+    pub fn generate(&self) -> Result<Instance, VerifyError> {
+        if self.num_customers == 0 || self.capacity <= 0 || self.num_vehicles == 0 {
+            Err(VerifyError::InvalidInstance(
+                "at least one customer, one vehicle and a positive capacity are needed".to_string(),
+            ))?;
+        }
+        let (lo, hi) = self.coordinate_range;
+        if lo > hi {
+            Err(VerifyError::InvalidInstance(format!(
+                "the minimum coordinate {lo} is larger than the maximum {hi}"
+            )))?;
+        }
+        if self.pdp && !self.num_customers.is_multiple_of(2) {
+            Err(VerifyError::InvalidInstance(format!(
+                "pdp instances need an even number of customers, got {}",
+                self.num_customers
+            )))?;
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let depot = Point {
+            id: 0,
+            x: (lo + (hi - lo) / 2) as f64,
+            y: (lo + (hi - lo) / 2) as f64,
+            demand: 0,
+            start: 0,
+            due: self.time_horizon,
+            service: 0,
+            pickup_delivery: self.pdp.then_some((0, 0)),
+            pickup_delivery_chain: None,
+        };
+
+        // keeps the total demand within the fleet capacity
+        let demands = if self.pdp { 2 } else { 1 };
+        let max_demand = (self.capacity as usize * self.num_vehicles * demands / self.num_customers)
+            .clamp(1, self.capacity as usize) as i32;
+
+        let mut pts = vec![depot.clone()];
+        while pts.len() <= self.num_customers {
+            let id = pts.len();
+            let mut pt = self.customer(&mut rng, &depot, id, |_| 0)?;
+            pt.demand = rng.gen_range(1..=max_demand);
+
+            if self.pdp {
+                let pickup = &pt;
+                let mut delivery = self.customer(&mut rng, &depot, id + 1, |d| {
+                    pickup.start + pickup.service + ceil_dist(pickup, d)
+                })?;
+                delivery.demand = -pickup.demand;
+                pt.pickup_delivery = Some((0, id as i32 + 1));
+                delivery.pickup_delivery = Some((id as i32, 0));
+                pts.push(pt);
+                pts.push(delivery);
+            } else {
+                pts.push(pt);
+            }
+        }
+
+        let inst = Instance {
+            name: format!("gen_{}", self.seed),
+            vehicles: self.num_vehicles as i32,
+            max_capacity: self.capacity,
+            pts,
+            is_pdp: self.pdp,
+            distances: Default::default(),
+            open_routes: false,
+            max_route_duration: None,
+            min_vehicles: None,
+            vehicle_capacities: vec![],
+            depots: vec![],
+            depot_capacities: vec![],
+            vehicle_depot_assignment: None,
+            soft_windows: false,
+            penalty_per_unit: 0.0,
+        };
+        inst.check_sanity()?;
+        Ok(inst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_instances_are_sane() {
+        for seed in 0..20 {
+            for pdp in [false, true] {
+                let generator = InstanceGenerator {
+                    num_customers: 50,
+                    window_tightness: seed as f64 / 20.0,
+                    seed,
+                    pdp,
+                    ..Default::default()
+                };
+                let inst = generator.generate().unwrap();
+
+                assert_eq!(inst.pts.len(), 51);
+                assert_eq!(inst.check_feasibility(), Ok(()));
+                assert_eq!(inst, generator.generate().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn too_short_horizon() {
+        let generator = InstanceGenerator {
+            time_horizon: 10,
+            ..Default::default()
+        };
+        assert!(generator.generate().is_err());
+    }
+
+    #[test]
+    fn inverted_coordinate_range() {
+        let generator = InstanceGenerator {
+            coordinate_range: (100, 0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            generator.generate(),
+            Err(VerifyError::InvalidInstance(_))
+        ));
+    }
+}
@@ -1,32 +1,120 @@
+pub mod formats;
+pub mod generator;
+pub mod numeric;
 pub mod verify;
 
+pub use verify::error;
+pub use verify::geo;
 pub use verify::instance;
 pub use verify::solution;
 
-use rug;
+pub use numeric::{fl, fl_prec, flf64};
+
 use std::fs::read_to_string;
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
+use verify::VerifyError;
 
-pub fn read<T: FromStr<Err = String>>(path: &Path) -> Result<T, String> {
-    let f = read_to_string(path).map_err(|x| format!("{}: {x}", path.display()))?;
+pub fn read<T: FromStr<Err = VerifyError>>(path: &Path) -> Result<T, VerifyError> {
+    let f =
+        read_to_string(path).map_err(|x| VerifyError::Io(format!("{}: {x}", path.display())))?;
 
     T::from_str(&f)
 }
 
-pub fn check_sintef_file(
+pub fn read_instance_path(path: &Path) -> Result<instance::Instance, VerifyError> {
+    read(path)
+}
+
+// parses an instance already in memory (e.g. read from stdin or the network)
+pub fn read_instance_str(content: &str) -> Result<instance::Instance, VerifyError> {
+    instance::Instance::from_str(content)
+}
+
+pub fn binary_sidecar(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".bin");
+    PathBuf::from(sidecar)
+}
+
+// None when there is no sidecar or it is older than the text instance
+pub fn read_binary_sidecar(path: &Path) -> Option<Result<instance::Instance, VerifyError>> {
+    let sidecar = binary_sidecar(path);
+    let modified = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    if modified(&sidecar)? < modified(path)? {
+        return None;
+    }
+
+    Some(
+        std::fs::read(&sidecar)
+            .map_err(|x| VerifyError::Io(format!("{}: {x}", sidecar.display())))
+            .and_then(|bytes| instance::Instance::from_binary(&bytes)),
+    )
+}
+
+pub fn read_json_instance(path: &Path) -> Result<instance::Instance, VerifyError> {
+    let f =
+        read_to_string(path).map_err(|x| VerifyError::Io(format!("{}: {x}", path.display())))?;
+
+    instance::Instance::from_json(&f)
+}
+
+pub fn read_solution_and_instance(
     path: &Path,
     instances_loc: &Path,
-) -> Result<(solution::Solution, rug::Float), String> {
+) -> Result<(solution::Solution, instance::Instance), VerifyError> {
     let solution = read::<solution::Solution>(path)?;
+    let instance = read_instance_for(&solution, instances_loc)?;
+    Ok((solution, instance))
+}
+
+pub fn read_instance_for(
+    solution: &solution::Solution,
+    instances_loc: &Path,
+) -> Result<instance::Instance, VerifyError> {
     let instance_path = if instances_loc.is_dir() {
         instances_loc.join(&solution.instance_name)
     } else {
         PathBuf::from(instances_loc)
     };
-    let instance = read::<instance::Instance>(&instance_path)?;
-    let dist = verify::verify(&instance, &solution)?;
+    read::<instance::Instance>(&instance_path)
+}
+
+// outcome of checking a feasible solution file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    pub solution: solution::Solution,
+    pub distance: rug::Float,
+    pub routes: usize,
+    pub instance_name: String,
+}
+
+pub fn check_sintef_file(
+    path: &Path,
+    instances_loc: &Path,
+    mode: Option<instance::DistanceMode>,
+    prec: Option<instance::Precision>,
+) -> Result<CheckResult, VerifyError> {
+    let (solution, instance) = read_solution_and_instance(path, instances_loc)?;
+    let mode = mode.unwrap_or(instance.distance_mode());
+    let prec = prec.unwrap_or_default();
+    let distance = verify::verify_with(&instance, &solution, mode, prec)?.total_distance;
+
+    Ok(CheckResult {
+        routes: solution.routes.len(),
+        instance_name: solution.instance_name.clone(),
+        solution,
+        distance,
+    })
+}
+
+pub fn check_sintef_file_f64(
+    path: &Path,
+    instances_loc: &Path,
+) -> Result<(solution::Solution, f64), VerifyError> {
+    let (solution, instance) = read_solution_and_instance(path, instances_loc)?;
+    let dist = verify::verify_f64(&instance, &solution)?;
 
     Ok((solution, dist))
 }
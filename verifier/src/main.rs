@@ -1,42 +1,622 @@
-use std::env;
-use std::path::PathBuf;
-use verifier;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use verifier::error::VerifyError;
+use verifier::formats::vrprep;
+use verifier::generator::InstanceGenerator;
+use verifier::instance::{DistanceMode, Instance, Precision};
+use verifier::solution::Solution;
+use verifier::verify::report::csv_field;
+use verifier::verify::{
+    verify, verify_all_with, verify_f64, verify_with, RouteResult, VerificationReport,
+};
 
-struct Args {
-    solution_path: PathBuf,
-    instances_location: PathBuf,
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
 }
 
-impl Args {
-    fn from_env() -> Option<Self> {
-        let args: Vec<_> = env::args().collect();
-        if args.len() == 1 {
-            None
-        } else {
-            Some(Args {
-                solution_path: PathBuf::from(&args[1]),
-                instances_location: PathBuf::from(if args.len() < 3 { "." } else { &args[2] }),
-            })
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// verify a single solution
+    Verify(VerifyArgs),
+    /// verify every solution matching a glob pattern
+    Batch(BatchArgs),
+    /// print instance statistics
+    Info(InfoArgs),
+    /// print per route breakdown of a solution
+    Stats(StatsArgs),
+    /// generate a random instance
+    Generate(GenerateArgs),
+    /// write binary copies of all instances in a directory for faster loading
+    Cache(CacheArgs),
+}
+
+#[derive(Args, Debug)]
+struct VerifyArgs {
+    /// path to the solution
+    solution: PathBuf,
+
+    /// path to the directory containing instances or to the instance itself
+    #[arg(short, long, default_value = ".")]
+    instance_dir: PathBuf,
+
+    /// print the result as json
+    #[arg(long)]
+    json: bool,
+
+    /// print per route results
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// use f64 arithmetic instead of rug, always with the instance distance mode
+    #[arg(long, conflicts_with_all = ["distance_mode", "precision"])]
+    fast: bool,
+
+    /// euclidean, manhattan or asymmetric
+    #[arg(long)]
+    distance_mode: Option<DistanceMode>,
+
+    /// fast, standard, high or number of bits
+    #[arg(long)]
+    precision: Option<Precision>,
+
+    /// format of the solution file, ortools-json solutions need the instance file passed with -i,
+    /// vrprep solutions need the vrprep instance file passed with -i
+    #[arg(long, value_enum, default_value_t)]
+    format: SolutionFormat,
+
+    /// write the route graph in graphviz format to this file, and render it to png if `dot' is installed;
+    /// for a .png file the graph is written to the same path with a .dot extension
+    #[arg(long)]
+    dot_output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum SolutionFormat {
+    #[default]
+    Sintef,
+    OrtoolsJson,
+    Vrprep,
+}
+
+#[derive(Args, Debug)]
+struct BatchArgs {
+    /// glob pattern matching the solutions, paths are read from stdin when missing or `-'
+    solutions: Option<String>,
+
+    /// path to the directory containing instances
+    #[arg(short, long)]
+    instance_dir: PathBuf,
+
+    /// write the results to this file instead of stdout
+    #[arg(long)]
+    csv_out: Option<PathBuf>,
+
+    /// print the results as json instead of csv
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct InfoArgs {
+    /// path to the instance
+    instance: PathBuf,
+
+    /// print detailed statistics of the customers
+    #[arg(long)]
+    stats: bool,
+}
+
+#[derive(Args, Debug)]
+struct StatsArgs {
+    /// path to the solution
+    solution: PathBuf,
+
+    /// path to the instance
+    instance: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    #[arg(long, default_value_t = 100)]
+    customers: usize,
+
+    #[arg(long, default_value_t = 25)]
+    vehicles: usize,
+
+    #[arg(long, default_value_t = 200)]
+    capacity: i32,
+
+    /// smallest coordinate
+    #[arg(long, default_value_t = 0)]
+    min_coordinate: i32,
+
+    /// largest coordinate
+    #[arg(long, default_value_t = 100)]
+    max_coordinate: i32,
+
+    /// due time of the depot
+    #[arg(long, default_value_t = 1000)]
+    horizon: i32,
+
+    #[arg(long, default_value_t = 60)]
+    window_width: i32,
+
+    /// from 0.0 (windows as wide as possible) to 1.0 (windows of the given width)
+    #[arg(long, default_value_t = 0.5)]
+    tightness: f64,
+
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// generate pickup and delivery pairs
+    #[arg(long)]
+    pdp: bool,
+
+    /// write the instance to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct CacheArgs {
+    /// path to the directory containing instances
+    instance_dir: PathBuf,
+}
+
+fn exit_code(err: &VerifyError) -> ExitCode {
+    match err {
+        VerifyError::Io(_) => ExitCode::from(3),
+        VerifyError::ParseError(_) | VerifyError::InvalidInstance(_) => ExitCode::from(2),
+        _ => ExitCode::from(1),
+    }
+}
+
+fn read_input(
+    solution: &Path,
+    instances: &Path,
+    format: SolutionFormat,
+) -> Result<(Solution, Instance), VerifyError> {
+    match format {
+        SolutionFormat::Sintef => verifier::read_solution_and_instance(solution, instances),
+        SolutionFormat::OrtoolsJson => {
+            let text = std::fs::read_to_string(solution)
+                .map_err(|e| VerifyError::Io(format!("{}: {e}", solution.display())))?;
+            let sol = Solution::from_ortools_json(&text)?;
+            let inst = verifier::read_instance_for(&sol, instances)?;
+            Ok((sol, inst))
+        }
+        SolutionFormat::Vrprep => {
+            let read = |path: &Path| {
+                std::fs::read_to_string(path)
+                    .map_err(|e| VerifyError::Io(format!("{}: {e}", path.display())))
+            };
+            let sol = vrprep::parse_solution(&read(solution)?)?;
+            let inst = vrprep::parse_instance(&read(instances)?)?;
+            Ok((sol, inst))
         }
     }
 }
 
-fn usage() {
-    println!("verifier path_to_solution [path_to_instance_directory|path_to_instance]");
+fn check(
+    solution: &Path,
+    instances: &Path,
+    format: SolutionFormat,
+    mode: Option<DistanceMode>,
+    prec: Option<Precision>,
+) -> Result<(Solution, VerificationReport), VerifyError> {
+    let (sol, inst) = read_input(solution, instances, format)?;
+    let mode = mode.unwrap_or(inst.distance_mode());
+    let report = verify_with(&inst, &sol, mode, prec.unwrap_or_default())?;
+    Ok((sol, report))
+}
+
+// an output ending in .png is the rendered image, the graph is written next to it
+fn write_dot(sol: &Solution, inst: &Instance, path: &Path) -> Result<(), VerifyError> {
+    let (dot, png) = if path.extension().is_some_and(|ext| ext == "png") {
+        (path.with_extension("dot"), path.to_path_buf())
+    } else {
+        (path.to_path_buf(), path.with_extension("png"))
+    };
+    std::fs::write(&dot, sol.to_dot(inst))
+        .map_err(|e| VerifyError::Io(format!("{}: {e}", dot.display())))?;
+
+    match std::process::Command::new("dot")
+        .arg("-Tpng")
+        .arg(&dot)
+        .arg("-o")
+        .arg(&png)
+        .status()
+    {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => eprintln!("could not run dot: {e}"),
+        Ok(status) if !status.success() => eprintln!("dot failed with {status}"),
+        Ok(_) => {}
+    }
+    Ok(())
 }
 
-fn main() -> Result<(), String> {
-    let args = Args::from_env();
-    match args {
-        None => {
-            usage();
-            Err("Not enough arguments".to_string())
+// empty for routes without slack times
+fn min_slack(r: &RouteResult) -> String {
+    r.min_slack().map_or(String::new(), |s| s.to_string())
+}
+
+fn print_routes(report: &VerificationReport) {
+    for r in report.route_results.iter() {
+        println!(
+            "route {}: {} points, distance {}, max load {}, min slack {}",
+            r.route_id,
+            r.arrival_times.len(),
+            r.distance,
+            r.max_load,
+            min_slack(r)
+        );
+    }
+}
+
+#[derive(Serialize)]
+struct RouteJson {
+    route_id: usize,
+    distance: String,
+    max_load: i32,
+    time_slack: String,
+}
+
+#[derive(Serialize)]
+struct VerificationJson {
+    instance_name: String,
+    routes: usize,
+    distance: Option<String>,
+    feasible: bool,
+    errors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    route_results: Option<Vec<RouteJson>>,
+}
+
+fn verification_json(args: &VerifyArgs) -> Result<VerificationJson, VerifyError> {
+    let (sol, inst) = read_input(&args.solution, &args.instance_dir, args.format)?;
+    if let Some(path) = &args.dot_output {
+        write_dot(&sol, &inst, path)?;
+    }
+
+    let mut json = VerificationJson {
+        instance_name: sol.instance_name.clone(),
+        routes: sol.routes.len(),
+        distance: None,
+        feasible: false,
+        errors: vec![],
+        route_results: None,
+    };
+
+    if args.fast {
+        match verify_f64(&inst, &sol) {
+            Ok(distance) => json.distance = Some(distance.to_string()),
+            Err(err) => json.errors.push(err.in_context(&sol)),
         }
-        Some(args) =>  {
-            let (sol, res) = verifier::check_sintef_file(&args.solution_path, &args.instances_location)?;
+        json.feasible = json.errors.is_empty();
+        return Ok(json);
+    }
+
+    let mode = args.distance_mode.unwrap_or(inst.distance_mode());
+    let prec = args.precision.unwrap_or_default();
+
+    let (distance, errors) = verify_all_with(&inst, &sol, mode, prec)?;
+    json.distance = distance.map(|d| d.to_string());
+    json.errors = errors.iter().map(|e| e.in_context(&sol)).collect();
+    json.feasible = errors.is_empty();
+
+    if json.feasible && args.verbose {
+        let report = verify_with(&inst, &sol, mode, prec)?;
+        json.route_results = Some(
+            report
+                .route_results
+                .iter()
+                .map(|r| RouteJson {
+                    route_id: r.route_id,
+                    distance: r.distance.to_string(),
+                    max_load: r.max_load,
+                    time_slack: min_slack(r),
+                })
+                .collect(),
+        );
+    }
+
+    Ok(json)
+}
+
+fn run_verify_json(args: VerifyArgs) -> ExitCode {
+    match verification_json(&args) {
+        Err(err) => {
+            eprintln!(
+                "{}",
+                serde_json::json!({ "feasible": false, "errors": [err.to_string()] })
+            );
+            exit_code(&err)
+        }
+        Ok(json) if json.feasible => {
+            println!("{}", serde_json::to_string(&json).unwrap());
+            ExitCode::SUCCESS
+        }
+        Ok(json) => {
+            eprintln!("{}", serde_json::to_string(&json).unwrap());
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn run_verify(args: VerifyArgs) -> Result<(), VerifyError> {
+    let (sol, inst) = read_input(&args.solution, &args.instance_dir, args.format)?;
+    if let Some(path) = &args.dot_output {
+        write_dot(&sol, &inst, path)?;
+    }
+
+    if args.fast {
+        let distance = verify_f64(&inst, &sol)?;
+        println!("{} {} {}", sol.instance_name, sol.routes.len(), distance);
+        return Ok(());
+    }
+
+    let mode = args.distance_mode.unwrap_or(inst.distance_mode());
+    let report = verify_with(&inst, &sol, mode, args.precision.unwrap_or_default())?;
+
+    println!(
+        "{} {} {}",
+        sol.instance_name,
+        sol.routes.len(),
+        report.total_distance
+    );
+    if args.verbose {
+        print_routes(&report);
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct BatchRow {
+    file: String,
+    instance: String,
+    routes: Option<usize>,
+    distance: Option<String>,
+    status: &'static str,
+    error: String,
+}
+
+impl BatchRow {
+    fn to_csv(&self) -> String {
+        [
+            csv_field(&self.file),
+            csv_field(&self.instance),
+            self.routes.map(|r| r.to_string()).unwrap_or_default(),
+            self.distance.clone().unwrap_or_default(),
+            self.status.to_string(),
+            csv_field(&self.error),
+        ]
+        .join(",")
+    }
+}
+
+fn batch_paths(solutions: &Option<String>) -> Result<Vec<PathBuf>, VerifyError> {
+    match solutions.as_deref() {
+        None | Some("-") => Ok(std::io::stdin()
+            .lines()
+            .map(|l| l.map_err(|e| VerifyError::Io(e.to_string())))
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(PathBuf::from)
+            .collect()),
+        Some(pattern) => glob::glob(pattern)
+            .map_err(|e| VerifyError::ParseError(format!("invalid pattern: {e}")))?
+            .map(|p| p.map_err(|e| VerifyError::Io(e.to_string())))
+            .collect(),
+    }
+}
+
+fn run_batch(args: BatchArgs) -> Result<(), VerifyError> {
+    let mut rows = vec![];
+    let mut best: Option<rug::Float> = None;
+    let mut worst: Option<rug::Float> = None;
+
+    for path in batch_paths(&args.solutions)? {
+        let mut row = BatchRow {
+            file: path.display().to_string(),
+            instance: String::new(),
+            routes: None,
+            distance: None,
+            status: "error",
+            error: String::new(),
+        };
+
+        let verified = verifier::read_solution_and_instance(&path, &args.instance_dir).and_then(
+            |(sol, inst)| {
+                row.instance = sol.instance_name.clone();
+                row.routes = Some(sol.routes.len());
+                row.status = "infeasible";
+                verify(&inst, &sol)
+            },
+        );
+
+        match verified {
+            Ok(report) => {
+                let distance = report.total_distance;
+                if best.as_ref().is_none_or(|b| distance < *b) {
+                    best = Some(distance.clone());
+                }
+                if worst.as_ref().is_none_or(|w| distance > *w) {
+                    worst = Some(distance.clone());
+                }
+                row.distance = Some(distance.to_string());
+                row.status = "ok";
+            }
+            Err(err) => row.error = err.to_string(),
+        }
+        rows.push(row);
+    }
+
+    let out = if args.json {
+        serde_json::to_string_pretty(&rows).unwrap() + "\n"
+    } else {
+        let mut out = "file,instance,routes,distance,status,error\n".to_string();
+        for row in rows.iter() {
+            out += &row.to_csv();
+            out += "\n";
+        }
+        out
+    };
+
+    match &args.csv_out {
+        None => print!("{out}"),
+        Some(path) => File::create(path)
+            .and_then(|mut f| f.write_all(out.as_bytes()))
+            .map_err(|e| VerifyError::Io(format!("{}: {e}", path.display())))?,
+    }
+
+    let passed = rows.iter().filter(|r| r.status == "ok").count();
+    eprintln!("passed: {passed}");
+    eprintln!("failed: {}", rows.len() - passed);
+    if let (Some(best), Some(worst)) = (best, worst) {
+        eprintln!("best distance: {best}");
+        eprintln!("worst distance: {worst}");
+    }
+    Ok(())
+}
+
+fn run_info(args: InfoArgs) -> Result<(), VerifyError> {
+    let inst = verifier::read::<Instance>(&args.instance)?;
+    let customers = &inst.pts[1..];
+
+    println!("name: {}", inst.name);
+    println!("customers: {}", customers.len());
+    println!("vehicles: {}", inst.vehicles);
+    println!("capacity: {}", inst.max_capacity);
+    println!("pdp: {}", inst.is_pdp);
+    println!("distance mode: {}", inst.distance_mode());
+    println!(
+        "total demand: {}",
+        customers
+            .iter()
+            .filter(|pt| pt.demand > 0)
+            .map(|pt| pt.demand)
+            .sum::<i32>()
+    );
+    println!("horizon: {} - {}", inst.pts[0].start, inst.pts[0].due);
+    println!(
+        "total service time: {}",
+        customers.iter().map(|pt| pt.service).sum::<i32>()
+    );
+
+    if args.stats {
+        let stats = inst.statistics();
+        println!("average demand: {}", stats.avg_demand);
+        println!("max demand: {}", stats.max_demand);
+        println!("average time window width: {}", stats.avg_time_window_width);
+        println!("min time window width: {}", stats.min_time_window_width);
+        println!(
+            "customers with tight windows: {}",
+            stats.customers_with_tight_windows
+        );
+        println!("pdp pairs: {}", stats.num_pdp_pairs);
+        println!("centroid: {} {}", stats.centroid.0, stats.centroid.1);
+        println!("max coordinate span: {}", stats.max_coordinate_span);
+    }
+    Ok(())
+}
+
+fn run_stats(args: StatsArgs) -> Result<(), VerifyError> {
+    let (sol, report) = check(
+        &args.solution,
+        &args.instance,
+        SolutionFormat::Sintef,
+        None,
+        None,
+    )?;
+    println!(
+        "{} {} {}",
+        sol.instance_name,
+        sol.routes.len(),
+        report.total_distance
+    );
+    print_routes(&report);
+    Ok(())
+}
+
+fn run_generate(args: GenerateArgs) -> Result<(), VerifyError> {
+    let inst = InstanceGenerator {
+        num_customers: args.customers,
+        num_vehicles: args.vehicles,
+        capacity: args.capacity,
+        coordinate_range: (args.min_coordinate, args.max_coordinate),
+        time_horizon: args.horizon,
+        avg_window_width: args.window_width,
+        window_tightness: args.tightness,
+        seed: args.seed,
+        pdp: args.pdp,
+    }
+    .generate()?;
+
+    match &args.output {
+        None => print!("{inst}"),
+        Some(path) => File::create(path)
+            .and_then(|mut f| write!(f, "{inst}"))
+            .map_err(|e| VerifyError::Io(format!("{}: {e}", path.display())))?,
+    }
+    Ok(())
+}
+
+fn run_cache(args: CacheArgs) -> Result<(), VerifyError> {
+    let io_err =
+        |path: &Path, e: std::io::Error| VerifyError::Io(format!("{}: {e}", path.display()));
+    let mut cached = 0;
+
+    for entry in args
+        .instance_dir
+        .read_dir()
+        .map_err(|e| io_err(&args.instance_dir, e))?
+    {
+        let path = entry.map_err(|e| io_err(&args.instance_dir, e))?.path();
+        if path.extension().is_some_and(|ext| ext == "bin") {
+            continue;
+        }
+
+        let written = verifier::read::<Instance>(&path).and_then(|inst| {
+            let sidecar = verifier::binary_sidecar(&path);
+            std::fs::write(&sidecar, inst.to_binary()).map_err(|e| io_err(&sidecar, e))
+        });
+        match written {
+            Ok(()) => cached += 1,
+            Err(err) => eprintln!("{}: {err}", path.display()),
+        }
+    }
+
+    eprintln!("cached {cached} instances");
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let res = match cli.command {
+        Command::Verify(args) if args.json => return run_verify_json(args),
+        Command::Verify(args) => run_verify(args),
+        Command::Batch(args) => run_batch(args),
+        Command::Info(args) => run_info(args),
+        Command::Stats(args) => run_stats(args),
+        Command::Generate(args) => run_generate(args),
+        Command::Cache(args) => run_cache(args),
+    };
 
-            println!("{} {} {}", sol.instance_name, sol.routes.len(), res);
-            Ok(())
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            exit_code(&err)
         }
     }
 }
@@ -0,0 +1,15 @@
+// distances and times are rug floats, of the standard precision unless it is
+// given
+use crate::instance::Precision;
+
+pub fn fl(val: i32) -> rug::Float {
+    fl_prec(val, Precision::STANDARD.0)
+}
+
+pub fn flf64(val: f64) -> rug::Float {
+    rug::Float::with_val(Precision::STANDARD.0, val)
+}
+
+pub fn fl_prec(val: i32, prec: u32) -> rug::Float {
+    rug::Float::with_val(prec, val)
+}
@@ -1,100 +1,420 @@
+pub mod binary;
+pub mod diff;
+pub mod dot;
+pub mod error;
+pub mod geo;
 pub mod instance;
+pub mod report;
+pub mod route_checker;
 pub mod solution;
-use instance::{fl, Instance};
+pub mod svg;
+use crate::numeric::flf64;
+pub use error::VerifyError;
+use geo::GeoInstance;
+use instance::{
+    DistanceMatrix, DistanceMode, DistanceSource, Distances, Instance, Precision, Rounded,
+    RoundingMode,
+};
 use itertools::Itertools;
-use solution::Solution;
+pub use report::{RankedSolution, RouteResult, SoftVerificationResult, VerificationReport};
+pub use route_checker::RouteChecker;
+use solution::{MultiTripSolution, Solution};
+use std::borrow::Cow;
 
-pub fn calc_route_distance(inst: &Instance, route: &Vec<usize>) -> rug::Float {
-    let depot = &inst.pts[0];
-    let first = &inst.pts[route[0]];
+pub fn calc_route_distance(
+    inst: &Instance,
+    route: &[usize],
+    mode: DistanceMode,
+) -> Result<rug::Float, VerifyError> {
+    let mut errors = vec![];
+    let (depot, route) = collect_route_depot(inst, None, 0, route, &mut errors);
+    first_error(errors)?;
+    Ok(route_distance(inst, &inst.with_mode(mode)?, depot, route))
+}
+
+// routes of multi-depot instances start and end at their depot and the points
+// in between are the customers, depots found at the ends are stripped even when
+// they are wrong so that the rest of the route can still be checked; open
+// routes may end at their last customer
+fn collect_route_depot<'a>(
+    inst: &Instance,
+    expected: Option<usize>,
+    route_id: usize,
+    route: &'a [usize],
+    errors: &mut Vec<VerifyError>,
+) -> (usize, &'a [usize]) {
+    // an empty route has no ends to look at
+    if route.is_empty() {
+        errors.push(VerifyError::EmptyRoute { route_id });
+        return (expected.unwrap_or(inst.depots()[0]), route);
+    }
+    if inst.depots.is_empty() {
+        return (0, route);
+    }
+
+    let (start, end) = (route[0], *route.last().unwrap());
+    let depot = expected.unwrap_or(start);
+    let ends_at_depot = end == depot || (inst.open_routes && !inst.is_depot(end));
+    let min_len = if end == depot { 3 } else { 2 };
+    if start != depot || !ends_at_depot || route.len() < min_len || !inst.is_depot(depot) {
+        errors.push(VerifyError::WrongDepot {
+            route_id,
+            depot,
+            start,
+            end,
+        });
+    }
+
+    let from = usize::from(inst.is_depot(start));
+    let to = (route.len() - usize::from(inst.is_depot(end))).max(from);
+    (depot, &route[from..to])
+}
+
+// without a solution the route id decides which vehicle serves the route
+fn route_depot<'a>(
+    inst: &Instance,
+    route_id: usize,
+    route: &'a [usize],
+) -> Result<(RouteVehicle, &'a [usize]), VerifyError> {
+    let mut errors = vec![];
+    let vehicle = route_id - 1;
+    let expected = inst.vehicle_depot(vehicle);
+    let (depot, route) = collect_route_depot(inst, expected, route_id, route, &mut errors);
+    first_error(errors)?;
+    Ok((RouteVehicle { vehicle, depot }, route))
+}
+
+// the vehicle serving a route and the depot it starts and ends at
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RouteVehicle {
+    vehicle: usize,
+    depot: usize,
+}
+
+impl RouteVehicle {
+    fn capacity(self, inst: &Instance) -> i32 {
+        inst.vehicle_capacity(self.vehicle, self.depot)
+    }
+}
+
+// routes use vehicles in order unless the solution assigns them explicitly
+pub(crate) fn route_vehicle_ids(sol: &Solution) -> Vec<usize> {
+    (0..sol.routes.len())
+        .map(|i| {
+            sol.vehicle_assignments
+                .as_ref()
+                .and_then(|a| a.get(i))
+                .copied()
+                .unwrap_or(i)
+        })
+        .collect()
+}
+
+// the solution with depots stripped from the routes and the vehicle of every route
+pub(crate) fn collect_vehicles<'a>(
+    inst: &Instance,
+    sol: &'a Solution,
+    errors: &mut Vec<VerifyError>,
+) -> (Cow<'a, Solution>, Vec<RouteVehicle>) {
+    let vehicle_ids = route_vehicle_ids(sol);
+
+    if inst.depots.is_empty() {
+        let vehicles = vehicle_ids
+            .into_iter()
+            .map(|vehicle| RouteVehicle { vehicle, depot: 0 })
+            .collect();
+        return (Cow::Borrowed(sol), vehicles);
+    }
+
+    let (vehicles, routes) = sol
+        .routes
+        .iter()
+        .zip(vehicle_ids)
+        .enumerate()
+        .map(|(i, (route, vehicle))| {
+            let expected = inst.vehicle_depot(vehicle);
+            let (depot, route) = collect_route_depot(inst, expected, i + 1, route, errors);
+            (RouteVehicle { vehicle, depot }, route.to_vec())
+        })
+        .unzip();
+
+    let sol = Solution {
+        routes,
+        ..sol.clone()
+    };
+    (Cow::Owned(sol), vehicles)
+}
+
+fn split_vehicles<'a>(
+    inst: &Instance,
+    sol: &'a Solution,
+) -> Result<(Cow<'a, Solution>, Vec<RouteVehicle>), VerifyError> {
+    let mut errors = vec![];
+    let split = collect_vehicles(inst, sol, &mut errors);
+    first_error(errors)?;
+    Ok(split)
+}
 
-    let last_idx = *route.last().unwrap();
-    let last = &inst.pts[last_idx];
+pub(crate) fn route_distance(
+    inst: &Instance,
+    dists: &impl Distances,
+    depot: usize,
+    route: &[usize],
+) -> rug::Float {
+    let first = route[0];
+    let last = *route.last().unwrap();
 
     let route_distance = route
         .iter()
-        .map(|&p| &inst.pts[p])
         .tuple_windows()
-        .map(|(from, to)| from.dist(to))
+        .map(|(&from, &to)| dists.between(from, to))
         .reduce(std::ops::Add::add)
-        .unwrap_or(fl(0));
+        .unwrap_or(dists.precision().fl(0));
+
+    let distance = dists.between(depot, first) + route_distance;
+    if inst.open_routes {
+        distance
+    } else {
+        distance + dists.between(last, depot)
+    }
+}
 
-    depot.dist(first) + route_distance + last.dist(depot)
+fn first_error(errors: Vec<VerifyError>) -> Result<(), VerifyError> {
+    match errors.into_iter().next() {
+        None => Ok(()),
+        Some(err) => Err(err),
+    }
 }
 
 pub fn check_route_time(
     inst: &Instance,
     route_id: usize,
-    route: &Vec<usize>,
-) -> Result<(), String> {
-    let depot = &inst.pts[0];
-    let first = &inst.pts[route[0]];
-    let mut time = fl(depot.start + depot.service);
-    time += depot.dist(first);
+    route: &[usize],
+    mode: DistanceMode,
+) -> Result<(), VerifyError> {
+    let (vehicle, route) = route_depot(inst, route_id, route)?;
+    let mut errors = vec![];
+    collect_route_time(
+        inst,
+        &inst.with_mode(mode)?,
+        vehicle.depot,
+        route_id,
+        route,
+        &mut errors,
+    );
+    first_error(errors)
+}
+
+pub fn collect_route_time(
+    inst: &Instance,
+    dists: &impl Distances,
+    d: usize,
+    route_id: usize,
+    route: &[usize],
+    errors: &mut Vec<VerifyError>,
+) -> (Vec<rug::Float>, Vec<rug::Float>) {
+    let depot = &inst.pts[d];
+    let route_start = dists.precision().fl(depot.start + depot.service);
+    let trip = Trip {
+        depot: d,
+        start: route_start,
+        returns: !inst.open_routes,
+    };
+    let (arrivals, departures, _) = collect_trip_time(inst, dists, trip, route_id, route, errors);
+    (arrivals, departures)
+}
+
+// a trip leaves the depot at `start` and ends when the vehicle is back at the
+// depot, or leaves its last customer when it does not return
+struct Trip {
+    depot: usize,
+    start: rug::Float,
+    returns: bool,
+}
+
+// the end of the trip is returned with the arrivals and departures
+fn collect_trip_time(
+    inst: &Instance,
+    dists: &impl Distances,
+    Trip {
+        depot: d,
+        start: route_start,
+        returns,
+    }: Trip,
+    route_id: usize,
+    route: &[usize],
+    errors: &mut Vec<VerifyError>,
+) -> (Vec<rug::Float>, Vec<rug::Float>, rug::Float) {
+    if route.is_empty() {
+        errors.push(VerifyError::EmptyRoute { route_id });
+        return (vec![], vec![], route_start);
+    }
+
+    let mut arrivals = Vec::with_capacity(route.len());
+    let mut departures = Vec::with_capacity(route.len());
+    let prec = dists.precision();
 
-    if time > first.due as f64 {
-        Err(format!(
-            "arrived too late ({}) at {} in route {} at position 0",
-            time, first.id, route_id,
-        ))?;
+    let depot = &inst.pts[d];
+    let first = &inst.pts[route[0]];
+    let mut time = route_start.clone();
+    time += dists.between(d, route[0]);
+    arrivals.push(time.clone());
+
+    if time > first.due {
+        errors.push(VerifyError::TimeWindowViolation {
+            route_id,
+            position: 0,
+            point_id: first.id,
+            arrival: time.clone(),
+            due: first.due,
+        });
     }
 
-    time = time.max(&fl(first.start));
+    time = time.max(&prec.fl(first.start));
 
     time += first.service;
+    departures.push(time.clone());
 
     for ((_, f), (tidx, t)) in route.iter().enumerate().tuple_windows() {
-        let from = &inst.pts[*f];
         let to = &inst.pts[*t];
 
-        time += from.dist(to);
-
-        if time > to.due as f64 {
-            Err(format!(
-                "arrived too late ({}) at {} in route {} at position {}",
-                time, to.id, route_id, tidx
-            ))?;
+        time += dists.between(*f, *t);
+        arrivals.push(time.clone());
+
+        if time > to.due {
+            errors.push(VerifyError::TimeWindowViolation {
+                route_id,
+                position: tidx,
+                point_id: to.id,
+                arrival: time.clone(),
+                due: to.due,
+            });
         }
 
-        time = time.max(&fl(to.start));
+        time = time.max(&prec.fl(to.start));
         time += to.service;
+        departures.push(time.clone());
     }
 
     let l = *route.last().unwrap();
-    let last = &inst.pts[l];
-    time += last.dist(&depot);
-    if time > depot.due as f64 {
-        Err(format!(
-            "arrived too late ({}) in route {} at depot",
-            time, route_id,
-        ))?;
+    if returns {
+        time += dists.between(l, d);
+        if time > depot.due {
+            errors.push(VerifyError::DepotReturnViolation {
+                route_id,
+                arrival: time.clone(),
+                due: depot.due,
+            });
+        }
     }
 
-    Ok(())
+    if let Some(limit) = inst.max_route_duration {
+        let duration = time.clone() - route_start;
+        if duration > limit {
+            errors.push(VerifyError::RouteDurationExceeded {
+                route_id,
+                duration,
+                limit,
+            });
+        }
+    }
+
+    (arrivals, departures, time)
+}
+
+// the first load violation of a route, `load_history` holds the customer,
+// its demand and the load after it for every customer up to the violation
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    pub position: usize,
+    pub customer_id: i32,
+    pub load: i32,
+    pub capacity: i32,
+    pub load_history: Vec<(usize, i32, i32)>,
+}
+
+// the load after servicing every customer of the route is returned when the
+// check passes, a route that does not start and end at its depot has no load
+// error
+pub fn check_route_load(
+    inst: &Instance,
+    route_id: usize,
+    route: &[usize],
+) -> Result<Vec<i32>, (VerifyError, Option<LoadError>)> {
+    let (vehicle, route) = route_depot(inst, route_id, route).map_err(|e| (e, None))?;
+    let capacity = vehicle.capacity(inst);
+    let mut errors = vec![];
+    let loads = collect_route_load(inst, capacity, route_id, route, &mut errors);
+    let Some(err) = errors.into_iter().next() else {
+        return Ok(loads);
+    };
+
+    let position = match err {
+        VerifyError::CapacityExceeded { position, .. }
+        | VerifyError::NegativeLoad { position, .. } => position,
+        _ => return Err((err, None)),
+    };
+    let load_history = route
+        .iter()
+        .zip(loads)
+        .take(position + 1)
+        .map(|(&c, load)| (c, inst.pts[c].demand, load))
+        .collect::<Vec<_>>();
+    let load = load_history[position].2;
+    Err((
+        err,
+        Some(LoadError {
+            position,
+            customer_id: inst.pts[route[position]].id,
+            load,
+            capacity,
+            load_history,
+        }),
+    ))
 }
 
-fn check_route_load(inst: &Instance, route_id: usize, route: &Vec<usize>) -> Result<(), String> {
+fn collect_route_load(
+    inst: &Instance,
+    capacity: i32,
+    route_id: usize,
+    route: &[usize],
+    errors: &mut Vec<VerifyError>,
+) -> Vec<i32> {
     let mut vehicle_load = 0;
+    let mut loads = Vec::with_capacity(route.len());
     for (p, pt) in route.iter().map(|&p_id| &inst.pts[p_id]).enumerate() {
         vehicle_load += pt.demand;
+        loads.push(vehicle_load);
         if vehicle_load < 0 {
-            Err(format!(
-                "current load is negative at {} in route {} at position {}",
-                pt.id, route_id, p,
-            ))?;
+            errors.push(VerifyError::NegativeLoad {
+                route_id,
+                position: p,
+                point_id: pt.id,
+                load: vehicle_load,
+            });
         }
 
-        if vehicle_load > inst.max_capacity {
-            Err(format!(
-                "load is greater than max load ({} > {}) at {} in route {} at position {}",
-                vehicle_load, inst.max_capacity, pt.id, route_id, p,
-            ))?;
+        if vehicle_load > capacity {
+            errors.push(VerifyError::CapacityExceeded {
+                route_id,
+                position: p,
+                point_id: pt.id,
+                load: vehicle_load,
+                capacity,
+            });
         }
     }
-    Ok(())
+
+    loads
 }
 
-fn check_pdp(inst: &Instance, sol: &Solution) -> Result<(), String> {
+fn check_pdp(inst: &Instance, sol: &Solution) -> Result<(), VerifyError> {
+    let mut errors = vec![];
+    collect_pdp(inst, sol, &mut errors);
+    first_error(errors)
+}
+
+fn collect_pdp(inst: &Instance, sol: &Solution, errors: &mut Vec<VerifyError>) {
     let mut point_route_id = vec![0; inst.pts.len()];
     let mut route_idx = vec![0; inst.pts.len()];
 
@@ -114,201 +434,945 @@ fn check_pdp(inst: &Instance, sol: &Solution) -> Result<(), String> {
             (pt, d as usize)
         };
 
+        // every pair is seen twice, report it only on its first occurrence
+        if pt != pickup.min(delivery) {
+            continue;
+        }
+
         if point_route_id[pickup] != point_route_id[delivery] {
-            Err(format!(
-                "pickup {} and delivery {} are not in the same routes (are in routes {} and {})",
-                pickup, delivery, point_route_id[pickup], point_route_id[delivery],
-            ))?
+            errors.push(VerifyError::PdpSameRoute {
+                pickup,
+                delivery,
+                route_a: point_route_id[pickup],
+                route_b: point_route_id[delivery],
+            });
         }
 
         if route_idx[pickup] > route_idx[delivery] {
-            Err(format!(
-                "delivery {} is before its pickup {} (are on positions {} and {})",
-                delivery, pickup, route_idx[delivery], route_idx[pickup],
-            ))?
+            errors.push(VerifyError::PdpOrdering {
+                pickup,
+                delivery,
+                pickup_pos: route_idx[pickup],
+                delivery_pos: route_idx[delivery],
+            });
         }
     }
+}
 
-    Ok(())
+fn check_pdp_chain(inst: &Instance, sol: &Solution) -> Result<(), VerifyError> {
+    let mut errors = vec![];
+    collect_pdp_chain(inst, sol, &mut errors);
+    first_error(errors)
+}
+
+fn collect_pdp_chain(inst: &Instance, sol: &Solution, errors: &mut Vec<VerifyError>) {
+    let chains = inst
+        .pts
+        .iter()
+        .filter_map(|pt| pt.pickup_delivery_chain.as_ref())
+        .unique()
+        .collect_vec();
+    if chains.is_empty() {
+        return;
+    }
+
+    let mut point_route_id = vec![0; inst.pts.len()];
+    let mut route_idx = vec![0; inst.pts.len()];
+
+    for (route_id, route) in sol.routes.iter().enumerate() {
+        for (i, &p) in route.iter().enumerate() {
+            point_route_id[p] = route_id + 1;
+            route_idx[p] = i;
+        }
+    }
+
+    for chain in chains {
+        for (&before, &after) in chain.iter().tuple_windows() {
+            if point_route_id[before] != point_route_id[after]
+                || route_idx[before] > route_idx[after]
+            {
+                errors.push(VerifyError::PdpChainViolation {
+                    before,
+                    after,
+                    route_a: point_route_id[before],
+                    route_b: point_route_id[after],
+                    before_pos: route_idx[before],
+                    after_pos: route_idx[after],
+                });
+            }
+        }
+    }
+}
+
+fn check_basic_sanity(inst: &Instance, sol: &Solution) -> Result<(), VerifyError> {
+    let mut errors = vec![];
+    collect_basic_sanity(inst, sol, &mut errors);
+    first_error(errors)
+}
+
+fn collect_basic_sanity(inst: &Instance, sol: &Solution, errors: &mut Vec<VerifyError>) {
+    let point_route_id = collect_visits(inst, sol, errors);
+
+    for (pt, visited) in point_route_id.iter().enumerate() {
+        if visited.is_none() {
+            errors.push(VerifyError::MissingVisit { point_id: pt });
+        }
+    }
 }
 
-fn check_basic_sanity(inst: &Instance, sol: &Solution) -> Result<(), String> {
+fn collect_visits(
+    inst: &Instance,
+    sol: &Solution,
+    errors: &mut Vec<VerifyError>,
+) -> Vec<Option<usize>> {
     let mut point_route_id = vec![None; inst.pts.len()];
 
-    point_route_id[0] = Some(0);
+    for depot in inst.depots() {
+        point_route_id[depot] = Some(0);
+    }
 
     for (route_id, route) in sol.routes.iter().enumerate() {
+        // routes that were empty before their depots were stripped are
+        // already reported
+        let empty = VerifyError::EmptyRoute {
+            route_id: route_id + 1,
+        };
+        if route.is_empty() && !errors.contains(&empty) {
+            errors.push(empty);
+        }
+
         for (r, &pt) in route.iter().enumerate() {
-            if pt == 0 {
-                Err(format!(
-                    "route {} visits depot at non-terminal position {}",
-                    route_id + 1,
-                    r
-                ))?;
+            if pt >= point_route_id.len() {
+                errors.push(VerifyError::UnknownPoint {
+                    point_id: pt,
+                    route_id: route_id + 1,
+                    position: r,
+                });
+                continue;
             }
 
-            if pt > point_route_id.len() {
-                Err(format!(
-                    "node {} in route {} at position {} is not described in the instance",
-                    pt,
-                    route_id + 1,
-                    r
-                ))?;
+            if inst.is_depot(pt) {
+                errors.push(VerifyError::DepotVisit {
+                    route_id: route_id + 1,
+                    position: r,
+                    point_id: pt,
+                });
+                continue;
             }
 
             match point_route_id[pt] {
                 None => point_route_id[pt] = Some(route_id + 1),
-                Some(other_route) => Err(format!(
-                    "node {} visited at least two times (in routes {} and {})",
-                    pt,
-                    route_id + 1,
-                    other_route
-                ))?,
+                Some(other_route) => errors.push(VerifyError::DuplicateVisit {
+                    point_id: pt,
+                    route_a: route_id + 1,
+                    route_b: other_route,
+                }),
             }
         }
     }
 
-    for (pt, visited) in point_route_id.iter().enumerate() {
-        if visited.is_none() {
-            Err(format!("node {} not visited in any route", pt,))?;
+    point_route_id
+}
+
+fn check_vehicles(inst: &Instance, sol: &Solution) -> Result<(), VerifyError> {
+    if sol.routes.len() > inst.vehicles as usize {
+        Err(VerifyError::TooManyVehicles {
+            used: sol.routes.len(),
+            allowed: inst.vehicles,
+        })?;
+    }
+
+    if let Some(required) = inst
+        .min_vehicles
+        .filter(|&min| sol.routes.len() < min as usize)
+    {
+        Err(VerifyError::TooFewVehicles {
+            used: sol.routes.len(),
+            required,
+        })?;
+    }
+
+    let Some(assignments) = &sol.vehicle_assignments else {
+        return Ok(());
+    };
+
+    if assignments.len() != sol.routes.len() {
+        Err(VerifyError::VehicleAssignmentLength {
+            assigned: assignments.len(),
+            routes: sol.routes.len(),
+        })?;
+    }
+
+    let mut vehicle_route = vec![None; inst.vehicles as usize];
+    for (route_id, &vehicle) in (1..).zip(assignments) {
+        match vehicle_route.get_mut(vehicle) {
+            None => Err(VerifyError::UnknownVehicle { route_id, vehicle })?,
+            Some(Some(other_route)) => Err(VerifyError::DuplicateVehicle {
+                vehicle,
+                route_a: route_id,
+                route_b: *other_route,
+            })?,
+            Some(assigned) => *assigned = Some(route_id),
         }
     }
 
     Ok(())
 }
 
-pub fn verify(inst: &Instance, sol: &Solution) -> Result<rug::Float, String> {
-    check_basic_sanity(&inst, &sol)?;
+fn route_result(
+    inst: &Instance,
+    dists: &impl Distances,
+    vehicle: RouteVehicle,
+    route_id: usize,
+    route: &[usize],
+) -> Result<RouteResult, VerifyError> {
+    let mut errors = vec![];
+    let (arrival_times, departure_times) =
+        collect_route_time(inst, dists, vehicle.depot, route_id, route, &mut errors);
+    let load_profile =
+        collect_route_load(inst, vehicle.capacity(inst), route_id, route, &mut errors);
+    first_error(errors)?;
+
+    let (slack_times, backward_slack_times) =
+        route_slack(inst, dists, vehicle.depot, route, &arrival_times);
+
+    Ok(RouteResult {
+        route_id,
+        distance: route_distance(inst, dists, vehicle.depot, route),
+        max_load: load_profile.iter().copied().fold(0, i32::max),
+        load_profile,
+        arrival_times,
+        departure_times,
+        slack_times,
+        backward_slack_times,
+    })
+}
+
+// returns the slack of every customer of the route (its due time minus the
+// arrival) and the backward slack, which is how much later the vehicle could
+// arrive without being late at this or any of the following customers or
+// when returning to the depot; late arrivals have negative slacks
+pub fn calc_route_slack(
+    inst: &Instance,
+    route: &[usize],
+) -> Result<(Vec<rug::Float>, Vec<rug::Float>), VerifyError> {
+    let mut errors = vec![];
+    let (depot, route) = collect_route_depot(inst, None, 0, route, &mut errors);
+    first_error(errors)?;
+    let dists = inst.with_mode(inst.distance_mode())?;
+
+    // time window violations only make the slacks negative
+    let (arrivals, _) = collect_route_time(inst, &dists, depot, 0, route, &mut vec![]);
+    Ok(route_slack(inst, &dists, depot, route, &arrivals))
+}
 
-    if inst.is_pdp {
-        check_pdp(&inst, &sol)?;
+fn route_slack(
+    inst: &Instance,
+    dists: &impl Distances,
+    d: usize,
+    route: &[usize],
+    arrivals: &[rug::Float],
+) -> (Vec<rug::Float>, Vec<rug::Float>) {
+    let prec = dists.precision();
+    let slack = route
+        .iter()
+        .zip(arrivals.iter())
+        .map(|(&p, arrival)| prec.fl(inst.pts[p].due) - arrival)
+        .collect();
+
+    let mut backward_slack = vec![prec.fl(0); route.len()];
+    // the latest departure from the current point that keeps the rest of the
+    // route on time
+    let mut latest_departure = match route.last() {
+        Some(&l) if !inst.open_routes => Some(prec.fl(inst.pts[d].due) - dists.between(l, d)),
+        _ => None,
+    };
+    for (i, &p) in route.iter().enumerate().rev() {
+        let pt = &inst.pts[p];
+        let mut latest_arrival = prec.fl(pt.due);
+        if let Some(departure) = latest_departure {
+            latest_arrival = latest_arrival.min(&(departure - pt.service));
+        }
+        backward_slack[i] = latest_arrival.clone() - &arrivals[i];
+        latest_departure = (i > 0).then(|| latest_arrival - dists.between(route[i - 1], p));
     }
 
-    if sol.routes.len() > inst.vehicles as usize {
-        Err(format!(
-            "more vehicles than allowed ({} > {})",
-            sol.routes.len(),
-            inst.vehicles
-        ))?;
+    (slack, backward_slack)
+}
+
+pub fn verify(inst: &Instance, sol: &Solution) -> Result<VerificationReport, VerifyError> {
+    verify_with_mode(inst, sol, inst.distance_mode())
+}
+
+pub fn verify_with_mode(
+    inst: &Instance,
+    sol: &Solution,
+    mode: DistanceMode,
+) -> Result<VerificationReport, VerifyError> {
+    verify_with(inst, sol, mode, Precision::STANDARD)
+}
+
+pub fn verify_with_precision(
+    inst: &Instance,
+    sol: &Solution,
+    prec: Precision,
+) -> Result<VerificationReport, VerifyError> {
+    verify_with(inst, sol, inst.distance_mode(), prec)
+}
+
+pub fn verify_with(
+    inst: &Instance,
+    sol: &Solution,
+    mode: DistanceMode,
+    prec: Precision,
+) -> Result<VerificationReport, VerifyError> {
+    verify_using(inst, &inst.distances_with(mode, prec)?, sol)
+}
+
+pub fn verify_with_rounding(
+    inst: &Instance,
+    sol: &Solution,
+    rounding: RoundingMode,
+) -> Result<VerificationReport, VerifyError> {
+    let dists = inst.distances_with(inst.distance_mode(), Precision::STANDARD)?;
+    verify_using(
+        inst,
+        &Rounded {
+            dists: &dists,
+            rounding,
+        },
+        sol,
+    )
+}
+
+pub fn verify_with_matrix(
+    inst: &Instance,
+    sol: &Solution,
+    matrix: &DistanceMatrix,
+) -> Result<VerificationReport, VerifyError> {
+    if matrix.len() != inst.pts.len() {
+        Err(VerifyError::InvalidInstance(format!(
+            "distance matrix has {} rows but the instance has {} points",
+            matrix.len(),
+            inst.pts.len()
+        )))?;
     }
+    verify_using(inst, matrix, sol)
+}
 
-    let mut total_distance = fl(0);
-    for (route_id, route) in sol.routes.iter().enumerate() {
-        check_route_time(&inst, route_id + 1, &route)?;
-        check_route_load(&inst, route_id + 1, &route)?;
+// names are compared the way solution files write them: lowercase, without
+// whitespace; an empty name matches anything
+fn check_instance_name(inst: &Instance, sol: &Solution) -> Result<(), VerifyError> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let (sol_name, inst_name) = (normalize(&sol.instance_name), normalize(&inst.name));
+    if !sol_name.is_empty() && !inst_name.is_empty() && sol_name != inst_name {
+        Err(VerifyError::InstanceNameMismatch {
+            solution: sol.instance_name.clone(),
+            instance: inst.name.clone(),
+        })?;
+    }
+    Ok(())
+}
+
+fn verify_using(
+    inst: &Instance,
+    dists: &impl Distances,
+    sol: &Solution,
+) -> Result<VerificationReport, VerifyError> {
+    check_instance_name(inst, sol)?;
+    verify_unchecked_using(inst, dists, sol)
+}
+
+// like `verify`, but does not check that the solution is for this instance
+pub fn verify_unchecked(
+    inst: &Instance,
+    sol: &Solution,
+) -> Result<VerificationReport, VerifyError> {
+    verify_unchecked_using(inst, &inst.with_mode(inst.distance_mode())?, sol)
+}
+
+fn verify_unchecked_using(
+    inst: &Instance,
+    dists: &impl Distances,
+    sol: &Solution,
+) -> Result<VerificationReport, VerifyError> {
+    let (sol, vehicles) = split_vehicles(inst, sol)?;
+    check_basic_sanity(inst, &sol)?;
 
-        total_distance += calc_route_distance(inst, &route);
+    if inst.is_pdp {
+        check_pdp(inst, &sol)?;
     }
+    check_pdp_chain(inst, &sol)?;
 
-    Ok(total_distance)
+    check_vehicles(inst, &sol)?;
+
+    let route_results = sol
+        .routes
+        .iter()
+        .enumerate()
+        .map(|(route_id, route)| route_result(inst, dists, vehicles[route_id], route_id + 1, route))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(VerificationReport::new(route_results))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use instance::Point;
+// pdp pairs are not checked, as a partial solution may contain only one node
+// of a pair
+pub fn verify_partial(
+    inst: &Instance,
+    sol: &Solution,
+    covered: &[usize],
+) -> Result<rug::Float, VerifyError> {
+    let (sol, vehicles) = split_vehicles(inst, sol)?;
+    let mut errors = vec![];
+    let point_route_id = collect_visits(inst, &sol, &mut errors);
+    for &pt in covered {
+        if point_route_id.get(pt).is_none_or(Option::is_none) {
+            errors.push(VerifyError::MissingVisit { point_id: pt });
+        }
+    }
+    first_error(errors)?;
 
-    fn setup() -> Instance {
-        let inst = Instance {
-            name: "test".to_string(),
-            is_pdp: false,
-            vehicles: 3,
-            max_capacity: 10,
-            pts: vec![
-                Point {
-                    id: 0,
-                    x: 0,
-                    y: 0,
-                    demand: 0,
-                    start: 0,
-                    due: 48,
-                    service: 0,
-                    pickup_delivery: None,
-                },
-                Point {
-                    id: 1,
-                    x: 0,
-                    y: 1,
-                    demand: 2,
-                    start: 0,
-                    due: 10,
-                    service: 10,
-                    pickup_delivery: None,
-                },
-                Point {
-                    id: 2,
-                    x: 1,
-                    y: 1,
-                    demand: 2,
-                    start: 0,
-                    due: 3600,
-                    service: 10,
-                    pickup_delivery: None,
-                },
-                Point {
-                    id: 3,
-                    x: 1,
-                    y: 0,
-                    demand: 2,
-                    start: 0,
-                    due: 3600,
-                    service: 10,
-                    pickup_delivery: None,
-                },
-                Point {
-                    id: 4,
-                    x: 0,
-                    y: -1,
-                    demand: 2,
-                    start: 0,
-                    due: 3600,
-                    service: 10,
-                    pickup_delivery: None,
-                },
-                Point {
-                    id: 5,
-                    x: -1,
-                    y: -1,
-                    demand: 2,
-                    start: 0,
-                    due: 3600,
-                    service: 10,
-                    pickup_delivery: None,
-                },
-                Point {
-                    id: 6,
-                    x: -1,
-                    y: 0,
-                    demand: 2,
-                    start: 0,
-                    due: 3600,
-                    service: 10,
-                    pickup_delivery: None,
-                },
-            ],
-        };
-        assert_eq!(inst.check_sanity(), Ok(()));
-        inst
+    check_vehicles(inst, &sol)?;
+
+    let dists = inst.with_mode(inst.distance_mode())?;
+    let mut total_distance = dists.precision().fl(0);
+    for (route_id, route) in sol.routes.iter().enumerate() {
+        total_distance +=
+            route_result(inst, &dists, vehicles[route_id], route_id + 1, route)?.distance;
     }
 
-    #[test]
-    fn verify_correct() {
-        let inst = setup();
+    Ok(total_distance)
+}
 
-        let res = verify(
-            &inst,
-            &Solution {
-                routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
-                ..Default::default()
-            },
-        );
+// with soft windows late arrivals at customers add to the penalty instead of
+// being errors, every other constraint (including the depot due time) is hard
+pub fn verify_soft(inst: &Instance, sol: &Solution) -> Result<SoftVerificationResult, VerifyError> {
+    let (sol, vehicles) = split_vehicles(inst, sol)?;
+    check_basic_sanity(inst, &sol)?;
 
-        assert_eq!(res, Ok(fl(8)));
+    if inst.is_pdp {
+        check_pdp(inst, &sol)?;
     }
+    check_pdp_chain(inst, &sol)?;
 
-    #[test]
-    fn test_check_basic_sanity_errors() {
-        let inst = setup();
+    check_vehicles(inst, &sol)?;
+
+    let dists = inst.with_mode(inst.distance_mode())?;
+    let mut result = SoftVerificationResult {
+        distance: dists.precision().fl(0),
+        penalty: 0.0,
+        penalty_violations: 0,
+    };
+    for (route_id, route) in sol.routes.iter().enumerate() {
+        let vehicle = vehicles[route_id];
+        let mut errors = vec![];
+        collect_route_time(
+            inst,
+            &dists,
+            vehicle.depot,
+            route_id + 1,
+            route,
+            &mut errors,
+        );
+        collect_route_load(
+            inst,
+            vehicle.capacity(inst),
+            route_id + 1,
+            route,
+            &mut errors,
+        );
+
+        for err in errors {
+            match err {
+                VerifyError::TimeWindowViolation { arrival, due, .. } if inst.soft_windows => {
+                    result.penalty += (arrival - due).to_f64() * inst.penalty_per_unit;
+                    result.penalty_violations += 1;
+                }
+                err => Err(err)?,
+            }
+        }
+
+        result.distance += route_distance(inst, &dists, vehicle.depot, route);
+    }
+
+    Ok(result)
+}
+
+// route ids in errors are 1-based positions of trips when all trips of all
+// vehicles are put one after another; a vehicle reloads at the depot between
+// its trips, which takes the depot service time
+pub fn verify_multitrip(
+    inst: &Instance,
+    sol: &MultiTripSolution,
+) -> Result<rug::Float, VerifyError> {
+    if sol.vehicle_routes.len() > inst.vehicles as usize {
+        Err(VerifyError::TooManyVehicles {
+            used: sol.vehicle_routes.len(),
+            allowed: inst.vehicles,
+        })?;
+    }
+
+    if let Some(required) = inst
+        .min_vehicles
+        .filter(|&min| sol.vehicle_routes.len() < min as usize)
+    {
+        Err(VerifyError::TooFewVehicles {
+            used: sol.vehicle_routes.len(),
+            required,
+        })?;
+    }
+
+    let trips = sol.to_solution();
+    let (trips, vehicles) = split_vehicles(inst, &trips)?;
+    check_basic_sanity(inst, &trips)?;
+
+    if inst.is_pdp {
+        check_pdp(inst, &trips)?;
+    }
+    check_pdp_chain(inst, &trips)?;
+
+    let dists = inst.with_mode(inst.distance_mode())?;
+    let mut total_distance = dists.precision().fl(0);
+    let mut errors = vec![];
+    let mut previous: Option<(usize, rug::Float)> = None;
+    for (i, (route, &vehicle)) in trips.routes.iter().zip(&vehicles).enumerate() {
+        let route_id = i + 1;
+        let depot = &inst.pts[vehicle.depot];
+        let leave = match previous {
+            Some((v, back)) if v == vehicle.vehicle => back + depot.service,
+            _ => dists.precision().fl(depot.start + depot.service),
+        };
+        // on open routes only the last trip of a vehicle does not return
+        let next_trip = vehicles
+            .get(i + 1)
+            .is_some_and(|next| next.vehicle == vehicle.vehicle);
+        let trip = Trip {
+            depot: vehicle.depot,
+            start: leave,
+            returns: !inst.open_routes || next_trip,
+        };
+
+        let (_, _, back) = collect_trip_time(inst, &dists, trip, route_id, route, &mut errors);
+        collect_route_load(inst, vehicle.capacity(inst), route_id, route, &mut errors);
+        total_distance += route_distance(inst, &dists, vehicle.depot, route);
+        if inst.open_routes && next_trip {
+            total_distance += dists.between(*route.last().unwrap(), vehicle.depot);
+        }
+        previous = Some((vehicle.vehicle, back));
+    }
+    first_error(errors)?;
+
+    Ok(total_distance)
+}
+
+pub fn verify_geo(geo: &GeoInstance, sol: &Solution) -> Result<VerificationReport, VerifyError> {
+    verify_using(&geo.inst, geo, sol)
+}
+
+pub fn verify_all(inst: &Instance, sol: &Solution) -> (Option<rug::Float>, Vec<VerifyError>) {
+    verify_all_using(inst, inst, sol)
+}
+
+pub fn verify_all_with(
+    inst: &Instance,
+    sol: &Solution,
+    mode: DistanceMode,
+    prec: Precision,
+) -> Result<(Option<rug::Float>, Vec<VerifyError>), VerifyError> {
+    Ok(verify_all_using(
+        inst,
+        &inst.distances_with(mode, prec)?,
+        sol,
+    ))
+}
+
+pub fn verify_all_geo(geo: &GeoInstance, sol: &Solution) -> (Option<rug::Float>, Vec<VerifyError>) {
+    verify_all_using(&geo.inst, geo, sol)
+}
+
+fn verify_all_using(
+    inst: &Instance,
+    dists: &impl Distances,
+    sol: &Solution,
+) -> (Option<rug::Float>, Vec<VerifyError>) {
+    let mut errors = vec![];
+
+    let (sol, vehicles) = collect_vehicles(inst, sol, &mut errors);
+    collect_basic_sanity(inst, &sol, &mut errors);
+    let sane = errors.is_empty();
+
+    // pdp pairs can only be matched when every node is visited exactly once
+    if sane && inst.is_pdp {
+        collect_pdp(inst, &sol, &mut errors);
+    }
+    if sane {
+        collect_pdp_chain(inst, &sol, &mut errors);
+    }
+
+    if let Err(err) = check_vehicles(inst, &sol) {
+        errors.push(err);
+    }
+
+    let mut total_distance = dists.precision().fl(0);
+    for (route_id, route) in sol.routes.iter().enumerate() {
+        if route.is_empty() || route.iter().any(|&pt| pt >= inst.pts.len()) {
+            continue;
+        }
+
+        let vehicle = vehicles[route_id];
+        collect_route_time(inst, dists, vehicle.depot, route_id + 1, route, &mut errors);
+        collect_route_load(
+            inst,
+            vehicle.capacity(inst),
+            route_id + 1,
+            route,
+            &mut errors,
+        );
+
+        total_distance += route_distance(inst, dists, vehicle.depot, route);
+    }
+
+    (sane.then_some(total_distance), errors)
+}
+
+#[cfg(feature = "parallel")]
+pub fn verify_parallel(inst: &Instance, sol: &Solution) -> Result<rug::Float, Vec<VerifyError>> {
+    use rayon::prelude::*;
+
+    let (sol, vehicles) = split_vehicles(inst, sol).map_err(|e| vec![e])?;
+    check_basic_sanity(inst, &sol).map_err(|e| vec![e])?;
+
+    if inst.is_pdp {
+        check_pdp(inst, &sol).map_err(|e| vec![e])?;
+    }
+    check_pdp_chain(inst, &sol).map_err(|e| vec![e])?;
+
+    check_vehicles(inst, &sol).map_err(|e| vec![e])?;
+
+    let dists = inst.with_mode(inst.distance_mode()).map_err(|e| vec![e])?;
+
+    let routes: Vec<(rug::Float, Vec<VerifyError>)> = sol
+        .routes
+        .par_iter()
+        .enumerate()
+        .map(|(route_id, route)| {
+            let mut errors = vec![];
+            let vehicle = vehicles[route_id];
+            collect_route_time(
+                inst,
+                &dists,
+                vehicle.depot,
+                route_id + 1,
+                route,
+                &mut errors,
+            );
+            collect_route_load(
+                inst,
+                vehicle.capacity(inst),
+                route_id + 1,
+                route,
+                &mut errors,
+            );
+            (route_distance(inst, &dists, vehicle.depot, route), errors)
+        })
+        .collect();
+
+    let mut total_distance = dists.precision().fl(0);
+    let mut errors = vec![];
+    for (distance, route_errors) in routes {
+        total_distance += distance;
+        errors.extend(route_errors);
+    }
+
+    if errors.is_empty() {
+        Ok(total_distance)
+    } else {
+        Err(errors)
+    }
+}
+
+fn dist_f64(inst: &Instance, from: usize, to: usize) -> f64 {
+    match &inst.distances {
+        DistanceSource::Matrix(matrix) => matrix[from][to].to_f64(),
+        DistanceSource::Euclidean => {
+            let (a, b) = (&inst.pts[from], &inst.pts[to]);
+            let xs = a.x - b.x;
+            let ys = a.y - b.y;
+            (xs * xs + ys * ys).sqrt()
+        }
+    }
+}
+
+fn check_route_time_f64(
+    inst: &Instance,
+    d: usize,
+    route_id: usize,
+    route: &[usize],
+) -> Result<(), VerifyError> {
+    let depot = &inst.pts[d];
+    let route_start = (depot.start + depot.service) as f64;
+    let mut time = route_start;
+    let mut prev = d;
+
+    for (position, &p) in route.iter().enumerate() {
+        let pt = &inst.pts[p];
+        time += dist_f64(inst, prev, p);
+
+        if time > pt.due as f64 {
+            Err(VerifyError::TimeWindowViolation {
+                route_id,
+                position,
+                point_id: pt.id,
+                arrival: flf64(time),
+                due: pt.due,
+            })?;
+        }
+
+        time = time.max(pt.start as f64) + pt.service as f64;
+        prev = p;
+    }
+
+    if !inst.open_routes {
+        time += dist_f64(inst, prev, d);
+        if time > depot.due as f64 {
+            Err(VerifyError::DepotReturnViolation {
+                route_id,
+                arrival: flf64(time),
+                due: depot.due,
+            })?;
+        }
+    }
+
+    if let Some(limit) = inst.max_route_duration {
+        if time - route_start > limit as f64 {
+            Err(VerifyError::RouteDurationExceeded {
+                route_id,
+                duration: flf64(time - route_start),
+                limit,
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn route_distance_f64(inst: &Instance, depot: usize, route: &[usize]) -> f64 {
+    std::iter::once(depot)
+        .chain(route.iter().copied())
+        .chain((!inst.open_routes).then_some(depot))
+        .tuple_windows()
+        .map(|(from, to)| dist_f64(inst, from, to))
+        .sum()
+}
+
+// same checks as `verify` in plain f64 arithmetic, which is much faster;
+// rounding errors may make the result differ from `verify` when an arrival
+// lands (almost) exactly on a due time, so use `verify` for final answers
+pub fn verify_f64(inst: &Instance, sol: &Solution) -> Result<f64, VerifyError> {
+    let (sol, vehicles) = split_vehicles(inst, sol)?;
+    check_basic_sanity(inst, &sol)?;
+
+    if inst.is_pdp {
+        check_pdp(inst, &sol)?;
+    }
+    check_pdp_chain(inst, &sol)?;
+
+    check_vehicles(inst, &sol)?;
+
+    let mut total_distance = 0.0;
+    for (route_id, route) in sol.routes.iter().enumerate() {
+        let vehicle = vehicles[route_id];
+        let mut errors = vec![];
+        check_route_time_f64(inst, vehicle.depot, route_id + 1, route)?;
+        collect_route_load(
+            inst,
+            vehicle.capacity(inst),
+            route_id + 1,
+            route,
+            &mut errors,
+        );
+        first_error(errors)?;
+        total_distance += route_distance_f64(inst, vehicle.depot, route);
+    }
+
+    Ok(total_distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::fl;
+    use instance::Point;
+
+    fn setup() -> Instance {
+        let inst = Instance {
+            name: "test".to_string(),
+            is_pdp: false,
+            distances: DistanceSource::Euclidean,
+            open_routes: false,
+            max_route_duration: None,
+            min_vehicles: None,
+            vehicle_capacities: vec![],
+            depots: vec![],
+            depot_capacities: vec![],
+            vehicle_depot_assignment: None,
+            soft_windows: false,
+            penalty_per_unit: 0.0,
+            vehicles: 3,
+            max_capacity: 10,
+            pts: vec![
+                Point {
+                    id: 0,
+                    x: 0.0,
+                    y: 0.0,
+                    demand: 0,
+                    start: 0,
+                    due: 48,
+                    service: 0,
+                    pickup_delivery: None,
+                    pickup_delivery_chain: None,
+                },
+                Point {
+                    id: 1,
+                    x: 0.0,
+                    y: 1.0,
+                    demand: 2,
+                    start: 0,
+                    due: 10,
+                    service: 10,
+                    pickup_delivery: None,
+                    pickup_delivery_chain: None,
+                },
+                Point {
+                    id: 2,
+                    x: 1.0,
+                    y: 1.0,
+                    demand: 2,
+                    start: 0,
+                    due: 3600,
+                    service: 10,
+                    pickup_delivery: None,
+                    pickup_delivery_chain: None,
+                },
+                Point {
+                    id: 3,
+                    x: 1.0,
+                    y: 0.0,
+                    demand: 2,
+                    start: 0,
+                    due: 3600,
+                    service: 10,
+                    pickup_delivery: None,
+                    pickup_delivery_chain: None,
+                },
+                Point {
+                    id: 4,
+                    x: 0.0,
+                    y: -1.0,
+                    demand: 2,
+                    start: 0,
+                    due: 3600,
+                    service: 10,
+                    pickup_delivery: None,
+                    pickup_delivery_chain: None,
+                },
+                Point {
+                    id: 5,
+                    x: -1.0,
+                    y: -1.0,
+                    demand: 2,
+                    start: 0,
+                    due: 3600,
+                    service: 10,
+                    pickup_delivery: None,
+                    pickup_delivery_chain: None,
+                },
+                Point {
+                    id: 6,
+                    x: -1.0,
+                    y: 0.0,
+                    demand: 2,
+                    start: 0,
+                    due: 3600,
+                    service: 10,
+                    pickup_delivery: None,
+                    pickup_delivery_chain: None,
+                },
+            ],
+        };
+        assert_eq!(inst.check_sanity(), Ok(()));
+        inst
+    }
+
+    #[test]
+    fn verify_correct() {
+        let inst = setup();
+
+        let res = verify(
+            &inst,
+            &Solution {
+                routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(res.map(|r| r.total_distance), Ok(fl(8)));
+    }
+
+    #[test]
+    fn verify_route_results() {
+        let inst = setup();
+
+        let report = verify(
+            &inst,
+            &Solution {
+                routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(report.route_results.len(), 2);
+
+        let first = &report.route_results[0];
+        assert_eq!(first.route_id, 1);
+        assert_eq!(first.distance, fl(4));
+        assert_eq!(first.max_load, 6);
+        assert_eq!(first.load_profile, vec![2, 4, 6]);
+        assert_eq!(first.arrival_times, vec![fl(1), fl(12), fl(23)]);
+        assert_eq!(first.departure_times, vec![fl(11), fl(22), fl(33)]);
+        assert_eq!(first.slack_times, vec![fl(9), fl(3588), fl(3577)]);
+        // the vehicle has to be back at the depot by 48
+        assert_eq!(first.backward_slack_times, vec![fl(9), fl(14), fl(14)]);
+        assert_eq!(
+            calc_route_slack(&inst, &[1, 2, 3]),
+            Ok((
+                first.slack_times.clone(),
+                first.backward_slack_times.clone()
+            ))
+        );
+        // arriving late at the last customer leaves no slack for earlier ones
+        assert_eq!(
+            calc_route_slack(&inst, &[3, 2, 1]),
+            Ok((
+                vec![fl(3599), fl(3588), fl(-13)],
+                vec![fl(-13), fl(-13), fl(-13)]
+            ))
+        );
+
+        assert_eq!(report.route_results[1].route_id, 2);
+        assert_eq!(report.route_results[1].distance, fl(4));
+    }
+
+    #[test]
+    fn test_check_basic_sanity_errors() {
+        let inst = setup();
 
         assert_eq!(
             check_basic_sanity(
@@ -318,7 +1382,38 @@ mod tests {
                     ..Default::default()
                 },
             ),
-            Err("route 1 visits depot at non-terminal position 2".to_string())
+            Err(VerifyError::DepotVisit {
+                route_id: 1,
+                position: 2,
+                point_id: 0
+            })
+        );
+
+        assert_eq!(
+            VerifyError::DepotVisit {
+                route_id: 1,
+                position: 2,
+                point_id: 0
+            }
+            .to_string(),
+            "route 1 visits depot at non-terminal position 3 (customer id 0, the depot)"
+        );
+
+        // the first point past the end of the instance used to be looked up
+        let past_end = inst.pts.len();
+        assert_eq!(
+            check_basic_sanity(
+                &inst,
+                &Solution {
+                    routes: vec![vec![1, 2, 3], vec![4, 5, past_end]],
+                    ..Default::default()
+                },
+            ),
+            Err(VerifyError::UnknownPoint {
+                point_id: past_end,
+                route_id: 2,
+                position: 2
+            })
         );
 
         assert_eq!(
@@ -329,7 +1424,11 @@ mod tests {
                     ..Default::default()
                 },
             ),
-            Err("node 60 in route 2 at position 2 is not described in the instance".to_string())
+            Err(VerifyError::UnknownPoint {
+                point_id: 60,
+                route_id: 2,
+                position: 2
+            })
         );
 
         assert_eq!(
@@ -340,7 +1439,11 @@ mod tests {
                     ..Default::default()
                 },
             ),
-            Err("node 3 visited at least two times (in routes 2 and 1)".to_string())
+            Err(VerifyError::DuplicateVisit {
+                point_id: 3,
+                route_a: 2,
+                route_b: 1
+            })
         );
         assert_eq!(
             check_basic_sanity(
@@ -350,7 +1453,11 @@ mod tests {
                     ..Default::default()
                 },
             ),
-            Err("node 1 visited at least two times (in routes 1 and 1)".to_string())
+            Err(VerifyError::DuplicateVisit {
+                point_id: 1,
+                route_a: 1,
+                route_b: 1
+            })
         );
 
         assert_eq!(
@@ -361,7 +1468,7 @@ mod tests {
                     ..Default::default()
                 },
             ),
-            Err("node 5 not visited in any route".to_string())
+            Err(VerifyError::MissingVisit { point_id: 5 })
         );
     }
 
@@ -377,20 +1484,47 @@ mod tests {
             },
         );
 
-        assert_eq!(res, Err("more vehicles than allowed (6 > 3)".to_string()));
+        assert_eq!(
+            res,
+            Err(VerifyError::TooManyVehicles {
+                used: 6,
+                allowed: 3
+            })
+        );
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "more vehicles than allowed (6 > 3)"
+        );
     }
 
     #[test]
     fn routes_too_large_load() {
         let inst = setup();
 
-        let res = check_route_load(&inst, 1, &(1..=6).collect());
+        let res = check_route_load(&inst, 1, &(1..=6).collect::<Vec<_>>());
 
         assert_eq!(
             res,
-            Err(
-                "load is greater than max load (12 > 10) at 6 in route 1 at position 5".to_string()
-            )
+            Err((
+                VerifyError::CapacityExceeded {
+                    route_id: 1,
+                    position: 5,
+                    point_id: 6,
+                    load: 12,
+                    capacity: 10
+                },
+                Some(LoadError {
+                    position: 5,
+                    customer_id: 6,
+                    load: 12,
+                    capacity: 10,
+                    load_history: (1..=6).map(|c| (c, 2, 2 * c as i32)).collect()
+                })
+            ))
+        );
+        assert_eq!(
+            res.unwrap_err().0.to_string(),
+            "load is greater than max load (12 > 10) at 6 in route 1 at position 6"
         );
     }
 
@@ -398,19 +1532,66 @@ mod tests {
     fn routes_time() {
         let inst = setup();
 
-        let res = check_route_time(&inst, 1, &vec![1, 2, 3, 6, 5, 4]);
+        let res = check_route_time(&inst, 1, &[1, 2, 3, 6, 5, 4], DistanceMode::Euclidean);
+
+        assert_eq!(
+            res,
+            Err(VerifyError::DepotReturnViolation {
+                route_id: 1,
+                arrival: fl(68),
+                due: 48
+            })
+        );
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "arrived too late (68.00000000000000000000000000000000000000) in route 1 at depot"
+        );
+
+        let res = check_route_time(&inst, 2, &[3, 2, 1], DistanceMode::Euclidean);
 
         assert_eq!(
             res,
-            Err(
-                "arrived too late (68.00000000000000000000000000000000000000) in route 1 at depot"
-                    .to_string()
-            )
+            Err(VerifyError::TimeWindowViolation {
+                route_id: 2,
+                position: 2,
+                point_id: 1,
+                arrival: fl(23),
+                due: 10
+            })
         );
+        assert_eq!(res.unwrap_err().to_string(), "arrived too late (23.00000000000000000000000000000000000000) at 1 in route 2 at position 3");
+    }
+
+    #[test]
+    fn large_due_times() {
+        // arrivals are compared with the due times as rug floats, without
+        // rounding either of them
+        let mut inst = setup();
+        inst.pts[0].due = i32::MAX;
+        inst.pts[1].y = 2_000_000_000.5;
+        inst.pts[1].due = 2_000_000_000;
+        inst.pts[1].service = 0;
 
-        let res = check_route_time(&inst, 2, &vec![3, 2, 1]);
+        assert_eq!(
+            check_route_time(&inst, 1, &[1], DistanceMode::Euclidean),
+            Err(VerifyError::TimeWindowViolation {
+                route_id: 1,
+                position: 0,
+                point_id: 1,
+                arrival: flf64(2_000_000_000.5),
+                due: 2_000_000_000
+            })
+        );
 
-        assert_eq!(res, Err("arrived too late (23.00000000000000000000000000000000000000) at 1 in route 2 at position 2".to_string()));
+        inst.pts[1].due = 2_000_000_001;
+        assert_eq!(
+            check_route_time(&inst, 1, &[1], DistanceMode::Euclidean),
+            Err(VerifyError::DepotReturnViolation {
+                route_id: 1,
+                arrival: flf64(4_000_000_001.0),
+                due: i32::MAX
+            })
+        );
     }
 
     #[test]
@@ -438,10 +1619,12 @@ mod tests {
 
         assert_eq!(
             res,
-            Err(
-                "pickup 3 and delivery 4 are not in the same routes (are in routes 1 and 2)"
-                    .to_string()
-            )
+            Err(VerifyError::PdpSameRoute {
+                pickup: 3,
+                delivery: 4,
+                route_a: 1,
+                route_b: 2
+            })
         );
 
         let res = check_pdp(
@@ -454,18 +1637,816 @@ mod tests {
 
         assert_eq!(
             res,
-            Err("delivery 6 is before its pickup 5 (are on positions 0 and 1)".to_string())
+            Err(VerifyError::PdpOrdering {
+                pickup: 5,
+                delivery: 6,
+                pickup_pos: 1,
+                delivery_pos: 0
+            })
+        );
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "delivery 6 is before its pickup 5 (are on positions 1 and 2)"
         );
 
-        let res = check_route_load(&inst, 1, &vec![3, 2, 6, 5, 4, 1]);
+        let res = check_route_load(&inst, 1, &[3, 2, 6, 5, 4, 1]);
 
         assert_eq!(
             res,
-            Err(("current load is negative at 6 in route 1 at position 2").to_string())
+            Err((
+                VerifyError::NegativeLoad {
+                    route_id: 1,
+                    position: 2,
+                    point_id: 6,
+                    load: -2
+                },
+                Some(LoadError {
+                    position: 2,
+                    customer_id: 6,
+                    load: -2,
+                    capacity: 10,
+                    load_history: vec![(3, 2, 2), (2, -2, 0), (6, -2, -2)]
+                })
+            ))
+        );
+
+        let res = check_route_load(&inst, 1, &[3, 6, 5, 4]);
+
+        assert_eq!(res, Ok(vec![2, 0, 2, 0]));
+    }
+
+    #[test]
+    fn pdp_chain() {
+        let mut inst = setup();
+        inst.pts[1].pickup_delivery_chain = Some(vec![1, 3, 4, 2]);
+        inst.pts[3].pickup_delivery_chain = Some(vec![1, 3, 4, 2]);
+        let sol = |routes| Solution {
+            routes,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check_pdp_chain(&inst, &sol(vec![vec![1, 3, 4, 2], vec![5, 6]])),
+            Ok(())
+        );
+        assert_eq!(
+            check_pdp_chain(&inst, &sol(vec![vec![1, 3, 2, 4], vec![5, 6]])),
+            Err(VerifyError::PdpChainViolation {
+                before: 4,
+                after: 2,
+                route_a: 1,
+                route_b: 1,
+                before_pos: 3,
+                after_pos: 2
+            })
         );
 
-        let res = check_route_load(&inst, 1, &vec![3, 6, 5, 4]);
+        let mut errors = vec![];
+        collect_pdp_chain(&inst, &sol(vec![vec![1, 2], vec![3, 4, 5, 6]]), &mut errors);
+        assert_eq!(
+            errors,
+            vec![
+                VerifyError::PdpChainViolation {
+                    before: 1,
+                    after: 3,
+                    route_a: 1,
+                    route_b: 2,
+                    before_pos: 0,
+                    after_pos: 0
+                },
+                VerifyError::PdpChainViolation {
+                    before: 4,
+                    after: 2,
+                    route_a: 2,
+                    route_b: 1,
+                    before_pos: 1,
+                    after_pos: 1
+                }
+            ]
+        );
 
-        assert_eq!(res, Ok(()));
+        inst.pts[5].pickup_delivery_chain = Some(vec![5, 0]);
+        assert!(inst.check_sanity().is_err());
+    }
+
+    #[test]
+    fn verify_soft_windows() {
+        let mut inst = setup();
+        inst.pts[2].due = 1;
+        let sol = Solution {
+            routes: vec![vec![3, 2, 1], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            verify_soft(&inst, &sol),
+            Err(VerifyError::TimeWindowViolation {
+                route_id: 1,
+                position: 1,
+                point_id: 2,
+                arrival: fl(12),
+                due: 1
+            })
+        );
+
+        inst.soft_windows = true;
+        inst.penalty_per_unit = 0.5;
+        assert_eq!(
+            verify_soft(&inst, &sol),
+            Ok(SoftVerificationResult {
+                distance: fl(8),
+                penalty: 12.0,
+                penalty_violations: 2
+            })
+        );
+        assert!(verify(&inst, &sol).is_err());
+    }
+
+    #[test]
+    fn empty_route() {
+        let inst = setup();
+        let sol = Solution {
+            routes: vec![vec![1, 2, 3], vec![], vec![4, 5, 6]],
+            ..Default::default()
+        };
+        let err = VerifyError::EmptyRoute { route_id: 2 };
+
+        assert_eq!(verify(&inst, &sol), Err(err.clone()));
+        assert_eq!(verify_f64(&inst, &sol), Err(err.clone()));
+        assert_eq!(verify_all(&inst, &sol), (None, vec![err.clone()]));
+        assert_eq!(
+            check_route_time(&inst, 2, &[], DistanceMode::Euclidean),
+            Err(err)
+        );
+        assert_eq!(
+            verify(&inst, &sol).unwrap_err().to_string(),
+            "route 2 is empty"
+        );
+    }
+
+    #[test]
+    fn verify_all_collects_every_violation() {
+        let mut inst = setup();
+        inst.pts[2].due = 1;
+
+        let (dist, errors) = verify_all(
+            &inst,
+            &Solution {
+                routes: vec![vec![3, 2, 1], vec![4, 5, 6]],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(dist, Some(fl(8)));
+        assert_eq!(
+            errors,
+            vec![
+                VerifyError::TimeWindowViolation {
+                    route_id: 1,
+                    position: 1,
+                    point_id: 2,
+                    arrival: fl(12),
+                    due: 1
+                },
+                VerifyError::TimeWindowViolation {
+                    route_id: 1,
+                    position: 2,
+                    point_id: 1,
+                    arrival: fl(23),
+                    due: 10
+                },
+            ]
+        );
+
+        let (dist, errors) = verify_all(
+            &inst,
+            &Solution {
+                routes: vec![vec![1, 2, 3, 4], vec![6], vec![60], vec![3]],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(dist, None);
+        assert_eq!(
+            errors,
+            vec![
+                VerifyError::UnknownPoint {
+                    point_id: 60,
+                    route_id: 3,
+                    position: 0
+                },
+                VerifyError::DuplicateVisit {
+                    point_id: 3,
+                    route_a: 4,
+                    route_b: 1
+                },
+                VerifyError::MissingVisit { point_id: 5 },
+                VerifyError::TooManyVehicles {
+                    used: 4,
+                    allowed: 3
+                },
+                VerifyError::TimeWindowViolation {
+                    route_id: 1,
+                    position: 1,
+                    point_id: 2,
+                    arrival: fl(12),
+                    due: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_all_on_correct_solution() {
+        let inst = setup();
+
+        let (dist, errors) = verify_all(
+            &inst,
+            &Solution {
+                routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(dist, Some(fl(8)));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn verify_using_precomputed_matrix() {
+        let inst = setup();
+        let matrix = inst.precompute_distances();
+
+        assert_eq!(matrix.len(), 7);
+        assert_eq!(matrix.dist(5, 2), matrix.dist(2, 5));
+        assert_eq!(matrix.dist(2, 5), &inst.pts[2].dist(&inst.pts[5]));
+
+        let sol = Solution {
+            routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            verify_with_matrix(&inst, &sol, &matrix),
+            verify(&inst, &sol)
+        );
+
+        let sol = Solution {
+            routes: vec![vec![3, 2, 1], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            verify_with_matrix(&inst, &sol, &matrix),
+            verify(&inst, &sol)
+        );
+    }
+
+    #[test]
+    fn verify_with_asymmetric_matrix() {
+        let mut inst = setup();
+        let sol = Solution {
+            routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+            ..Default::default()
+        };
+        let euclidean_distance = verify(&inst, &sol).unwrap().total_distance;
+
+        let matrix = inst.precompute_distances();
+        let mut rows: Vec<Vec<rug::Float>> = (0..7)
+            .map(|i| (0..7).map(|j| matrix.dist(i, j).clone()).collect())
+            .collect();
+        rows[1][2] += 1;
+        inst.distances = DistanceSource::Matrix(rows);
+
+        let report = verify(&inst, &sol).unwrap();
+        assert_eq!(report.total_distance, euclidean_distance + 1);
+        assert_eq!(inst.between(2, 1), fl(1));
+
+        let matrix = inst.precompute_distances();
+        assert_eq!(verify_with_matrix(&inst, &sol, &matrix), Ok(report));
+    }
+
+    #[test]
+    fn verify_geo_instance() {
+        let geo = GeoInstance::from_geo_str(concat!(
+            "2 100\n",
+            "0 51.7592 19.4560 0 0 1000 0\n",
+            "1 52.2297 21.0122 10 0 500 10\n",
+            "2 50.0647 19.9450 10 0 300 10\n",
+        ))
+        .unwrap();
+
+        let sol = Solution {
+            routes: vec![vec![1], vec![2]],
+            ..Default::default()
+        };
+
+        let expected = geo.between(0, 1) * 2 + geo.between(0, 2) * 2;
+        assert_eq!(verify_geo(&geo, &sol).unwrap().total_distance, expected);
+        assert_eq!(verify_all_geo(&geo, &sol), (Some(expected), vec![]));
+
+        let sol = Solution {
+            routes: vec![vec![1, 2]],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            verify_geo(&geo, &sol),
+            Err(VerifyError::TimeWindowViolation { point_id: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn verify_in_manhattan_mode() {
+        let inst = setup();
+        let sol = Solution {
+            routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            calc_route_distance(&inst, &[1, 2, 3], DistanceMode::Manhattan),
+            Ok(fl(4))
+        );
+
+        let report = verify_with_mode(&inst, &sol, DistanceMode::Manhattan).unwrap();
+        assert_eq!(
+            report.total_distance,
+            calc_route_distance(&inst, &[1, 2, 3], DistanceMode::Manhattan).unwrap()
+                + calc_route_distance(&inst, &[4, 5, 6], DistanceMode::Manhattan).unwrap()
+        );
+
+        assert!(matches!(
+            verify_with_mode(&inst, &sol, DistanceMode::Asymmetric),
+            Err(VerifyError::InvalidInstance(_))
+        ));
+    }
+
+    #[test]
+    fn verify_with_different_precisions() {
+        let inst = setup();
+        let sol = Solution {
+            routes: vec![vec![1, 3, 2], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        let fast = verify_with_precision(&inst, &sol, Precision::FAST).unwrap();
+        let standard = verify_with_precision(&inst, &sol, Precision::STANDARD).unwrap();
+        let high = verify_with_precision(&inst, &sol, Precision::HIGH).unwrap();
+
+        assert_eq!(fast.total_distance.prec(), 64);
+        assert_eq!(standard.total_distance.prec(), 128);
+        assert_eq!(high.total_distance.prec(), 256);
+        assert_eq!(high.route_results[0].arrival_times[0].prec(), 256);
+
+        assert_eq!(Ok(standard), verify(&inst, &sol));
+        assert!((high.total_distance - fast.total_distance).abs() < 1e-12);
+    }
+
+    #[test]
+    fn depot_return_uses_distance_source() {
+        let mut inst = setup();
+        let matrix = inst.precompute_distances();
+        let mut rows: Vec<Vec<rug::Float>> = (0..7)
+            .map(|i| (0..7).map(|j| matrix.dist(i, j).clone()).collect())
+            .collect();
+        rows[3][0] += 100;
+        inst.distances = DistanceSource::Matrix(rows);
+
+        assert!(matches!(
+            check_route_time(&inst, 1, &[1, 2, 3], DistanceMode::Asymmetric),
+            Err(VerifyError::DepotReturnViolation { route_id: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn verify_f64_matches_verify() {
+        let inst = setup();
+
+        let sol = Solution {
+            routes: vec![vec![1, 3, 2], vec![4, 5, 6]],
+            ..Default::default()
+        };
+        let exact = verify(&inst, &sol).unwrap().total_distance.to_f64();
+        assert!((verify_f64(&inst, &sol).unwrap() - exact).abs() < 1e-9);
+
+        let sol = Solution {
+            routes: vec![vec![1, 2, 3, 6, 5, 4]],
+            ..Default::default()
+        };
+        assert_eq!(
+            verify_f64(&inst, &sol),
+            Err(VerifyError::DepotReturnViolation {
+                route_id: 1,
+                arrival: fl(68),
+                due: 48
+            })
+        );
+    }
+
+    #[test]
+    fn verify_with_rounded_legs() {
+        let inst = setup();
+        let sol = Solution {
+            routes: vec![vec![1, 3, 2], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        let none = verify_with_rounding(&inst, &sol, RoundingMode::None).unwrap();
+        assert_eq!(Ok(none), verify(&inst, &sol));
+
+        // the only non integer legs are the two diagonals of length sqrt(2)
+        let truncated = verify_with_rounding(&inst, &sol, RoundingMode::Truncate(1)).unwrap();
+        let expected: rug::Float = fl(6) + fl(28) / 10;
+        assert!((truncated.total_distance - expected).abs() < 1e-30);
+
+        let ceiled = verify_with_rounding(&inst, &sol, RoundingMode::Ceiling(0)).unwrap();
+        assert_eq!(ceiled.total_distance, fl(6) + fl(4));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn verify_parallel_matches_verify() {
+        let inst = setup();
+
+        let sol = Solution {
+            routes: vec![vec![1, 3, 2], vec![4, 5, 6]],
+            ..Default::default()
+        };
+        assert_eq!(
+            verify_parallel(&inst, &sol),
+            verify(&inst, &sol)
+                .map(|r| r.total_distance)
+                .map_err(|e| vec![e])
+        );
+
+        let sol = Solution {
+            routes: vec![vec![3, 2, 1], vec![6, 5, 4]],
+            ..Default::default()
+        };
+        assert_eq!(verify_parallel(&inst, &sol), Err(verify_all(&inst, &sol).1));
+    }
+
+    #[test]
+    fn verify_partial_solution() {
+        let inst = setup();
+        let partial = Solution {
+            routes: vec![vec![1, 2, 3]],
+            ..Default::default()
+        };
+
+        assert_eq!(verify_partial(&inst, &partial, &[1, 2, 3]), Ok(fl(4)));
+        assert_eq!(
+            verify_partial(&inst, &partial, &[1, 4]),
+            Err(VerifyError::MissingVisit { point_id: 4 })
+        );
+        assert_eq!(
+            verify_partial(
+                &inst,
+                &Solution {
+                    routes: vec![vec![1, 2], vec![2]],
+                    ..Default::default()
+                },
+                &[1, 2]
+            ),
+            Err(VerifyError::DuplicateVisit {
+                point_id: 2,
+                route_a: 2,
+                route_b: 1
+            })
+        );
+    }
+
+    fn multi_depot() -> Instance {
+        concat!(
+            "md_test\n",
+            "3 100\n",
+            "0 0 0 0 0 1000 0\n",
+            "1 10 0 0 0 1000 0\n",
+            "2 1 0 5 0 1000 0\n",
+            "3 11 0 5 0 1000 0\n",
+            "4 2 0 5 0 1000 0\n",
+            "\n",
+            "DEPOTS:\n",
+            "0 10\n",
+            "1 6\n",
+            "VEHICLE DEPOTS: 0 1 0\n",
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn verify_multi_depot() {
+        let inst = multi_depot();
+        let sol = |routes: Vec<Vec<usize>>| Solution {
+            routes,
+            ..Default::default()
+        };
+
+        let res = verify(&inst, &sol(vec![vec![0, 2, 4, 0], vec![1, 3, 1]]));
+        assert_eq!(res.map(|r| r.total_distance), Ok(fl(6)));
+
+        assert_eq!(
+            verify(&inst, &sol(vec![vec![0, 2, 4, 0], vec![0, 3, 0]])),
+            Err(VerifyError::WrongDepot {
+                route_id: 2,
+                depot: 1,
+                start: 0,
+                end: 0
+            })
+        );
+        assert_eq!(
+            verify(&inst, &sol(vec![vec![0, 2, 0], vec![1, 3, 4, 1]])),
+            Err(VerifyError::CapacityExceeded {
+                route_id: 2,
+                position: 1,
+                point_id: 4,
+                load: 10,
+                capacity: 6
+            })
+        );
+        assert_eq!(
+            verify(&inst, &sol(vec![vec![0, 2, 1, 4, 0], vec![1, 3, 1]])),
+            Err(VerifyError::DepotVisit {
+                route_id: 1,
+                position: 1,
+                point_id: 1
+            })
+        );
+        assert_eq!(
+            check_route_time(&inst, 2, &[1, 3, 1], DistanceMode::Euclidean),
+            Ok(())
+        );
+
+        let mut unassigned = inst.clone();
+        unassigned.vehicle_depot_assignment = None;
+        let res = verify(
+            &unassigned,
+            &sol(vec![vec![1, 2, 1], vec![1, 3, 1], vec![0, 4, 0]]),
+        );
+        assert_eq!(res.map(|r| r.total_distance), Ok(fl(24)));
+        assert!(matches!(
+            verify(&unassigned, &sol(vec![vec![0, 2, 4, 1], vec![1, 3, 1]])),
+            Err(VerifyError::WrongDepot { route_id: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn empty_route_multi_depot() {
+        let inst = multi_depot();
+        let sol = Solution {
+            instance_name: "md_test".to_string(),
+            routes: vec![vec![0, 2, 4, 0], vec![], vec![0, 3, 0]],
+            ..Default::default()
+        };
+        let err = VerifyError::EmptyRoute { route_id: 2 };
+
+        assert_eq!(verify(&inst, &sol), Err(err.clone()));
+        assert_eq!(verify_all(&inst, &sol), (None, vec![err.clone()]));
+        assert_eq!(
+            check_route_time(&inst, 2, &[], DistanceMode::Euclidean),
+            Err(err.clone())
+        );
+        assert_eq!(check_route_load(&inst, 2, &[]), Err((err, None)));
+        assert_eq!(
+            calc_route_distance(&inst, &[], DistanceMode::Euclidean),
+            Err(VerifyError::EmptyRoute { route_id: 0 })
+        );
+        assert!(calc_route_slack(&inst, &[]).is_err());
+        assert!(calc_route_distance(&setup(), &[], DistanceMode::Euclidean).is_err());
+    }
+
+    #[test]
+    fn verify_heterogeneous_fleet() {
+        let mut inst = setup();
+        inst.vehicle_capacities = vec![10, 4, 6];
+        assert_eq!(inst.check_sanity(), Ok(()));
+        let sol = |vehicle_assignments| Solution {
+            routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+            vehicle_assignments,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            verify(&inst, &sol(None)),
+            Err(VerifyError::CapacityExceeded {
+                route_id: 2,
+                position: 2,
+                point_id: 6,
+                load: 6,
+                capacity: 4
+            })
+        );
+        let res = verify(&inst, &sol(Some(vec![0, 2])));
+        assert_eq!(res.map(|r| r.total_distance), Ok(fl(8)));
+        assert_eq!(
+            verify(&inst, &sol(Some(vec![2, 1]))),
+            Err(VerifyError::CapacityExceeded {
+                route_id: 2,
+                position: 2,
+                point_id: 6,
+                load: 6,
+                capacity: 4
+            })
+        );
+        assert_eq!(
+            verify(&inst, &sol(Some(vec![0, 3]))),
+            Err(VerifyError::UnknownVehicle {
+                route_id: 2,
+                vehicle: 3
+            })
+        );
+        assert_eq!(
+            verify(&inst, &sol(Some(vec![2, 2]))),
+            Err(VerifyError::DuplicateVehicle {
+                vehicle: 2,
+                route_a: 2,
+                route_b: 1
+            })
+        );
+        assert_eq!(
+            verify(&inst, &sol(Some(vec![0]))),
+            Err(VerifyError::VehicleAssignmentLength {
+                assigned: 1,
+                routes: 2
+            })
+        );
+
+        inst.vehicle_capacities = vec![10, 4];
+        assert!(inst.check_sanity().is_err());
+    }
+
+    #[test]
+    fn verify_open_routes() {
+        let closed = setup();
+        let mut open = setup();
+        open.open_routes = true;
+        let sol = Solution {
+            routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        assert_eq!(verify(&closed, &sol).map(|r| r.total_distance), Ok(fl(8)));
+        assert_eq!(verify(&open, &sol).map(|r| r.total_distance), Ok(fl(6)));
+        assert_eq!(verify_f64(&open, &sol), Ok(6.0));
+        assert_eq!(
+            calc_route_distance(&open, &[1, 2, 3], DistanceMode::Euclidean),
+            Ok(fl(3))
+        );
+
+        // the last customer is served after the depot closes, which only
+        // matters when the vehicle has to return
+        let mut late = setup();
+        late.pts[0].due = 39;
+        late.pts[3].start = 39;
+        late.pts[3].service = 0;
+        let route = [1, 2, 3];
+        assert!(check_route_time(&late, 1, &route, DistanceMode::Euclidean).is_err());
+        late.open_routes = true;
+        assert_eq!(
+            check_route_time(&late, 1, &route, DistanceMode::Euclidean),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn instance_name_mismatch() {
+        let inst = setup();
+        let mut sol = Solution {
+            instance_name: "other".to_string(),
+            routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        let err = verify(&inst, &sol).unwrap_err();
+        assert_eq!(
+            err,
+            VerifyError::InstanceNameMismatch {
+                solution: "other".to_string(),
+                instance: "test".to_string()
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "solution instance name 'other' does not match instance name 'test' — did you pass the wrong files?"
+        );
+        assert!(verify_unchecked(&inst, &sol).is_ok());
+
+        sol.instance_name = " TEST".to_string();
+        assert!(verify(&inst, &sol).is_ok());
+        sol.instance_name.clear();
+        assert!(verify(&inst, &sol).is_ok());
+    }
+
+    #[test]
+    fn verify_min_vehicles() {
+        let mut inst = setup();
+        inst.min_vehicles = Some(3);
+        let sol = |routes| Solution {
+            routes,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            verify(&inst, &sol(vec![vec![1, 2, 3], vec![4, 5, 6]])),
+            Err(VerifyError::TooFewVehicles {
+                used: 2,
+                required: 3
+            })
+        );
+        assert!(verify(&inst, &sol(vec![vec![1, 2, 3], vec![4, 5], vec![6]])).is_ok());
+    }
+
+    #[test]
+    fn verify_max_route_duration() {
+        let mut inst = setup();
+        let sol = Solution {
+            routes: vec![vec![1, 2, 3], vec![4, 5, 6]],
+            ..Default::default()
+        };
+
+        inst.max_route_duration = Some(34);
+        assert_eq!(verify(&inst, &sol).map(|r| r.total_distance), Ok(fl(8)));
+
+        inst.max_route_duration = Some(30);
+        let err = VerifyError::RouteDurationExceeded {
+            route_id: 1,
+            duration: fl(34),
+            limit: 30,
+        };
+        assert_eq!(verify(&inst, &sol), Err(err.clone()));
+        assert_eq!(verify_f64(&inst, &sol), Err(err));
+
+        inst.open_routes = true;
+        assert_eq!(
+            check_route_time(&inst, 1, &[1, 2, 3], DistanceMode::Euclidean),
+            Err(VerifyError::RouteDurationExceeded {
+                route_id: 1,
+                duration: fl(33),
+                limit: 30,
+            })
+        );
+    }
+
+    #[test]
+    fn verify_multiple_trips() {
+        let inst = setup();
+        let sol = |vehicle_routes| MultiTripSolution {
+            vehicle_routes,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            verify_multitrip(
+                &inst,
+                &sol(vec![vec![vec![1, 2], vec![3]], vec![vec![4, 5, 6]]])
+            ),
+            Ok(fl(2).sqrt() + 8)
+        );
+        // on open routes the vehicle still returns between its trips
+        let mut open = inst.clone();
+        open.open_routes = true;
+        let trips = sol(vec![vec![vec![1, 2], vec![3]], vec![vec![4, 5, 6]]]);
+        assert_eq!(verify_multitrip(&open, &trips), Ok(fl(2).sqrt() + 6));
+        open.pts[0].due = 23;
+        assert_eq!(
+            verify_multitrip(&open, &trips),
+            Err(VerifyError::DepotReturnViolation {
+                route_id: 1,
+                arrival: fl(2).sqrt() + 22,
+                due: 23
+            })
+        );
+        // the second trip can only start after the vehicle is back from the first
+        assert_eq!(
+            verify_multitrip(&inst, &sol(vec![vec![vec![4, 5, 6], vec![1, 2, 3]]])),
+            Err(VerifyError::TimeWindowViolation {
+                route_id: 2,
+                position: 0,
+                point_id: 1,
+                arrival: fl(35),
+                due: 10
+            })
+        );
+        assert_eq!(
+            verify_multitrip(&inst, &sol(vec![vec![vec![1, 2, 3]], vec![vec![4, 5]]])),
+            Err(VerifyError::MissingVisit { point_id: 6 })
+        );
+        assert_eq!(
+            verify_multitrip(
+                &inst,
+                &sol(vec![
+                    vec![vec![1]],
+                    vec![vec![2, 3]],
+                    vec![vec![4]],
+                    vec![vec![5, 6]]
+                ])
+            ),
+            Err(VerifyError::TooManyVehicles {
+                used: 4,
+                allowed: 3
+            })
+        );
     }
 }
@@ -0,0 +1,34 @@
+use super::VerifyError;
+use serde::{de::DeserializeOwned, Serialize};
+
+// every binary file starts with the magic bytes followed by the format
+// version, bump the version whenever a serialized type changes so that stale
+// caches are rejected instead of being misread
+const MAGIC: &[u8; 4] = b"VRPB";
+pub const VERSION: u16 = 10;
+
+pub(crate) fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(VERSION.to_le_bytes());
+    bytes.extend(bincode::serialize(value).unwrap());
+    bytes
+}
+
+pub(crate) fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, VerifyError> {
+    let header = MAGIC.len() + 2;
+    if bytes.len() < header || &bytes[..MAGIC.len()] != MAGIC {
+        Err(VerifyError::ParseError(
+            "not a binary instance or solution file".to_string(),
+        ))?;
+    }
+
+    let version = u16::from_le_bytes([bytes[MAGIC.len()], bytes[MAGIC.len() + 1]]);
+    if version != VERSION {
+        Err(VerifyError::ParseError(format!(
+            "binary file has version {version}, expected {VERSION}, it needs to be regenerated"
+        )))?;
+    }
+
+    bincode::deserialize(&bytes[header..])
+        .map_err(|e| VerifyError::ParseError(format!("corrupted binary file: {e}")))
+}
@@ -0,0 +1,137 @@
+use super::instance::{Distances, Instance};
+use super::solution::Solution;
+use super::{collect_vehicles, route_distance};
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+use std::collections::HashSet;
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SolutionDiff {
+    pub customers_changed_route: Vec<usize>,
+    pub routes_added: usize,
+    pub routes_removed: usize,
+    #[serde_as(as = "DisplayFromStr")]
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub distance_delta: rug::Float,
+    pub routes_modified: Vec<usize>,
+}
+
+fn total_distance(inst: &Instance, sol: &Solution) -> rug::Float {
+    let mut total = inst.precision().fl(0);
+    // a diff does not verify the solutions, so errors are ignored here
+    let (sol, vehicles) = collect_vehicles(inst, sol, &mut vec![]);
+    for (route, vehicle) in sol.routes.iter().zip(vehicles) {
+        if !route.is_empty() && route.iter().all(|&p| p < inst.pts.len()) {
+            total += route_distance(inst, inst, vehicle.depot, route);
+        }
+    }
+    total
+}
+
+// routes of both solutions are paired greedily by the number of shared
+// customers, unpaired routes of `sol_b` are added and those of `sol_a` removed;
+// route ids in the result are 1-based positions in `sol_b`
+pub fn solution_diff(inst: &Instance, sol_a: &Solution, sol_b: &Solution) -> SolutionDiff {
+    let sets = |sol: &Solution| {
+        sol.routes
+            .iter()
+            .map(|r| {
+                r.iter()
+                    .copied()
+                    .filter(|&p| !inst.is_depot(p))
+                    .collect::<HashSet<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    let (sets_a, sets_b) = (sets(sol_a), sets(sol_b));
+
+    let mut overlaps = vec![];
+    for (a, set_a) in sets_a.iter().enumerate() {
+        for (b, set_b) in sets_b.iter().enumerate() {
+            let shared = set_a.intersection(set_b).count();
+            if shared > 0 {
+                overlaps.push((shared, a, b));
+            }
+        }
+    }
+    overlaps.sort_by(|x, y| y.0.cmp(&x.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+
+    let mut pair_of_a = vec![None; sets_a.len()];
+    let mut pair_of_b = vec![None; sets_b.len()];
+    for (_, a, b) in overlaps {
+        if pair_of_a[a].is_none() && pair_of_b[b].is_none() {
+            pair_of_a[a] = Some(b);
+            pair_of_b[b] = Some(a);
+        }
+    }
+
+    let mut customers_changed_route = vec![];
+    for (a, route) in sol_a.routes.iter().enumerate() {
+        for &c in route.iter() {
+            let stays = pair_of_a[a].is_some_and(|b| sets_b[b].contains(&c));
+            if !stays {
+                customers_changed_route.push(c);
+            }
+        }
+    }
+    customers_changed_route.sort_unstable();
+
+    let routes_modified = pair_of_b
+        .iter()
+        .enumerate()
+        .filter_map(|(b, a)| {
+            a.filter(|&a| sol_a.routes[a] != sol_b.routes[b])
+                .map(|_| b + 1)
+        })
+        .collect();
+
+    SolutionDiff {
+        customers_changed_route,
+        routes_added: pair_of_b.iter().filter(|a| a.is_none()).count(),
+        routes_removed: pair_of_a.iter().filter(|b| b.is_none()).count(),
+        distance_delta: total_distance(inst, sol_b) - total_distance(inst, sol_a),
+        routes_modified,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::fl;
+    use std::str::FromStr;
+
+    #[test]
+    fn diff() {
+        let instance = concat!(
+            "3 10\n",
+            "0 0 0 0 0 100 0\n",
+            "1 0 1 1 0 100 0\n",
+            "2 1 1 1 0 100 0\n",
+            "3 1 0 1 0 100 0\n",
+            "4 0 -1 1 0 100 0\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+        let sol = |routes: Vec<Vec<usize>>| Solution {
+            instance_name: String::new(),
+            routes,
+            ..Default::default()
+        };
+
+        let a = sol(vec![vec![1, 2], vec![3], vec![4]]);
+        let b = sol(vec![vec![4], vec![2, 1, 3]]);
+        let diff = solution_diff(&inst, &a, &b);
+
+        assert_eq!(diff.customers_changed_route, vec![3]);
+        assert_eq!(diff.routes_added, 0);
+        assert_eq!(diff.routes_removed, 1);
+        assert_eq!(diff.routes_modified, vec![2]);
+        assert!(diff.distance_delta < 0);
+
+        let same = solution_diff(&inst, &a, &a);
+        assert_eq!(same.customers_changed_route, Vec::<usize>::new());
+        assert_eq!(same.routes_modified, Vec::<usize>::new());
+        assert_eq!(same.distance_delta, fl(0));
+    }
+}
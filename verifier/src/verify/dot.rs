@@ -0,0 +1,74 @@
+use super::instance::{Distances, Instance};
+use super::solution::Solution;
+use std::fmt::Write;
+
+impl Solution {
+    pub fn to_dot(&self, inst: &Instance) -> String {
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph \"{}\" {{", inst.name.replace('"', "\\\"")).unwrap();
+        for (i, pt) in inst.pts.iter().enumerate() {
+            let shape = if i == 0 { "doublecircle" } else { "circle" };
+            writeln!(
+                dot,
+                "  {i} [shape={shape}, label=\"{}\\n{}\\n[{},{}]\"];",
+                pt.id, pt.demand, pt.start, pt.due
+            )
+            .unwrap();
+        }
+
+        let routes = self.routes.len().max(1);
+        for (r, route) in self.routes.iter().enumerate() {
+            let color = format!("{:.3} 0.800 0.800", r as f64 / routes as f64);
+            let stops = std::iter::once(0)
+                .chain(route.iter().copied())
+                .chain(std::iter::once(0))
+                .collect::<Vec<_>>();
+            for leg in stops.windows(2) {
+                let (from, to) = (leg[0], leg[1]);
+                if from >= inst.pts.len() || to >= inst.pts.len() {
+                    continue;
+                }
+                writeln!(
+                    dot,
+                    "  {from} -> {to} [color=\"{color}\", label=\"{:.2}\"];",
+                    inst.between(from, to).to_f64()
+                )
+                .unwrap();
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn route_graph() {
+        let instance = concat!(
+            "2 10\n",
+            "0 0 0 0 0 100 0\n",
+            "1 3 4 5 0 100 0\n",
+            "2 0 10 5 20 90 0\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+        let sol = Solution {
+            instance_name: String::new(),
+            routes: vec![vec![1, 2]],
+            ..Default::default()
+        };
+
+        let dot = sol.to_dot(&inst);
+        assert!(dot.starts_with("digraph \"\" {\n"));
+        assert!(dot.contains("  0 [shape=doublecircle, label=\"0\\n0\\n[0,100]\"];\n"));
+        assert!(dot.contains("  2 [shape=circle, label=\"2\\n5\\n[20,90]\"];\n"));
+        assert!(dot.contains("  0 -> 1 [color=\"0.000 0.800 0.800\", label=\"5.00\"];\n"));
+        assert_eq!(dot.matches("->").count(), 3);
+        assert!(dot.ends_with("}\n"));
+    }
+}
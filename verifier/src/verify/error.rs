@@ -0,0 +1,333 @@
+use std::fmt::Display;
+
+use super::solution::Solution;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    TimeWindowViolation {
+        route_id: usize,
+        position: usize,
+        point_id: i32,
+        arrival: rug::Float,
+        due: i32,
+    },
+    DepotReturnViolation {
+        route_id: usize,
+        arrival: rug::Float,
+        due: i32,
+    },
+    RouteDurationExceeded {
+        route_id: usize,
+        duration: rug::Float,
+        limit: i32,
+    },
+    CapacityExceeded {
+        route_id: usize,
+        position: usize,
+        point_id: i32,
+        load: i32,
+        capacity: i32,
+    },
+    NegativeLoad {
+        route_id: usize,
+        position: usize,
+        point_id: i32,
+        load: i32,
+    },
+    EmptyRoute {
+        route_id: usize,
+    },
+    UnfinishedRoute {
+        route_id: usize,
+    },
+    DepotVisit {
+        route_id: usize,
+        position: usize,
+        point_id: usize,
+    },
+    UnknownPoint {
+        point_id: usize,
+        route_id: usize,
+        position: usize,
+    },
+    DuplicateVisit {
+        point_id: usize,
+        route_a: usize,
+        route_b: usize,
+    },
+    MissingVisit {
+        point_id: usize,
+    },
+    PdpSameRoute {
+        pickup: usize,
+        delivery: usize,
+        route_a: usize,
+        route_b: usize,
+    },
+    PdpOrdering {
+        pickup: usize,
+        delivery: usize,
+        pickup_pos: usize,
+        delivery_pos: usize,
+    },
+    PdpChainViolation {
+        before: usize,
+        after: usize,
+        route_a: usize,
+        route_b: usize,
+        before_pos: usize,
+        after_pos: usize,
+    },
+    WrongDepot {
+        route_id: usize,
+        depot: usize,
+        start: usize,
+        end: usize,
+    },
+    TooManyVehicles {
+        used: usize,
+        allowed: i32,
+    },
+    TooFewVehicles {
+        used: usize,
+        required: i32,
+    },
+    VehicleAssignmentLength {
+        assigned: usize,
+        routes: usize,
+    },
+    UnknownVehicle {
+        route_id: usize,
+        vehicle: usize,
+    },
+    DuplicateVehicle {
+        vehicle: usize,
+        route_a: usize,
+        route_b: usize,
+    },
+    InstanceNameMismatch {
+        solution: String,
+        instance: String,
+    },
+    InvalidInstance(String),
+    ParseError(String),
+    Io(String),
+}
+
+impl Display for VerifyError {
+    // positions are counted from 1 here, as in the written routes
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::TimeWindowViolation {
+                route_id,
+                position,
+                point_id,
+                arrival,
+                ..
+            } => write!(
+                f,
+                "arrived too late ({arrival}) at {point_id} in route {route_id} at position {}",
+                position + 1
+            ),
+            VerifyError::DepotReturnViolation {
+                route_id, arrival, ..
+            } => write!(f, "arrived too late ({arrival}) in route {route_id} at depot"),
+            VerifyError::RouteDurationExceeded {
+                route_id,
+                duration,
+                limit,
+            } => write!(
+                f,
+                "route {route_id} takes too long ({duration} > {limit})"
+            ),
+            VerifyError::CapacityExceeded {
+                route_id,
+                position,
+                point_id,
+                load,
+                capacity,
+            } => write!(
+                f,
+                "load is greater than max load ({load} > {capacity}) at {point_id} in route {route_id} at position {}",
+                position + 1
+            ),
+            VerifyError::NegativeLoad {
+                route_id,
+                position,
+                point_id,
+                ..
+            } => write!(
+                f,
+                "current load is negative at {point_id} in route {route_id} at position {}",
+                position + 1
+            ),
+            VerifyError::EmptyRoute { route_id } => write!(f, "route {route_id} is empty"),
+            VerifyError::UnfinishedRoute { route_id } => {
+                write!(f, "route {route_id} was not ended")
+            }
+            VerifyError::DepotVisit {
+                route_id,
+                position,
+                point_id,
+            } => write!(
+                f,
+                "route {route_id} visits depot at non-terminal position {} (customer id {point_id}, the depot)",
+                position + 1
+            ),
+            VerifyError::UnknownPoint {
+                point_id,
+                route_id,
+                position,
+            } => write!(
+                f,
+                "node {point_id} in route {route_id} at position {} is not described in the instance",
+                position + 1
+            ),
+            VerifyError::DuplicateVisit {
+                point_id,
+                route_a,
+                route_b,
+            } => write!(
+                f,
+                "node {point_id} visited at least two times (in routes {route_a} and {route_b})"
+            ),
+            VerifyError::MissingVisit { point_id } => {
+                write!(f, "node {point_id} not visited in any route")
+            }
+            VerifyError::PdpSameRoute {
+                pickup,
+                delivery,
+                route_a,
+                route_b,
+            } => write!(
+                f,
+                "pickup {pickup} and delivery {delivery} are not in the same routes (are in routes {route_a} and {route_b})"
+            ),
+            VerifyError::PdpOrdering {
+                pickup,
+                delivery,
+                pickup_pos,
+                delivery_pos,
+            } => write!(
+                f,
+                "delivery {delivery} is before its pickup {pickup} (are on positions {} and {})",
+                delivery_pos + 1,
+                pickup_pos + 1
+            ),
+            VerifyError::PdpChainViolation {
+                before,
+                after,
+                route_a,
+                route_b,
+                before_pos,
+                after_pos,
+            } => write!(
+                f,
+                "{before} has to be visited before {after} in the same route (are in routes {route_a} and {route_b} on positions {} and {})",
+                before_pos + 1,
+                after_pos + 1
+            ),
+            VerifyError::WrongDepot {
+                route_id,
+                depot,
+                start,
+                end,
+            } => write!(
+                f,
+                "route {route_id} has to start and end at depot {depot}, but goes from {start} to {end}"
+            ),
+            VerifyError::TooManyVehicles { used, allowed } => {
+                write!(f, "more vehicles than allowed ({used} > {allowed})")
+            }
+            VerifyError::TooFewVehicles { used, required } => {
+                write!(f, "fewer vehicles than required ({used} < {required})")
+            }
+            VerifyError::VehicleAssignmentLength { assigned, routes } => write!(
+                f,
+                "vehicles are assigned to {assigned} routes, but the solution has {routes} routes"
+            ),
+            VerifyError::UnknownVehicle { route_id, vehicle } => write!(
+                f,
+                "route {route_id} is assigned to vehicle {vehicle}, which is not in the fleet"
+            ),
+            VerifyError::DuplicateVehicle {
+                vehicle,
+                route_a,
+                route_b,
+            } => write!(
+                f,
+                "vehicle {vehicle} is used by at least two routes ({route_a} and {route_b})"
+            ),
+            VerifyError::InstanceNameMismatch { solution, instance } => write!(
+                f,
+                "solution instance name '{solution}' does not match instance name '{instance}' — did you pass the wrong files?"
+            ),
+            VerifyError::InvalidInstance(msg)
+            | VerifyError::ParseError(msg)
+            | VerifyError::Io(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl VerifyError {
+    // the route (counted from 1) the error is about, errors about two routes
+    // or the whole solution have none
+    pub fn route_id(&self) -> Option<usize> {
+        match self {
+            VerifyError::TimeWindowViolation { route_id, .. }
+            | VerifyError::DepotReturnViolation { route_id, .. }
+            | VerifyError::RouteDurationExceeded { route_id, .. }
+            | VerifyError::CapacityExceeded { route_id, .. }
+            | VerifyError::NegativeLoad { route_id, .. }
+            | VerifyError::EmptyRoute { route_id }
+            | VerifyError::UnfinishedRoute { route_id }
+            | VerifyError::DepotVisit { route_id, .. }
+            | VerifyError::UnknownPoint { route_id, .. }
+            | VerifyError::WrongDepot { route_id, .. }
+            | VerifyError::UnknownVehicle { route_id, .. } => Some(*route_id),
+            _ => None,
+        }
+    }
+
+    // the message followed by the route it is about, as it is in the solution
+    pub fn in_context(&self, sol: &Solution) -> String {
+        let route = self
+            .route_id()
+            .and_then(|id| sol.routes.get(id.checked_sub(1)?))
+            .filter(|route| !route.is_empty());
+        match route {
+            Some(route) => format!("{self} [route: {}]", itertools::join(route, " ")),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_in_context() {
+        let sol = Solution {
+            routes: vec![vec![1, 2], vec![10, 33, 42]],
+            ..Default::default()
+        };
+        let late = VerifyError::NegativeLoad {
+            route_id: 2,
+            position: 2,
+            point_id: 42,
+            load: -1,
+        };
+        assert_eq!(
+            late.in_context(&sol),
+            "current load is negative at 42 in route 2 at position 3 [route: 10 33 42]"
+        );
+
+        let missing = VerifyError::MissingVisit { point_id: 3 };
+        assert_eq!(missing.in_context(&sol), missing.to_string());
+        let unknown = VerifyError::EmptyRoute { route_id: 7 };
+        assert_eq!(unknown.in_context(&sol), unknown.to_string());
+    }
+}
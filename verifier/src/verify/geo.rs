@@ -0,0 +1,128 @@
+use super::instance::{Distances, Instance, Point, Precision};
+use super::VerifyError;
+use crate::numeric::flf64;
+use serde::{Deserialize, Serialize};
+
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+fn radians(degrees: f64) -> rug::Float {
+    let pi = rug::Float::with_val(Precision::STANDARD.0, rug::float::Constant::Pi);
+    flf64(degrees) * pi / 180
+}
+
+impl GeoPoint {
+    pub fn haversine_dist(&self, other: &GeoPoint) -> rug::Float {
+        let lat1 = radians(self.lat);
+        let lat2 = radians(other.lat);
+        let half_dlat: rug::Float = (radians(other.lat) - &lat1) / 2;
+        let half_dlon: rug::Float = (radians(other.lon) - radians(self.lon)) / 2;
+
+        let a = half_dlat.sin().square() + lat1.cos() * lat2.cos() * half_dlon.sin().square();
+        let c = a.sqrt().asin() * 2;
+
+        c * EARTH_RADIUS_KM
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct GeoInstance {
+    pub inst: Instance,
+    pub geo: Vec<GeoPoint>,
+}
+
+fn parse_geo_row(row: &str, geo: &mut Vec<GeoPoint>) -> Result<Point, VerifyError> {
+    let mut cols: Vec<&str> = row.split_whitespace().collect();
+    if cols.len() < 3 {
+        Err(VerifyError::ParseError(format!(
+            "expected latitude and longitude in line `{row}'"
+        )))?;
+    }
+
+    let coord = |c: &str| {
+        c.parse::<f64>().map_err(|_| {
+            VerifyError::ParseError(format!(
+                "can't parse line `{row}': `{c}' is not a valid coordinate"
+            ))
+        })
+    };
+
+    geo.push(GeoPoint {
+        lat: coord(cols[1])?,
+        lon: coord(cols[2])?,
+    });
+
+    cols[1] = "0";
+    cols[2] = "0";
+    cols.join(" ").parse()
+}
+
+impl GeoInstance {
+    pub fn from_geo_str(s: &str) -> Result<Self, VerifyError> {
+        let mut geo = vec![];
+        let inst = Instance::parse_with(s, |row| parse_geo_row(row, &mut geo))?;
+        let geo_inst = GeoInstance { inst, geo };
+        geo_inst.inst.check_sanity_with(&geo_inst)?;
+        Ok(geo_inst)
+    }
+}
+
+impl Distances for GeoInstance {
+    fn between(&self, from: usize, to: usize) -> rug::Float {
+        self.geo[from].haversine_dist(&self.geo[to])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn haversine_between_cities() {
+        let lodz = GeoPoint {
+            lat: 51.7592,
+            lon: 19.4560,
+        };
+        let warsaw = GeoPoint {
+            lat: 52.2297,
+            lon: 21.0122,
+        };
+
+        let d = lodz.haversine_dist(&warsaw).to_f64();
+        assert!((d - 118.9).abs() < 0.5, "{d}");
+        assert_eq!(lodz.haversine_dist(&lodz), 0);
+        assert_eq!(lodz.haversine_dist(&warsaw), warsaw.haversine_dist(&lodz));
+    }
+
+    #[test]
+    fn read_geo_instance() {
+        let instance = concat!(
+            "2 100\n",
+            "0 51.7592 19.4560 0 0 1000 0\n",
+            "1 52.2297 21.0122 10 0 500 10\n",
+        );
+
+        let geo = GeoInstance::from_geo_str(instance).unwrap();
+
+        assert_eq!(
+            geo.geo,
+            vec![
+                GeoPoint {
+                    lat: 51.7592,
+                    lon: 19.4560
+                },
+                GeoPoint {
+                    lat: 52.2297,
+                    lon: 21.0122
+                }
+            ]
+        );
+        assert_eq!(geo.inst.pts[1].demand, 10);
+        assert_eq!(geo.between(0, 1), geo.geo[0].haversine_dist(&geo.geo[1]));
+    }
+}
@@ -1,10 +1,14 @@
+use super::VerifyError;
+use crate::numeric::{fl, fl_prec};
 use itertools::Itertools;
 use pest::Parser;
 use pest_derive::Parser;
-use rug;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
-use serde_with;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 
 #[derive(Parser)]
@@ -12,117 +16,281 @@ use std::str::FromStr;
 pub struct InstanceParser;
 
 #[serde_with::serde_as]
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Point {
     pub id: i32,
-    pub x: i32,
-    pub y: i32,
+    pub x: f64,
+    pub y: f64,
     pub demand: i32,
     pub start: i32,
     pub due: i32,
     pub service: i32,
     pub pickup_delivery: Option<(i32, i32)>,
+    // points that have to be visited in this order, in one route
+    #[serde(default)]
+    pub pickup_delivery_chain: Option<Vec<usize>>,
 }
 
-pub const PRECISION: u32 = 128;
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Precision(pub u32);
 
+impl Precision {
+    pub const FAST: Precision = Precision(64);
+    pub const STANDARD: Precision = Precision(128);
+    pub const HIGH: Precision = Precision(256);
 
-pub fn fl(val: i32) -> rug::Float {
-    rug::Float::with_val(PRECISION, val)
+    pub fn fl(self, val: i32) -> rug::Float {
+        fl_prec(val, self.0)
+    }
+
+    pub fn flf64(self, val: f64) -> rug::Float {
+        rug::Float::with_val(self.0, val)
+    }
 }
 
-pub fn flf64(val: f64) -> rug::Float {
-    rug::Float::with_val(PRECISION, val)
+impl Default for Precision {
+    fn default() -> Self {
+        Precision::STANDARD
+    }
 }
 
-impl Point {
-    pub fn dist(&self, other: &Self) -> rug::Float {
-        let xs = self.x - other.x;
-        let ys = self.y - other.y;
-        fl(xs * xs + ys * ys).sqrt()
+impl Display for Precision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-pub fn calc_route_distance(inst: &Instance, route: &Vec<usize>) -> rug::Float {
-    let depot = &inst.pts[0];
-    let first = &inst.pts[route[0]];
+impl FromStr for Precision {
+    type Err = VerifyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let prec = match s.to_lowercase().as_str() {
+            "fast" => Precision::FAST,
+            "standard" => Precision::STANDARD,
+            "high" => Precision::HIGH,
+            bits => Precision(bits.parse().map_err(|_| {
+                VerifyError::ParseError(format!(
+                    "unknown precision `{s}', expected fast, standard, high or a number of bits"
+                ))
+            })?),
+        };
 
-    let l = *route.last().unwrap();
-    let last = &inst.pts[l];
+        if prec.0 < rug::float::prec_min() || prec.0 > rug::float::prec_max() {
+            Err(VerifyError::ParseError(format!(
+                "precision {} is out of the supported range",
+                prec.0
+            )))?;
+        }
 
-    let route_distance = route
-        .iter()
-        .map(|&p| &inst.pts[p])
-        .tuple_windows()
-        .map(|(from, to)| from.dist(to))
-        .reduce(std::ops::Add::add)
-        .unwrap_or(fl(0));
+        Ok(prec)
+    }
+}
 
-    depot.dist(first) + route_distance + last.dist(depot)
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceMode {
+    #[default]
+    Euclidean,
+    Manhattan,
+    Asymmetric,
 }
 
-pub fn check_route_time(
-    inst: &Instance,
-    route_id: usize,
-    route: &Vec<usize>,
-) -> Result<(), String> {
-    let depot = &inst.pts[0];
-    let first = &inst.pts[route[0]];
-    let mut time = fl(depot.start + depot.service);
-    time += depot.dist(first);
+impl Display for DistanceMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DistanceMode::Euclidean => write!(f, "euclidean"),
+            DistanceMode::Manhattan => write!(f, "manhattan"),
+            DistanceMode::Asymmetric => write!(f, "asymmetric"),
+        }
+    }
+}
 
-    if time > first.due as f64 {
-        Err(format!(
-            "arrived too late ({}) at {} in route {} at position 0",
-            time, first.id, route_id,
-        ))?;
+impl FromStr for DistanceMode {
+    type Err = VerifyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "euclidean" => Ok(DistanceMode::Euclidean),
+            "manhattan" => Ok(DistanceMode::Manhattan),
+            "asymmetric" => Ok(DistanceMode::Asymmetric),
+            _ => Err(VerifyError::ParseError(format!(
+                "unknown distance mode `{s}', expected euclidean, manhattan or asymmetric"
+            ))),
+        }
     }
+}
 
-    time = time.max(&rug::Float::with_val(PRECISION, first.start));
+// Solomon (c101), Gehring & Homberger (c1_2_1) and Li & Lim (lc101, lc1_2_1)
+// instances are grouped by the clustering of customers and the horizon
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum InstanceFamily {
+    C1,
+    C2,
+    R1,
+    R2,
+    RC1,
+    RC2,
+    LC1,
+    LC2,
+    LR1,
+    LR2,
+    LRC1,
+    LRC2,
+    Unknown,
+}
 
-    time += first.service;
+impl Display for InstanceFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
 
-    for ((_, f), (tidx, t)) in route.iter().enumerate().tuple_windows() {
-        let from = &inst.pts[*f];
-        let to = &inst.pts[*t];
+impl FromStr for InstanceFamily {
+    type Err = VerifyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unknown" => Ok(InstanceFamily::Unknown),
+            family => match InstanceFamily::from_name(family) {
+                InstanceFamily::Unknown => Err(VerifyError::ParseError(format!(
+                    "unknown instance family `{s}', expected e.g. c1, rc2 or lr1"
+                ))),
+                known => Ok(known),
+            },
+        }
+    }
+}
 
-        time += from.dist(to);
+impl InstanceFamily {
+    pub fn from_name(name: &str) -> InstanceFamily {
+        let name = name.to_lowercase();
+        let (li_lim, rest) = match name.strip_prefix('l') {
+            Some(rest) => (true, rest),
+            None => (false, name.as_str()),
+        };
+        let (kind, rest) = ["rc", "r", "c"]
+            .into_iter()
+            .find_map(|kind| Some((kind, rest.strip_prefix(kind)?)))
+            .unwrap_or(("", rest));
+        let mut chars = rest.chars();
+        let class = chars.next();
+        let suffix = chars.as_str();
+
+        // the class is followed by `_size_index' or by the two digit index
+        let numbered = suffix.starts_with('_')
+            || (suffix.len() == 2 && suffix.chars().all(|c| c.is_ascii_digit()));
+        if !numbered {
+            return InstanceFamily::Unknown;
+        }
 
-        if time > to.due as f64 {
-            Err(format!(
-                "arrived too late ({}) at {} in route {} at position {}",
-                time, to.id, route_id, tidx
-            ))?;
+        match (li_lim, kind, class) {
+            (false, "c", Some('1')) => InstanceFamily::C1,
+            (false, "c", Some('2')) => InstanceFamily::C2,
+            (false, "r", Some('1')) => InstanceFamily::R1,
+            (false, "r", Some('2')) => InstanceFamily::R2,
+            (false, "rc", Some('1')) => InstanceFamily::RC1,
+            (false, "rc", Some('2')) => InstanceFamily::RC2,
+            (true, "c", Some('1')) => InstanceFamily::LC1,
+            (true, "c", Some('2')) => InstanceFamily::LC2,
+            (true, "r", Some('1')) => InstanceFamily::LR1,
+            (true, "r", Some('2')) => InstanceFamily::LR2,
+            (true, "rc", Some('1')) => InstanceFamily::LRC1,
+            (true, "rc", Some('2')) => InstanceFamily::LRC2,
+            _ => InstanceFamily::Unknown,
+        }
+    }
+}
+
+impl Point {
+    pub fn dist(&self, other: &Self) -> rug::Float {
+        self.dist_with(other, DistanceMode::Euclidean, Precision::STANDARD)
+    }
+
+    pub fn dist_mode(&self, other: &Self, mode: DistanceMode) -> rug::Float {
+        self.dist_with(other, mode, Precision::STANDARD)
+    }
+
+    // coordinates alone can not describe asymmetric distances, those come from the instance matrix
+    pub fn dist_with(&self, other: &Self, mode: DistanceMode, prec: Precision) -> rug::Float {
+        let xs = prec.flf64(self.x) - other.x;
+        let ys = prec.flf64(self.y) - other.y;
+        match mode {
+            DistanceMode::Euclidean | DistanceMode::Asymmetric => {
+                (xs.square() + ys.square()).sqrt()
+            }
+            DistanceMode::Manhattan => xs.abs() + ys.abs(),
         }
+    }
+}
+
+pub trait Distances {
+    fn between(&self, from: usize, to: usize) -> rug::Float;
+
+    fn precision(&self) -> Precision {
+        Precision::STANDARD
+    }
+}
 
-        time = time.max(&fl(to.start));
-        time += to.service;
+// coordinates are floats, they are left out, so points that are equal still
+// hash the same
+impl Hash for Point {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.demand.hash(state);
+        self.start.hash(state);
+        self.due.hash(state);
+        self.service.hash(state);
+        self.pickup_delivery.hash(state);
+        self.pickup_delivery_chain.hash(state);
+    }
+}
+
+// coordinates are checked to be finite when parsed, so `==` is an equivalence
+impl Eq for Point {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceMatrix(Vec<Vec<rug::Float>>);
+
+impl DistanceMatrix {
+    pub fn dist(&self, i: usize, j: usize) -> &rug::Float {
+        &self.0[i][j]
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
     }
 
-    let l = *route.last().unwrap();
-    let last = &inst.pts[l];
-    time += last.dist(&depot);
-    if time > depot.due as f64 {
-        Err(format!(
-            "arrived too late ({}) in route {} at depot",
-            time, route_id,
-        ))?;
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
+}
 
-    Ok(())
+impl Distances for DistanceMatrix {
+    fn between(&self, from: usize, to: usize) -> rug::Float {
+        self.dist(from, to).clone()
+    }
 }
 
+// the route checks live in verify.rs, they are exported here too because this
+// is where they used to be
+pub use super::{calc_route_distance, check_route_time};
+
+// fails to compile when a copy with a different signature shows up here again
+const _: fn(&Instance, &[usize], DistanceMode) -> Result<rug::Float, VerifyError> =
+    calc_route_distance;
+const _: fn(&Instance, usize, &[usize], DistanceMode) -> Result<(), VerifyError> = check_route_time;
+
 impl Display for Point {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.pickup_delivery {
-            None => write!(
+            None => writeln!(
                 f,
-                "{:5} {:7} {:10} {:10} {:10} {:10} {:10}\n",
+                "{:5} {:7} {:10} {:10} {:10} {:10} {:10}",
                 self.id, self.x, self.y, self.demand, self.start, self.due, self.service
             ),
-            Some((pickup, delivery)) => write!(
+            Some((pickup, delivery)) => writeln!(
                 f,
-                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
                 self.id,
                 self.x,
                 self.y,
@@ -138,77 +306,225 @@ impl Display for Point {
 }
 
 impl FromStr for Point {
-    type Err = String;
+    type Err = VerifyError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut vs: Vec<i32> = Vec::with_capacity(9);
-
-        for (i, c) in s.split_whitespace().into_iter().enumerate() {
-            match c.parse() {
-                Ok(n) => vs.push(n),
-                Err(_) => return Err(format!("can't parse line `{s}': error in trying to parse field {i}: `{c}' can not be parsed ")),
+        let mut vs: Vec<i32> = Vec::with_capacity(7);
+        let mut coords: Vec<f64> = Vec::with_capacity(2);
+
+        for (i, c) in s.split_whitespace().enumerate() {
+            // only the coordinates may have a fractional part
+            let parsed = match i {
+                1 | 2 => c
+                    .parse()
+                    .ok()
+                    .filter(|x: &f64| x.is_finite())
+                    .map(|x| coords.push(x)),
+                _ => c.parse().ok().map(|n| vs.push(n)),
             };
+            if parsed.is_none() {
+                return Err(VerifyError::ParseError(format!("can't parse line `{s}': error in trying to parse field {i}: `{c}' can not be parsed ")));
+            }
         }
 
-        let nums = vs.len();
+        let nums = vs.len() + coords.len();
 
         if nums != 7 && nums != 9 {
-            Err(format!(
-                "expected 7 or 9 integers in line `{s}', have {nums} numbers"
-            ))?;
+            Err(VerifyError::ParseError(format!(
+                "expected 7 or 9 numbers in line `{s}', have {nums} numbers"
+            )))?;
         }
 
         Ok(Point {
             id: vs[0],
-            x: vs[1],
-            y: vs[2],
-            demand: vs[3],
-            start: vs[4],
-            due: vs[5],
-            service: vs[6],
-            pickup_delivery: if vs.len() > 7 {
-                Some((vs[7], vs[8]))
+            x: coords[0],
+            y: coords[1],
+            demand: vs[1],
+            start: vs[2],
+            due: vs[3],
+            service: vs[4],
+            pickup_delivery: if vs.len() > 5 {
+                Some((vs[5], vs[6]))
             } else {
                 None
             },
+            pickup_delivery_chain: None,
         })
     }
 }
 
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InstanceStats {
+    pub num_customers: usize,
+    pub total_demand: i64,
+    pub avg_demand: f64,
+    pub max_demand: i32,
+    pub time_horizon: i32,
+    pub avg_time_window_width: f64,
+    pub min_time_window_width: i64,
+    pub customers_with_tight_windows: usize,
+    pub is_pdp: bool,
+    pub num_pdp_pairs: usize,
+    pub centroid: (f64, f64),
+    pub max_coordinate_span: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub enum DistanceSource {
+    #[default]
+    Euclidean,
+    Matrix(Vec<Vec<rug::Float>>),
+}
+
+// the derived serde representation is the canonical json format, used by
+// `to_json`, `from_json` and the server
 #[serde_with::serde_as]
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Instance {
     pub name: String,
     pub vehicles: i32,
     pub max_capacity: i32,
     pub pts: Vec<Point>,
     pub is_pdp: bool,
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub distances: DistanceSource,
+    // the longest time a vehicle may spend away from its depot
+    #[serde(default)]
+    pub max_route_duration: Option<i32>,
+    // the solution has to use at least this many vehicles
+    #[serde(default)]
+    pub min_vehicles: Option<i32>,
+    // vehicles do not return to the depot at the end of their routes
+    #[serde(default)]
+    pub open_routes: bool,
+    // capacity of every vehicle, an empty list means `max_capacity` (or the
+    // capacity of its depot)
+    #[serde(default)]
+    pub vehicle_capacities: Vec<i32>,
+    // indices of depots in `pts`, an empty list means a single depot at 0
+    #[serde(default)]
+    pub depots: Vec<usize>,
+    // vehicle capacity of every depot, an empty list means `max_capacity`
+    #[serde(default)]
+    pub depot_capacities: Vec<i32>,
+    // index into `depots` for every vehicle, without it a route may use any depot
+    #[serde(default)]
+    pub vehicle_depot_assignment: Option<Vec<usize>>,
+    // late arrivals at customers are penalized by `penalty_per_unit` for every
+    // unit of lateness instead of making the solution infeasible
+    #[serde(default)]
+    pub soft_windows: bool,
+    #[serde(default)]
+    pub penalty_per_unit: f64,
+}
+
+// equal instances hash the same, the other way round only `==` can tell, the
+// distances and the other float fields are not hashed
+impl Hash for Instance {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.vehicles.hash(state);
+        self.max_capacity.hash(state);
+        self.is_pdp.hash(state);
+        self.pts.hash(state);
+    }
 }
 
+// coordinates, matrix distances and penalties are checked to be finite, so
+// `==` is an equivalence here as well
+impl Eq for Instance {}
+
 impl Display for Instance {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut limits = String::new();
+        if let Some(limit) = self.max_route_duration {
+            limits += &format!("MAX ROUTE DURATION: {limit}\n");
+        }
+        if let Some(min) = self.min_vehicles {
+            limits += &format!("MIN VEHICLES: {min}\n");
+        }
         if self.is_pdp {
-            write! {f, "{}\t{}\t0\n", &self.vehicles, self.max_capacity}?;
+            // li&lim files have no name line, but it is kept when there is a
+            // name, so that it survives a round trip
+            if !self.name.is_empty() {
+                writeln!(f, "{}", self.name)?;
+            }
+            write!(f, "{limits}")?;
+            writeln! {f, "{}\t{}\t0", &self.vehicles, self.max_capacity}?;
         } else {
-            write! {f, "{}\n\nVEHICLE\nNUMBER     CAPACITY\n{:4}{:13}\n\nCUSTOMER\nCUST NO.  XCOORD.    YCOORD.    DEMAND   READY TIME  DUE DATE   SERVICE TIME\n\n", &self.name, self.vehicles, self.max_capacity}?;
+            write! {f, "{}\n{limits}\nVEHICLE\nNUMBER     CAPACITY\n{:4}{:13}\n\nCUSTOMER\nCUST NO.  XCOORD.    YCOORD.    DEMAND   READY TIME  DUE DATE   SERVICE TIME\n\n", &self.name, self.vehicles, self.max_capacity}?;
         };
         for pt in self.pts.iter() {
             write!(f, "{}", pt)?;
         }
+        if self.open_routes {
+            writeln!(f, "\nOPEN")?;
+        }
+        if self.soft_windows {
+            writeln!(f, "\nSOFT WINDOWS: {}", self.penalty_per_unit)?;
+        }
+        if !self.vehicle_capacities.is_empty() {
+            writeln!(
+                f,
+                "\nVEHICLE CAPACITIES: {}",
+                self.vehicle_capacities.iter().join(" ")
+            )?;
+        }
+        if !self.depots.is_empty() {
+            writeln!(f, "\nDEPOTS:")?;
+            for (i, depot) in self.depots.iter().enumerate() {
+                match self.depot_capacities.get(i) {
+                    None => writeln!(f, "{depot}")?,
+                    Some(capacity) => writeln!(f, "{depot} {capacity}")?,
+                }
+            }
+            if let Some(assignment) = &self.vehicle_depot_assignment {
+                writeln!(f, "VEHICLE DEPOTS: {}", assignment.iter().join(" "))?;
+            }
+        }
+        if let DistanceSource::Matrix(matrix) = &self.distances {
+            writeln!(f, "\nDISTANCE MATRIX\n")?;
+            for row in matrix.iter() {
+                writeln!(f, "{}", row.iter().join(" "))?;
+            }
+        }
         Ok(())
     }
 }
 
 impl FromStr for Instance {
-    type Err = String;
+    type Err = VerifyError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parsed = InstanceParser::parse(Rule::file, &s)
-            .map_err(|x| format!("Instance parsing problem: {x}"))?
+        let inst = Instance::parse_with(s, str::parse)?;
+        inst.check_sanity()?;
+        Ok(inst)
+    }
+}
+
+impl Instance {
+    pub(crate) fn parse_with(
+        s: &str,
+        mut parse_row: impl FnMut(&str) -> Result<Point, VerifyError>,
+    ) -> Result<Self, VerifyError> {
+        let parsed = InstanceParser::parse(Rule::file, s)
+            .map_err(|x| VerifyError::ParseError(format!("Instance parsing problem: {x}")))?
             .next()
             .unwrap();
 
         let mut pts: Vec<Point> = vec![];
         let mut v: Vec<i32> = vec![];
         let mut name = "".to_string();
+        let mut distances = DistanceSource::Euclidean;
+        let mut max_route_duration = None;
+        let mut min_vehicles = None;
+        let mut open_routes = false;
+        let mut vehicle_capacities = vec![];
+        let mut depots = vec![];
+        let mut depot_capacities = vec![];
+        let mut vehicle_depot_assignment = None;
+        let mut penalty_per_unit = None;
 
         for r in parsed.into_inner() {
             match r.as_rule() {
@@ -217,16 +533,67 @@ impl FromStr for Instance {
                         .as_span()
                         .as_str()
                         .split_whitespace()
-                        .into_iter()
-                        .map(|c| c.parse().unwrap_or_default())
-                        .collect();
+                        .map(|c| parse_number(c, "vehicle count or capacity"))
+                        .collect::<Result<_, _>>()?;
                 }
                 Rule::row => {
-                    pts.push(r.as_span().as_str().parse().unwrap());
+                    pts.push(parse_row(r.as_span().as_str())?);
                 }
                 Rule::instance_name => {
                     name = r.as_span().as_str().to_string();
                 }
+                Rule::matrix => {
+                    distances = DistanceSource::Matrix(
+                        r.into_inner()
+                            .map(|row| row.into_inner().map(parse_matrix_value).collect())
+                            .collect::<Result<_, _>>()?,
+                    );
+                }
+                Rule::max_route_duration => {
+                    let limit = r.into_inner().next().unwrap().as_str();
+                    let limit = parse_number(limit, "maximum route duration")?;
+                    if limit < 0 {
+                        Err(VerifyError::ParseError(format!(
+                            "maximum route duration can't be negative, it is {limit}"
+                        )))?;
+                    }
+                    max_route_duration = Some(limit);
+                }
+                Rule::min_vehicles => {
+                    let min = r.into_inner().next().unwrap().as_str();
+                    min_vehicles = Some(parse_number(min, "minimum number of vehicles")?);
+                }
+                Rule::open => open_routes = true,
+                Rule::soft_windows => {
+                    penalty_per_unit = Some(parse_penalty(r.into_inner().next().unwrap())?);
+                }
+                Rule::vehicle_capacities => {
+                    vehicle_capacities = r
+                        .into_inner()
+                        .map(|d| parse_number(d.as_str(), "vehicle capacity"))
+                        .collect::<Result<_, _>>()?;
+                }
+                Rule::depots => {
+                    for r in r.into_inner() {
+                        let rule = r.as_rule();
+                        let nums: Vec<usize> = r
+                            .into_inner()
+                            .map(|d| parse_number(d.as_str(), "depot"))
+                            .collect::<Result<_, _>>()?;
+                        match rule {
+                            Rule::depot => {
+                                depots.push(nums[0]);
+                                depot_capacities.extend(nums.get(1).map(|&c| c as i32));
+                            }
+                            _ => vehicle_depot_assignment = Some(nums),
+                        }
+                    }
+                    if !depot_capacities.is_empty() && depot_capacities.len() != depots.len() {
+                        Err(VerifyError::ParseError(
+                            "either all depots or none of them need a capacity".to_string(),
+                        ))?;
+                    }
+                }
                 Rule::d => {}
                 _ => unreachable!(),
             }
@@ -237,14 +604,302 @@ impl FromStr for Instance {
             max_capacity: v[1],
             is_pdp: pts[0].pickup_delivery.is_some(),
             pts,
+            distances,
+            max_route_duration,
+            min_vehicles,
+            open_routes,
+            vehicle_capacities,
+            depots,
+            depot_capacities,
+            vehicle_depot_assignment,
+            soft_windows: penalty_per_unit.is_some(),
+            penalty_per_unit: penalty_per_unit.unwrap_or_default(),
         };
-        inst.check_sanity()?;
         Ok(inst)
     }
 }
 
+fn parse_matrix_value(value: pest::iterators::Pair<Rule>) -> Result<rug::Float, VerifyError> {
+    let s = value.as_str();
+    rug::Float::parse(s)
+        .map(|v| rug::Float::with_val(Precision::STANDARD.0, v))
+        .map_err(|e| VerifyError::ParseError(format!("can't parse matrix value `{s}': {e}")))
+}
+
+// smallest and largest value of a coordinate, the points can not be empty
+pub(crate) fn coordinate_range(pts: &[Point], coord: impl Fn(&Point) -> f64) -> (f64, f64) {
+    pts.iter()
+        .map(coord)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), c| {
+            (min.min(c), max.max(c))
+        })
+}
+
+fn parse_number<T: FromStr>(s: &str, what: &str) -> Result<T, VerifyError>
+where
+    T::Err: Display,
+{
+    s.parse()
+        .map_err(|e| VerifyError::ParseError(format!("can't parse {what} `{s}': {e}")))
+}
+
+fn parse_penalty(value: pest::iterators::Pair<Rule>) -> Result<f64, VerifyError> {
+    let s = value.as_str();
+    s.parse()
+        .map_err(|e| VerifyError::ParseError(format!("can't parse penalty `{s}': {e}")))
+}
+
+impl Distances for Instance {
+    fn between(&self, from: usize, to: usize) -> rug::Float {
+        match &self.distances {
+            DistanceSource::Euclidean => self.pts[from].dist(&self.pts[to]),
+            DistanceSource::Matrix(matrix) => matrix[from][to].clone(),
+        }
+    }
+}
+
+pub struct ModeDistances<'a> {
+    inst: &'a Instance,
+    mode: DistanceMode,
+    prec: Precision,
+}
+
+impl Distances for ModeDistances<'_> {
+    fn between(&self, from: usize, to: usize) -> rug::Float {
+        match (self.mode, &self.inst.distances) {
+            (DistanceMode::Asymmetric, DistanceSource::Matrix(matrix)) => {
+                rug::Float::with_val(self.prec.0, &matrix[from][to])
+            }
+            (mode, _) => self.inst.pts[from].dist_with(&self.inst.pts[to], mode, self.prec),
+        }
+    }
+
+    fn precision(&self) -> Precision {
+        self.prec
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RoundingMode {
+    #[default]
+    None,
+    RoundToNearest(u32),
+    Truncate(u32),
+    Ceiling(u32),
+}
+
+impl RoundingMode {
+    pub fn apply(self, val: rug::Float) -> rug::Float {
+        let (decimals, op): (u32, fn(rug::Float) -> rug::Float) = match self {
+            RoundingMode::None => return val,
+            RoundingMode::RoundToNearest(d) => (d, rug::Float::round),
+            RoundingMode::Truncate(d) => (d, rug::Float::trunc),
+            RoundingMode::Ceiling(d) => (d, rug::Float::ceil),
+        };
+
+        let scale = rug::Float::with_val(val.prec(), rug::ops::Pow::pow(fl(10), decimals));
+        op(val * &scale) / scale
+    }
+}
+
+pub struct Rounded<'a, D: Distances> {
+    pub dists: &'a D,
+    pub rounding: RoundingMode,
+}
+
+impl<D: Distances> Distances for Rounded<'_, D> {
+    fn between(&self, from: usize, to: usize) -> rug::Float {
+        self.rounding.apply(self.dists.between(from, to))
+    }
+
+    fn precision(&self) -> Precision {
+        self.dists.precision()
+    }
+}
+
 impl Instance {
-    fn point_ids_are_sequential(&self) -> Result<(), String> {
+    pub fn to_binary(&self) -> Vec<u8> {
+        super::binary::encode(self)
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, VerifyError> {
+        let inst: Instance = super::binary::decode(bytes)?;
+        inst.check_sanity()?;
+        Ok(inst)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    // li&lim files have no name line, such instances get the given name (e.g.
+    // the name of their file) instead
+    pub fn from_str_with_name(s: &str, name: &str) -> Result<Self, VerifyError> {
+        let mut inst = Instance::from_str(s)?;
+        if inst.name.is_empty() {
+            inst.name = name.to_string();
+        }
+        Ok(inst)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, VerifyError> {
+        let inst: Instance = serde_json::from_str(s)
+            .map_err(|e| VerifyError::ParseError(format!("Instance parsing problem: {e}")))?;
+        inst.check_sanity()?;
+        Ok(inst)
+    }
+
+    pub fn depots(&self) -> Vec<usize> {
+        if self.depots.is_empty() {
+            vec![0]
+        } else {
+            self.depots.clone()
+        }
+    }
+
+    pub fn is_depot(&self, pt: usize) -> bool {
+        if self.depots.is_empty() {
+            pt == 0
+        } else {
+            self.depots.contains(&pt)
+        }
+    }
+
+    // the depot a vehicle has to start and end at, if it is fixed
+    pub fn vehicle_depot(&self, vehicle: usize) -> Option<usize> {
+        if self.depots.is_empty() {
+            return Some(0);
+        }
+        let assignment = self.vehicle_depot_assignment.as_ref()?;
+        assignment.get(vehicle).map(|&d| self.depots[d])
+    }
+
+    pub fn vehicle_capacity(&self, vehicle: usize, depot: usize) -> i32 {
+        self.vehicle_capacities
+            .get(vehicle)
+            .copied()
+            .unwrap_or_else(|| self.depot_capacity(depot))
+    }
+
+    pub fn depot_capacity(&self, depot: usize) -> i32 {
+        self.depots
+            .iter()
+            .position(|&d| d == depot)
+            .and_then(|i| self.depot_capacities.get(i))
+            .copied()
+            .unwrap_or(self.max_capacity)
+    }
+
+    // None for instances without a name
+    pub fn family(&self) -> Option<InstanceFamily> {
+        (!self.name.is_empty()).then(|| InstanceFamily::from_name(&self.name))
+    }
+
+    pub fn distance_mode(&self) -> DistanceMode {
+        match self.distances {
+            DistanceSource::Euclidean => DistanceMode::Euclidean,
+            DistanceSource::Matrix(_) => DistanceMode::Asymmetric,
+        }
+    }
+
+    pub fn with_mode(&self, mode: DistanceMode) -> Result<ModeDistances<'_>, VerifyError> {
+        self.distances_with(mode, Precision::STANDARD)
+    }
+
+    pub fn distances_with(
+        &self,
+        mode: DistanceMode,
+        prec: Precision,
+    ) -> Result<ModeDistances<'_>, VerifyError> {
+        if mode == DistanceMode::Asymmetric && self.distances == DistanceSource::Euclidean {
+            Err(VerifyError::InvalidInstance(format!(
+                "instance {} has no distance matrix required by the asymmetric distance mode",
+                self.name
+            )))?;
+        }
+        Ok(ModeDistances {
+            inst: self,
+            mode,
+            prec,
+        })
+    }
+
+    // from the distance matrix if there is one, computed on every call
+    pub fn distance(&self, i: usize, j: usize) -> rug::Float {
+        self.between(i, j)
+    }
+
+    // `distance_matrix()[i][j]` is `distance(i, j)`, O(n²) time and memory
+    pub fn distance_matrix(&self) -> Vec<Vec<rug::Float>> {
+        self.precompute_distances().0
+    }
+
+    // at most `k` other points for every point, nearest first, ties by index
+    pub fn nearest_neighbors(&self, k: usize) -> Vec<Vec<usize>> {
+        self.distance_matrix()
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                (0..row.len())
+                    .filter(|&j| j != i)
+                    .sorted_by(|&a, &b| row[a].total_cmp(&row[b]))
+                    .take(k)
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn precompute_distances(&self) -> DistanceMatrix {
+        let n = self.pts.len();
+        let mut matrix = vec![vec![fl(0); n]; n];
+        if let DistanceSource::Matrix(m) = &self.distances {
+            return DistanceMatrix(m.clone());
+        }
+        for (i, from) in self.pts.iter().enumerate() {
+            for (j, to) in self.pts.iter().enumerate().skip(i + 1) {
+                let d = from.dist(to);
+                matrix[j][i] = d.clone();
+                matrix[i][j] = d;
+            }
+        }
+        DistanceMatrix(matrix)
+    }
+
+    fn check_distances(&self) -> Result<(), VerifyError> {
+        let DistanceSource::Matrix(matrix) = &self.distances else {
+            return Ok(());
+        };
+
+        let n = self.pts.len();
+        if matrix.len() != n {
+            Err(VerifyError::InvalidInstance(format!(
+                "distance matrix has {} rows but the instance has {n} points",
+                matrix.len()
+            )))?;
+        }
+
+        for (i, row) in matrix.iter().enumerate() {
+            if row.len() != n {
+                Err(VerifyError::InvalidInstance(format!(
+                    "row {i} of the distance matrix has {} values but the instance has {n} points",
+                    row.len()
+                )))?;
+            }
+
+            if let Some(j) = row
+                .iter()
+                .position(|d| d.is_sign_negative() || !d.is_finite())
+            {
+                Err(VerifyError::InvalidInstance(format!(
+                    "distance from {i} to {j} in the distance matrix is not a non-negative number"
+                )))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn point_ids_are_sequential(&self) -> Result<(), VerifyError> {
         let pts: Vec<usize> = self
             .pts
             .iter()
@@ -255,153 +910,789 @@ impl Instance {
         if pts.is_empty() {
             Ok(())
         } else {
-            Err(format!("points {:?} do not have correct ids", pts))
+            Err(VerifyError::InvalidInstance(format!(
+                "points {:?} do not have correct ids",
+                pts
+            )))
         }
     }
 
-    fn check_demands(&self) -> Result<(), String> {
+    fn check_demands(&self) -> Result<(), VerifyError> {
         for pt in self.pts.iter() {
             if pt.demand > self.max_capacity {
-                Err(format!("point {} can not be visited because its demands are greater than vehicle capacity", pt.id))?;
+                Err(VerifyError::InvalidInstance(format!("point {} can not be visited because its demands are greater than vehicle capacity", pt.id)))?;
             }
 
             if pt.demand < 0 && !self.is_pdp {
-                Err(format!(
+                Err(VerifyError::InvalidInstance(format!(
                     "point {} has negative demands and this is not pdp",
                     pt.id
-                ))?;
+                )))?;
             }
 
             if !self.is_pdp && pt.pickup_delivery.is_some() {
-                Err(format!(
+                Err(VerifyError::InvalidInstance(format!(
                     "point {} has pickup and delivery but the instance is not pdp",
                     pt.id
-                ))?;
+                )))?;
             }
 
             if let Some((p, d)) = pt.pickup_delivery {
                 if p != 0 && d != 0 {
-                    Err(format!(
+                    Err(VerifyError::InvalidInstance(format!(
                         "point {} has nonzero both pickup ({p}) and delivery ({d})",
                         pt.id
-                    ))?;
+                    )))?;
                 }
 
                 let other_idx = if p != 0 { p } else { d };
 
                 if other_idx < 0 || other_idx >= self.pts.len() as i32 {
-                    Err(format!(
+                    Err(VerifyError::InvalidInstance(format!(
                         "points {} pdp pair {other_idx} does not refer to any legal point",
                         pt.id,
-                    ))?;
+                    )))?;
                 }
 
                 let other = &self.pts[other_idx as usize];
 
                 if other.pickup_delivery.is_none() {
-                    Err(format!("point {} pdp pair {other_idx} is not pdp", pt.id,))?;
+                    Err(VerifyError::InvalidInstance(format!(
+                        "point {} pdp pair {other_idx} is not pdp",
+                        pt.id,
+                    )))?;
                 }
 
                 if (other.pickup_delivery != Some((0, pt.id)))
                     && (other.pickup_delivery != Some((pt.id, 0)))
                 {
-                    Err(format!("point {} and {other_idx} are a pdp pair but their pickup and deliveries do not match", pt.id,))?;
+                    Err(VerifyError::InvalidInstance(format!("point {} and {other_idx} are a pdp pair but their pickup and deliveries do not match", pt.id,)))?;
                 }
 
                 let others_demand = other.demand;
                 if pt.demand + others_demand != 0 {
-                    Err(format!(
+                    Err(VerifyError::InvalidInstance(format!(
                         "point {} demands {} does not sum to 0 with ther pdp pair {other} demands {others_demand}",
                         pt.id,
                         pt.demand,
-                    ))?;
+                    )))?;
                 }
             }
         }
 
-        if self.is_pdp && self.pts[0].pickup_delivery != Some((0, 0)) {
-            Err(format!("depots pdp pair is not (0, 0)"))?;
+        for depot in self.depots().into_iter().map(|d| &self.pts[d]) {
+            if self.is_pdp && depot.pickup_delivery != Some((0, 0)) {
+                Err(VerifyError::InvalidInstance(
+                    "depots pdp pair is not (0, 0)".to_string(),
+                ))?;
+            }
+
+            let depots_demand = depot.demand;
+
+            if depots_demand != 0 {
+                Err(VerifyError::InvalidInstance(format!(
+                    "depots demand is non-zero ({depots_demand})"
+                )))?;
+            }
         }
 
-        let depots_demand = self.pts[0].demand;
+        Ok(())
+    }
+
+    fn check_vehicle_capacities(&self) -> Result<(), VerifyError> {
+        if let Some(min) = self.min_vehicles.filter(|&min| min > self.vehicles) {
+            Err(VerifyError::InvalidInstance(format!(
+                "at least {min} vehicles have to be used, but there are only {}",
+                self.vehicles
+            )))?;
+        }
 
-        if depots_demand != 0 {
-            Err(format!("depots demand is non-zero ({depots_demand})"))?;
+        if !self.vehicle_capacities.is_empty()
+            && self.vehicle_capacities.len() != self.vehicles as usize
+        {
+            Err(VerifyError::InvalidInstance(format!(
+                "there are {} vehicles but {} vehicle capacities",
+                self.vehicles,
+                self.vehicle_capacities.len()
+            )))?;
         }
 
         Ok(())
     }
 
-    fn check_time(&self) -> Result<(), String> {
+    fn check_pdp_chains(&self) -> Result<(), VerifyError> {
         for pt in self.pts.iter() {
-            if pt.start > pt.due {
-                Err(format!(
-                    "point {} can not be visited because the due time ({}) is before start ({})",
-                    pt.id, pt.due, pt.start
-                ))?;
-            }
-
-            let depot = &self.pts[0];
+            let Some(chain) = &pt.pickup_delivery_chain else {
+                continue;
+            };
 
-            let earliest_arrival = depot.start + depot.dist(pt);
-            if earliest_arrival > pt.due {
-                Err(format!(
-                    "earliest possible arrival ({earliest_arrival}) from depot to point {} is after the points due time {}",
-                    pt.id, pt.due
-                ))?;
+            if let Some(&c) = chain
+                .iter()
+                .find(|&&c| c >= self.pts.len() || self.is_depot(c))
+            {
+                Err(VerifyError::InvalidInstance(format!(
+                    "chain of point {} contains {c}, which is not a client",
+                    pt.id
+                )))?;
             }
 
-            let earliest_service_finish = fl(pt.start).max(&earliest_arrival) + pt.service;
-            let earliest_return = earliest_service_finish + pt.dist(&depot);
-
-            if earliest_return > depot.due {
-                Err(format!(
-                    "earliest possible return to depot ({earliest_return}) to point {} is after the depot due time {}",
-                    pt.id, depot.due
-                ))?;
+            if !chain.iter().all_unique() {
+                Err(VerifyError::InvalidInstance(format!(
+                    "chain of point {} contains a point more than once",
+                    pt.id
+                )))?;
             }
         }
         Ok(())
     }
 
-    pub fn check_sanity(&self) -> Result<(), String> {
-        let clients = self.pts.len();
-        if clients < 2 {
-            Err(format!(
-                "the instance needs at least two points (depot and one client to visit), it has {}",
-                clients
-            ))?;
+    fn check_depots(&self) -> Result<(), VerifyError> {
+        if let Some(&depot) = self.depots.iter().find(|&&d| d >= self.pts.len()) {
+            Err(VerifyError::InvalidInstance(format!(
+                "depot {depot} does not refer to any legal point"
+            )))?;
         }
-        self.point_ids_are_sequential()?;
-        self.check_demands()?;
-        self.check_time()?;
-        Ok(())
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        if !self.depot_capacities.is_empty() && self.depot_capacities.len() != self.depots.len() {
+            Err(VerifyError::InvalidInstance(format!(
+                "there are {} depots but {} depot capacities",
+                self.depots.len(),
+                self.depot_capacities.len()
+            )))?;
+        }
 
-    #[test]
-    fn read_gh_point() {
-        let line = " 0    1      2    3   4   5  6";
-        let point = Point::from_str(line).unwrap();
+        if let Some(assignment) = &self.vehicle_depot_assignment {
+            if assignment.len() != self.vehicles as usize {
+                Err(VerifyError::InvalidInstance(format!(
+                    "there are {} vehicles but {} of them are assigned to depots",
+                    self.vehicles,
+                    assignment.len()
+                )))?;
+            }
 
-        assert_eq!(
-            point,
-            Point {
-                id: 0,
-                x: 1,
-                y: 2,
-                demand: 3,
-                start: 4,
-                due: 5,
-                service: 6,
-                pickup_delivery: None
+            if let Some(&d) = assignment.iter().find(|&&d| d >= self.depots.len()) {
+                Err(VerifyError::InvalidInstance(format!(
+                    "vehicle assigned to depot {d}, but there are only {} depots",
+                    self.depots.len()
+                )))?;
             }
-        );
-    }
+        }
+
+        Ok(())
+    }
+
+    fn check_time(&self, dists: &impl Distances) -> Result<(), VerifyError> {
+        if !self.penalty_per_unit.is_finite() || self.penalty_per_unit < 0.0 {
+            Err(VerifyError::InvalidInstance(format!(
+                "penalty for lateness has to be a non-negative number, it is {}",
+                self.penalty_per_unit
+            )))?;
+        }
+
+        if let Some(limit) = self.max_route_duration.filter(|&limit| limit <= 0) {
+            Err(VerifyError::InvalidInstance(format!(
+                "maximum route duration has to be positive, it is {limit}"
+            )))?;
+        }
+
+        for pt in self.pts.iter() {
+            if pt.start > pt.due {
+                Err(VerifyError::InvalidInstance(format!(
+                    "point {} can not be visited because the due time ({}) is before start ({})",
+                    pt.id, pt.due, pt.start
+                )))?;
+            }
+
+            // with several depots it is enough that any of them can serve the
+            // point, the error is reported for the first one
+            let mut reachable = self
+                .depots()
+                .into_iter()
+                .map(|d| self.check_reachable(dists, d, pt));
+            let first = reachable.next().unwrap();
+            if first.is_err() && !reachable.any(|r| r.is_ok()) {
+                first?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_reachable(
+        &self,
+        dists: &impl Distances,
+        d: usize,
+        pt: &Point,
+    ) -> Result<(), VerifyError> {
+        let depot = &self.pts[d];
+
+        let earliest_arrival = depot.start + dists.between(d, pt.id as usize);
+        if earliest_arrival > pt.due {
+            Err(VerifyError::InvalidInstance(format!(
+                "earliest possible arrival ({earliest_arrival}) from depot to point {} is after the points due time {}",
+                pt.id, pt.due
+            )))?;
+        }
+
+        let earliest_service_finish = fl(pt.start).max(&earliest_arrival) + pt.service;
+        let earliest_return = earliest_service_finish + dists.between(pt.id as usize, d);
+
+        if !self.open_routes && earliest_return > depot.due {
+            Err(VerifyError::InvalidInstance(format!(
+                "earliest possible return to depot ({earliest_return}) to point {} is after the depot due time {}",
+                pt.id, depot.due
+            )))?;
+        }
+        Ok(())
+    }
+
+    pub fn check_sanity(&self) -> Result<(), VerifyError> {
+        self.check_sanity_with(self)
+    }
+
+    fn transformed(&self, f: impl Fn(&mut Point)) -> Result<Instance, VerifyError> {
+        let mut inst = self.clone();
+        inst.pts.iter_mut().for_each(f);
+        inst.check_sanity()?;
+        Ok(inst)
+    }
+
+    // times are scaled together with the coordinates, so that the time windows
+    // keep matching the distances
+    // times are rounded to keep them integers
+    pub fn scale(&self, factor: f64) -> Result<Instance, VerifyError> {
+        let scaled = |v: i32| (v as f64 * factor).round() as i32;
+        let mut inst = self.clone();
+        if let DistanceSource::Matrix(matrix) = &mut inst.distances {
+            for d in matrix.iter_mut().flatten() {
+                *d *= factor;
+            }
+        }
+        inst.transformed(|pt| {
+            pt.x *= factor;
+            pt.y *= factor;
+            pt.service = scaled(pt.service);
+            pt.start = scaled(pt.start);
+            pt.due = scaled(pt.due);
+        })
+    }
+
+    pub fn translate(&self, dx: f64, dy: f64) -> Result<Instance, VerifyError> {
+        self.transformed(|pt| {
+            pt.x += dx;
+            pt.y += dy;
+        })
+    }
+
+    pub fn flip_x(&self) -> Result<Instance, VerifyError> {
+        self.transformed(|pt| pt.x = -pt.x)
+    }
+
+    pub fn flip_y(&self) -> Result<Instance, VerifyError> {
+        self.transformed(|pt| pt.y = -pt.y)
+    }
+
+    pub fn normalize_coordinates(&self) -> Result<Instance, VerifyError> {
+        let (min_x, max_x) = coordinate_range(&self.pts, |pt| pt.x);
+        let (min_y, max_y) = coordinate_range(&self.pts, |pt| pt.y);
+        let translated = self.translate(-min_x, -min_y)?;
+
+        match (max_x - min_x).max(max_y - min_y) {
+            0.0 => Ok(translated),
+            span => translated.scale(1000.0 / span),
+        }
+    }
+
+    // `n` customers chosen at random, pdp pairs and chains are kept whole, so
+    // a few more may be taken; the depots are kept and the points renumbered
+    pub fn subsample(&self, n: usize, seed: u64) -> Result<Instance, VerifyError> {
+        let len = self.pts.len();
+        let mut linked = vec![vec![]; len];
+        for (c, pt) in self.pts.iter().enumerate() {
+            let pair = pt
+                .pickup_delivery
+                .map(|(p, d)| (p + d) as usize)
+                .filter(|&o| o != 0);
+            for o in pair
+                .into_iter()
+                .chain(pt.pickup_delivery_chain.iter().flatten().copied())
+                .filter(|&o| o < len && o != c)
+            {
+                linked[c].push(o);
+                linked[o].push(c);
+            }
+        }
+
+        // customers that have to be taken together
+        let mut seen: Vec<bool> = (0..len).map(|c| self.is_depot(c)).collect();
+        let mut groups = vec![];
+        for c in 0..len {
+            if seen[c] {
+                continue;
+            }
+            seen[c] = true;
+            let mut group = vec![c];
+            let mut i = 0;
+            while i < group.len() {
+                for &o in linked[group[i]].iter() {
+                    if !seen[o] {
+                        seen[o] = true;
+                        group.push(o);
+                    }
+                }
+                i += 1;
+            }
+            groups.push(group);
+        }
+        groups.shuffle(&mut StdRng::seed_from_u64(seed));
+
+        let mut keep: Vec<bool> = (0..len).map(|c| self.is_depot(c)).collect();
+        let mut taken = 0;
+        for group in groups.iter().take_while(|_| taken < n) {
+            taken += group.len();
+            group.iter().for_each(|&c| keep[c] = true);
+        }
+
+        let kept: Vec<usize> = (0..len).filter(|&c| keep[c]).collect();
+        let mut index = vec![0; len];
+        for (new, &old) in kept.iter().enumerate() {
+            index[old] = new;
+        }
+        let reindex = |i: i32| if i == 0 { 0 } else { index[i as usize] as i32 };
+
+        let mut inst = self.clone();
+        inst.pts = kept
+            .iter()
+            .map(|&old| {
+                let pt = &self.pts[old];
+                Point {
+                    id: index[old] as i32,
+                    pickup_delivery: pt.pickup_delivery.map(|(p, d)| (reindex(p), reindex(d))),
+                    pickup_delivery_chain: pt
+                        .pickup_delivery_chain
+                        .as_ref()
+                        .map(|chain| chain.iter().map(|&c| index[c]).collect()),
+                    ..pt.clone()
+                }
+            })
+            .collect();
+        inst.depots = self.depots.iter().map(|&d| index[d]).collect();
+        if let DistanceSource::Matrix(matrix) = &self.distances {
+            inst.distances = DistanceSource::Matrix(
+                kept.iter()
+                    .map(|&i| kept.iter().map(|&j| matrix[i][j].clone()).collect())
+                    .collect(),
+            );
+        }
+
+        inst.check_sanity()?;
+        Ok(inst)
+    }
+
+    // the customers of `other` are added after the points of `self`, whose
+    // depots are kept; the fleets are put together and the smaller capacity is
+    // used, any other settings are those of `self`
+    // the merged instance does not have to be feasible, even when both are
+    pub fn merge(&self, other: &Instance) -> Result<Instance, VerifyError> {
+        if self.is_pdp != other.is_pdp {
+            Err(VerifyError::InvalidInstance(format!(
+                "instances {} and {} can not be merged, only one of them is pdp",
+                self.name, other.name
+            )))?;
+        }
+        if self.distances != DistanceSource::Euclidean
+            || other.distances != DistanceSource::Euclidean
+            || self.vehicle_depot_assignment.is_some()
+            || other.vehicle_depot_assignment.is_some()
+        {
+            Err(VerifyError::InvalidInstance(format!(
+                "instances {} and {} can not be merged, distance matrices and vehicle depot assignments are not supported",
+                self.name, other.name
+            )))?;
+        }
+
+        let customers: Vec<usize> = (0..other.pts.len())
+            .filter(|&c| !other.is_depot(c))
+            .collect();
+        let mut index = vec![0; other.pts.len()];
+        for (i, &c) in customers.iter().enumerate() {
+            index[c] = self.pts.len() + i;
+        }
+        let reindex = |i: i32| if i == 0 { 0 } else { index[i as usize] as i32 };
+
+        let mut inst = self.clone();
+        inst.name = format!("{}+{}", self.name, other.name);
+        inst.pts.extend(customers.iter().map(|&c| {
+            let pt = &other.pts[c];
+            Point {
+                id: index[c] as i32,
+                pickup_delivery: pt.pickup_delivery.map(|(p, d)| (reindex(p), reindex(d))),
+                pickup_delivery_chain: pt
+                    .pickup_delivery_chain
+                    .as_ref()
+                    .map(|chain| chain.iter().map(|&c| index[c]).collect()),
+                ..pt.clone()
+            }
+        }));
+        inst.vehicles = self.vehicles + other.vehicles;
+        inst.max_capacity = self.max_capacity.min(other.max_capacity);
+        if !self.vehicle_capacities.is_empty() || !other.vehicle_capacities.is_empty() {
+            let capacities = |inst: &Instance| match inst.vehicle_capacities.as_slice() {
+                [] => vec![inst.max_capacity; inst.vehicles as usize],
+                capacities => capacities.to_vec(),
+            };
+            inst.vehicle_capacities = [capacities(self), capacities(other)].concat();
+        }
+
+        inst.check_sanity()?;
+        Ok(inst)
+    }
+
+    // in pdp instances only pickups are counted in the demand, the average is
+    // taken over the customers with a positive demand
+    pub fn statistics(&self) -> InstanceStats {
+        let customers = (0..self.pts.len())
+            .filter(|&i| !self.is_depot(i))
+            .map(|i| &self.pts[i])
+            .collect::<Vec<_>>();
+        let n = customers.len() as f64;
+        let time_horizon = self.pts[0].due;
+        let widths = || customers.iter().map(|pt| pt.due as i64 - pt.start as i64);
+        let span = |coord: fn(&Point) -> f64| {
+            let (min, max) = coordinate_range(&self.pts, coord);
+            max - min
+        };
+
+        let demands = customers
+            .iter()
+            .filter(|pt| pt.demand > 0)
+            .map(|pt| pt.demand as i64)
+            .collect::<Vec<_>>();
+        let total_demand = demands.iter().sum::<i64>();
+
+        InstanceStats {
+            num_customers: customers.len(),
+            total_demand,
+            avg_demand: total_demand as f64 / demands.len().max(1) as f64,
+            max_demand: customers.iter().map(|pt| pt.demand).max().unwrap(),
+            time_horizon,
+            avg_time_window_width: widths().map(|w| w as f64).sum::<f64>() / n,
+            min_time_window_width: widths().min().unwrap(),
+            customers_with_tight_windows: widths()
+                .filter(|&w| w * 10 < time_horizon as i64)
+                .count(),
+            is_pdp: self.is_pdp,
+            num_pdp_pairs: customers
+                .iter()
+                .filter(|pt| matches!(pt.pickup_delivery, Some((0, d)) if d != 0))
+                .count(),
+            centroid: (
+                customers.iter().map(|pt| pt.x).sum::<f64>() / n,
+                customers.iter().map(|pt| pt.y).sum::<f64>() / n,
+            ),
+            max_coordinate_span: span(|pt| pt.x).max(span(|pt| pt.y)),
+        }
+    }
+
+    // necessary conditions only, an instance passing them may still have no
+    // feasible solution
+    pub fn check_feasibility(&self) -> Result<(), Vec<VerifyError>> {
+        let mut errors = vec![];
+        let prec = self.precision();
+        let depots = self.depots();
+
+        for (c, pt) in self.pts.iter().enumerate() {
+            if self.is_depot(c) {
+                continue;
+            }
+
+            // the customer is served from the depot closest to it
+            let d = *depots
+                .iter()
+                .min_by(|&&a, &&b| self.between(a, c).total_cmp(&self.between(b, c)))
+                .unwrap();
+            let depot = &self.pts[d];
+            let leave = prec.fl(depot.start) + depot.service;
+            let arrival = prec.fl(pt.start).max(&(leave + self.between(d, c)));
+            if arrival > pt.due {
+                errors.push(VerifyError::InvalidInstance(format!(
+                    "point {} can not be reached from the depot before its due time {}",
+                    pt.id, pt.due
+                )));
+            }
+
+            let (pickup, delivery) = match pt.pickup_delivery {
+                Some((0, d)) if d != 0 => (c, d as usize),
+                _ => continue,
+            };
+
+            let delivery_pt = &self.pts[delivery];
+            let delivery_arrival = prec
+                .fl(delivery_pt.start)
+                .max(&(arrival + pt.service + self.between(pickup, delivery)));
+            if delivery_arrival > delivery_pt.due {
+                errors.push(VerifyError::InvalidInstance(format!(
+                    "delivery {delivery} can not be reached from its pickup {pickup} before its due time {}",
+                    delivery_pt.due
+                )));
+            } else if !self.open_routes
+                && delivery_arrival + delivery_pt.service + self.between(delivery, d) > depot.due
+            {
+                errors.push(VerifyError::InvalidInstance(format!(
+                    "pickup {pickup} and delivery {delivery} can not be served before the depot due time {}",
+                    depot.due
+                )));
+            }
+        }
+
+        // in pdp the load is freed on delivery, so there is no such bound
+        if !self.is_pdp {
+            if let Err(err) = self.check_fleet_capacity() {
+                errors.push(err);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // the total demand needs at least as many vehicles as it takes when the
+    // largest ones are used first; a vehicle without a fixed depot may start
+    // at the one where its capacity is the largest
+    fn check_fleet_capacity(&self) -> Result<(), VerifyError> {
+        let depots = self.depots();
+        let mut capacities = (0..self.vehicles.max(0) as usize)
+            .map(|v| match self.vehicle_depot(v) {
+                Some(d) => self.vehicle_capacity(v, d),
+                None => depots
+                    .iter()
+                    .map(|&d| self.vehicle_capacity(v, d))
+                    .max()
+                    .unwrap(),
+            })
+            .collect::<Vec<_>>();
+        capacities.sort_unstable_by(|a, b| b.cmp(a));
+
+        let smallest = capacities.last().copied().unwrap_or(self.max_capacity);
+        if smallest <= 0 {
+            Err(VerifyError::InvalidInstance(format!(
+                "vehicle capacities have to be positive, but one is {smallest}"
+            )))?;
+        }
+        let largest = capacities.first().copied().unwrap_or(self.max_capacity) as i64;
+
+        let total_demand: i64 = (0..self.pts.len())
+            .filter(|&c| !self.is_depot(c))
+            .map(|c| self.pts[c].demand as i64)
+            .sum();
+        let mut left = total_demand;
+        let mut needed = 0;
+        for &capacity in capacities.iter().take_while(|_| left > 0) {
+            left -= capacity as i64;
+            needed += 1;
+        }
+        if left > 0 {
+            needed += (left + largest - 1) / largest;
+        }
+
+        if needed > self.vehicles as i64 {
+            Err(VerifyError::InvalidInstance(format!(
+                "total demand {total_demand} needs at least {needed} vehicles, but only {} are available",
+                self.vehicles
+            )))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_sanity_with(&self, dists: &impl Distances) -> Result<(), VerifyError> {
+        let clients = self.pts.len();
+        if clients < 2 {
+            Err(VerifyError::InvalidInstance(format!(
+                "the instance needs at least two points (depot and one client to visit), it has {}",
+                clients
+            )))?;
+        }
+        self.point_ids_are_sequential()?;
+        self.check_vehicle_capacities()?;
+        self.check_depots()?;
+        self.check_pdp_chains()?;
+        self.check_distances()?;
+        self.check_demands()?;
+        self.check_time(dists)?;
+        Ok(())
+    }
+}
+
+// where and when a point is visited, for `InstanceBuilder`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stop {
+    pub x: f64,
+    pub y: f64,
+    pub start: i32,
+    pub due: i32,
+    pub service: i32,
+}
+
+// points get sequential ids in the order in which they are added, the
+// instance is pdp when any pickup and delivery pair was added
+#[derive(Clone, Debug, Default)]
+pub struct InstanceBuilder {
+    name: String,
+    vehicles: i32,
+    capacity: i32,
+    pts: Vec<Point>,
+    depots: Vec<usize>,
+    is_pdp: bool,
+}
+
+impl InstanceBuilder {
+    pub fn name(&mut self, n: &str) -> &mut Self {
+        self.name = n.to_string();
+        self
+    }
+
+    pub fn vehicles(&mut self, v: i32) -> &mut Self {
+        self.vehicles = v;
+        self
+    }
+
+    pub fn capacity(&mut self, c: i32) -> &mut Self {
+        self.capacity = c;
+        self
+    }
+
+    fn add(&mut self, stop: Stop, demand: i32) -> usize {
+        let id = self.pts.len();
+        self.pts.push(Point {
+            id: id as i32,
+            x: stop.x,
+            y: stop.y,
+            demand,
+            start: stop.start,
+            due: stop.due,
+            service: stop.service,
+            pickup_delivery: None,
+            pickup_delivery_chain: None,
+        });
+        id
+    }
+
+    pub fn add_depot(&mut self, x: f64, y: f64, start: i32, due: i32, service: i32) -> &mut Self {
+        let depot = self.add(
+            Stop {
+                x,
+                y,
+                start,
+                due,
+                service,
+            },
+            0,
+        );
+        self.depots.push(depot);
+        self
+    }
+
+    pub fn add_customer(
+        &mut self,
+        x: f64,
+        y: f64,
+        demand: i32,
+        start: i32,
+        due: i32,
+        service: i32,
+    ) -> &mut Self {
+        self.add(
+            Stop {
+                x,
+                y,
+                start,
+                due,
+                service,
+            },
+            demand,
+        );
+        self
+    }
+
+    // the delivery is added right after its pickup
+    pub fn add_pickup_delivery(&mut self, demand: i32, pickup: Stop, delivery: Stop) -> &mut Self {
+        let p = self.add(pickup, demand);
+        let d = self.add(delivery, -demand);
+        self.pts[p].pickup_delivery = Some((0, d as i32));
+        self.pts[d].pickup_delivery = Some((p as i32, 0));
+        self.is_pdp = true;
+        self
+    }
+
+    pub fn build(self) -> Result<Instance, VerifyError> {
+        if self.depots.is_empty() {
+            Err(VerifyError::InvalidInstance(
+                "the instance needs a depot".to_string(),
+            ))?;
+        }
+
+        let mut pts = self.pts;
+        if self.is_pdp {
+            for &d in self.depots.iter() {
+                pts[d].pickup_delivery = Some((0, 0));
+            }
+        }
+
+        let inst = Instance {
+            name: self.name,
+            vehicles: self.vehicles,
+            max_capacity: self.capacity,
+            pts,
+            is_pdp: self.is_pdp,
+            distances: DistanceSource::Euclidean,
+            max_route_duration: None,
+            min_vehicles: None,
+            open_routes: false,
+            vehicle_capacities: vec![],
+            // a single depot at 0 needs no list
+            depots: if self.depots == [0] {
+                vec![]
+            } else {
+                self.depots
+            },
+            depot_capacities: vec![],
+            vehicle_depot_assignment: None,
+            soft_windows: false,
+            penalty_per_unit: 0.0,
+        };
+        inst.check_sanity()?;
+        Ok(inst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::flf64;
+    use proptest::prelude::*;
+
+    #[test]
+    fn read_gh_point() {
+        let line = " 0    1      2    3   4   5  6";
+        let point = Point::from_str(line).unwrap();
+
+        assert_eq!(
+            point,
+            Point {
+                id: 0,
+                x: 1.0,
+                y: 2.0,
+                demand: 3,
+                start: 4,
+                due: 5,
+                service: 6,
+                pickup_delivery: None,
+                pickup_delivery_chain: None
+            }
+        );
+    }
 
     #[test]
     fn read_gh_point_not_enough() {
@@ -410,9 +1701,9 @@ mod tests {
 
         assert_eq!(
             point,
-            Err(format!(
-                "expected 7 or 9 integers in line `{line}', have 4 numbers"
-            ))
+            Err(VerifyError::ParseError(format!(
+                "expected 7 or 9 numbers in line `{line}', have 4 numbers"
+            )))
         );
     }
 
@@ -423,9 +1714,9 @@ mod tests {
 
         assert_eq!(
             point,
-            Err(format!(
-                "expected 7 or 9 integers in line `{line}', have 8 numbers"
-            ))
+            Err(VerifyError::ParseError(format!(
+                "expected 7 or 9 numbers in line `{line}', have 8 numbers"
+            )))
         );
     }
 
@@ -436,9 +1727,9 @@ mod tests {
 
         assert_eq!(
             point,
-            Err(format!(
-                "expected 7 or 9 integers in line `{line}', have 10 numbers"
-            ))
+            Err(VerifyError::ParseError(format!(
+                "expected 7 or 9 numbers in line `{line}', have 10 numbers"
+            )))
         );
     }
 
@@ -451,13 +1742,14 @@ mod tests {
             point,
             Point {
                 id: 0,
-                x: 1,
-                y: 2,
+                x: 1.0,
+                y: 2.0,
                 demand: 3,
                 start: 4,
                 due: 5,
                 service: 6,
-                pickup_delivery: Some((7, 8))
+                pickup_delivery: Some((7, 8)),
+                pickup_delivery_chain: None
             }
         );
     }
@@ -486,52 +1778,66 @@ mod tests {
         assert_eq!(
             inst.unwrap(),
             Instance {
-                name: String::from(""),
+                name: String::from("c1_1_1"),
                 vehicles: 12,
                 max_capacity: 100,
                 pts: vec![
                     Point {
                         id: 0,
-                        x: 1,
-                        y: 2,
+                        x: 1.0,
+                        y: 2.0,
                         demand: 0,
                         start: 4,
                         due: 100,
                         service: 6,
                         pickup_delivery: None,
+                        pickup_delivery_chain: None,
                     },
                     Point {
                         id: 1,
-                        x: 2,
-                        y: 3,
+                        x: 2.0,
+                        y: 3.0,
                         demand: 4,
                         start: 5,
                         due: 6,
                         service: 7,
                         pickup_delivery: None,
+                        pickup_delivery_chain: None,
                     },
                     Point {
                         id: 2,
-                        x: 3,
-                        y: 4,
+                        x: 3.0,
+                        y: 4.0,
                         demand: 5,
                         start: 6,
                         due: 7,
                         service: 8,
                         pickup_delivery: None,
+                        pickup_delivery_chain: None,
                     },
                     Point {
                         id: 3,
-                        x: 4,
-                        y: 5,
+                        x: 4.0,
+                        y: 5.0,
                         demand: 6,
                         start: 7,
                         due: 10,
                         service: 9,
                         pickup_delivery: None,
+                        pickup_delivery_chain: None,
                     },
                 ],
                 is_pdp: false,
+                distances: DistanceSource::Euclidean,
+                open_routes: false,
+                max_route_duration: None,
+                min_vehicles: None,
+                vehicle_capacities: vec![],
+                depots: vec![],
+                depot_capacities: vec![],
+                vehicle_depot_assignment: None,
+                soft_windows: false,
+                penalty_per_unit: 0.0,
             }
         );
     }
@@ -559,57 +1865,990 @@ mod tests {
                 pts: vec![
                     Point {
                         id: 0,
-                        x: 1,
-                        y: 2,
+                        x: 1.0,
+                        y: 2.0,
                         demand: 0,
                         start: 4,
                         due: 100,
                         service: 6,
                         pickup_delivery: Some((0, 0)),
+                        pickup_delivery_chain: None,
                     },
                     Point {
                         id: 1,
-                        x: 2,
-                        y: 3,
+                        x: 2.0,
+                        y: 3.0,
                         demand: 4,
                         start: 5,
                         due: 6,
                         service: 7,
                         pickup_delivery: Some((0, 2)),
+                        pickup_delivery_chain: None,
                     },
                     Point {
                         id: 2,
-                        x: 3,
-                        y: 4,
+                        x: 3.0,
+                        y: 4.0,
                         demand: -4,
                         start: 6,
                         due: 7,
                         service: 8,
                         pickup_delivery: Some((1, 0)),
+                        pickup_delivery_chain: None,
                     },
                     Point {
                         id: 3,
-                        x: 4,
-                        y: 5,
+                        x: 4.0,
+                        y: 5.0,
                         demand: 6,
                         start: 7,
                         due: 10,
                         service: 9,
                         pickup_delivery: Some((0, 4)),
+                        pickup_delivery_chain: None,
                     },
                     Point {
                         id: 4,
-                        x: 5,
-                        y: 6,
+                        x: 5.0,
+                        y: 6.0,
                         demand: -6,
                         start: 8,
                         due: 10,
                         service: 10,
                         pickup_delivery: Some((3, 0)),
+                        pickup_delivery_chain: None,
                     },
                 ],
                 is_pdp: true,
+                distances: DistanceSource::Euclidean,
+                open_routes: false,
+                max_route_duration: None,
+                min_vehicles: None,
+                vehicle_capacities: vec![],
+                depots: vec![],
+                depot_capacities: vec![],
+                vehicle_depot_assignment: None,
+                soft_windows: false,
+                penalty_per_unit: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn read_instance_with_name() {
+        let instance = concat!(
+            "12\t100\n",
+            "0\t1\t2\t0\t4\t100\t6\t0\t0\n",
+            "1\t2\t3\t4\t5\t6\t7\t0\t2\n",
+            "2\t3\t4\t-4\t6\t7\t8\t1\t0\n",
+        );
+        let inst = Instance::from_str_with_name(instance, "lc101").unwrap();
+        assert_eq!(inst.name, "lc101");
+
+        // names given in the file are kept
+        let named = Instance::from_str_with_name(&inst.to_string(), "other").unwrap();
+        assert_eq!(named.name, "lc101");
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let instance = concat!(
+            "12 100\n",
+            "0 1 2 0 4 100 6\n",
+            "1 2 3 4 5 60 7\n",
+            "\n",
+            "DISTANCE MATRIX\n",
+            "\n",
+            "0 1.5\n",
+            "2.25 0\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+        assert_eq!(Instance::from_json(&inst.to_json()), Ok(inst.clone()));
+
+        let mut broken = inst;
+        broken.pts[1].due = 0;
+        assert!(matches!(
+            Instance::from_json(&broken.to_json()),
+            Err(VerifyError::InvalidInstance(_))
+        ));
+        assert!(matches!(
+            Instance::from_json("{}"),
+            Err(VerifyError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        let instance = concat!(
+            "12 100\n",
+            "0 1 2 0 4 100 6\n",
+            "1 2 3 4 5 60 7\n",
+            "\n",
+            "DISTANCE MATRIX\n",
+            "\n",
+            "0 1.5\n",
+            "2.25 0\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+        let mut bytes = inst.to_binary();
+        assert_eq!(Instance::from_binary(&bytes), Ok(inst));
+
+        bytes[4] += 1;
+        assert!(Instance::from_binary(&bytes).is_err());
+        assert!(Instance::from_binary(b"VRP").is_err());
+    }
+
+    #[test]
+    fn read_solomon_header_variants() {
+        let rows = concat!(
+            "    0      40         50          0          0       1236          0   \n",
+            "    1      45         68         10        912        967         90   \n",
+        );
+        let canonical = concat!(
+            "C101\n",
+            "\n",
+            "VEHICLE\n",
+            "NUMBER     CAPACITY\n",
+            "  25         200\n",
+            "\n",
+            "CUSTOMER\n",
+            "CUST NO.  XCOORD.   YCOORD.    DEMAND   READY TIME   DUE DATE   SERVICE   TIME\n",
+            " \n",
+        );
+        let nameless = concat!(
+            "VEHICLE\n",
+            "NUMBER     CAPACITY\n",
+            "  25         200\n",
+            "CUSTOMER\n",
+            "CUST NO.  XCOORD.   YCOORD.    DEMAND   READY TIME   DUE DATE   SERVICE   TIME\n",
+        );
+        let separate_lines = concat!(
+            "  C101  \n",
+            "VEHICLE\n",
+            "NUMBER\n",
+            "CAPACITY\n",
+            "  25\n",
+            "  200\n",
+            "\n",
+            "CUSTOMER\n",
+            "CUST NO.  XCOORD.   YCOORD.    DEMAND   READY TIME   DUE DATE   SERVICE   TIME\n",
+            "\n",
+        );
+
+        for (header, name) in [
+            (canonical, "C101"),
+            (nameless, ""),
+            (separate_lines, "C101"),
+        ] {
+            let inst = Instance::from_str(&(header.to_string() + rows)).unwrap();
+            assert_eq!(inst.name, name);
+            assert_eq!((inst.vehicles, inst.max_capacity), (25, 200));
+            assert_eq!(inst.pts.len(), 2);
+            assert_eq!(inst.pts[1].due, 967);
+        }
+
+        let without_final_newline = canonical.to_string() + rows.trim_end();
+        assert_eq!(
+            Instance::from_str(&without_final_newline)
+                .unwrap()
+                .pts
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn read_instance_with_matrix() {
+        let instance = concat!(
+            "12 100\n",
+            "0 1 2 0 4 100 6\n",
+            "1 2 3 4 5 60 7\n",
+            "\n",
+            "DISTANCE MATRIX\n",
+            "\n",
+            "0 1.5\n",
+            "2.25 0\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+
+        assert_eq!(
+            inst.distances,
+            DistanceSource::Matrix(vec![vec![fl(0), flf64(1.5)], vec![flf64(2.25), fl(0)]])
+        );
+        assert_eq!(inst.between(0, 1), flf64(1.5));
+        assert_eq!(inst.between(1, 0), flf64(2.25));
+
+        let inst = Instance {
+            name: "m1".to_string(),
+            ..inst
+        };
+        assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst));
+    }
+
+    #[test]
+    fn read_instance_with_non_square_matrix() {
+        let instance = concat!(
+            "12 100\n",
+            "0 1 2 0 4 100 6\n",
+            "1 2 3 4 5 60 7\n",
+            "DISTANCE MATRIX\n",
+            "0 1\n",
+            "1\n",
+        );
+
+        assert_eq!(
+            Instance::from_str(instance),
+            Err(VerifyError::InvalidInstance(
+                "row 1 of the distance matrix has 1 values but the instance has 2 points"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn feasibility() {
+        let pdp = concat!(
+            "2 10\n",
+            "0 0 0 0 0 100 0 0 0\n",
+            "1 10 0 5 0 100 10 0 2\n",
+            "2 20 0 -5 0 100 0 1 0\n",
+        );
+        let mut inst = Instance::from_str(pdp).unwrap();
+        assert_eq!(inst.check_feasibility(), Ok(()));
+
+        inst.pts[2].due = 25;
+        assert_eq!(
+            inst.check_feasibility(),
+            Err(vec![VerifyError::InvalidInstance(
+                "delivery 2 can not be reached from its pickup 1 before its due time 25"
+                    .to_string()
+            )])
+        );
+
+        let vrp = concat!(
+            "1 10\n",
+            "0 0 0 0 0 100 0\n",
+            "1 1 0 6 0 100 0\n",
+            "2 2 0 6 0 100 0\n",
+        );
+        assert_eq!(
+            Instance::from_str(vrp).unwrap().check_feasibility(),
+            Err(vec![VerifyError::InvalidInstance(
+                "total demand 12 needs at least 2 vehicles, but only 1 are available".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn statistics() {
+        let instance = concat!(
+            "2 10\n",
+            "0 0 0 0 0 100 0 0 0\n",
+            "1 10 0 5 0 100 10 0 2\n",
+            "2 20 4 -5 30 35 0 1 0\n",
+        );
+        let stats = Instance::from_str(instance).unwrap().statistics();
+
+        assert_eq!(
+            stats,
+            InstanceStats {
+                num_customers: 2,
+                total_demand: 5,
+                avg_demand: 5.0,
+                max_demand: 5,
+                time_horizon: 100,
+                avg_time_window_width: 52.5,
+                min_time_window_width: 5,
+                customers_with_tight_windows: 1,
+                is_pdp: true,
+                num_pdp_pairs: 1,
+                centroid: (15.0, 2.0),
+                max_coordinate_span: 20.0,
             }
         );
     }
+
+    #[test]
+    fn transformations() {
+        let instance = concat!(
+            "2 10\n",
+            "0 -10 20 0 0 200 0\n",
+            "1 10 0 5 0 100 10\n",
+            "2 30 -20 5 30 90 0\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+        let coords = |inst: &Instance| inst.pts.iter().map(|pt| (pt.x, pt.y)).collect_vec();
+
+        assert_eq!(
+            coords(&inst.translate(1.0, -1.0).unwrap()),
+            vec![(-9.0, 19.0), (11.0, -1.0), (31.0, -21.0)]
+        );
+        assert_eq!(
+            coords(&inst.flip_x().unwrap().flip_y().unwrap()),
+            vec![(10.0, -20.0), (-10.0, 0.0), (-30.0, 20.0)]
+        );
+
+        let scaled = inst.scale(2.0).unwrap();
+        assert_eq!(
+            coords(&scaled),
+            vec![(-20.0, 40.0), (20.0, 0.0), (60.0, -40.0)]
+        );
+        assert_eq!(
+            (
+                scaled.pts[2].start,
+                scaled.pts[2].due,
+                scaled.pts[1].service
+            ),
+            (60, 180, 20)
+        );
+
+        let normalized = inst.normalize_coordinates().unwrap();
+        assert_eq!(
+            coords(&normalized),
+            vec![(0.0, 1000.0), (500.0, 500.0), (1000.0, 0.0)]
+        );
+        assert_eq!(normalized.pts[0].due, 5000);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let gh = concat!(
+            "c1_2_1\n",
+            "12 100\n",
+            "0 1 2 0 4 100 6\n",
+            "1 2 3 4 5 60 7\n",
+            "\n",
+            "DISTANCE MATRIX\n",
+            "\n",
+            "0 1.5\n",
+            "2.25 0\n",
+        );
+        let ll = concat!(
+            "12\t100\n",
+            "0\t1\t2\t0\t4\t100\t6\t0\t0\n",
+            "1\t2\t3\t4\t5\t60\t7\t0\t2\n",
+            "2\t3\t4\t-4\t6\t70\t8\t1\t0\n",
+        );
+
+        for s in [gh, ll] {
+            let inst = Instance::from_str(s).unwrap();
+            assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst));
+        }
+
+        let mut named = Instance::from_str(ll).unwrap();
+        named.name = "lc1_2_1".to_string();
+        assert_eq!(Instance::from_str(&named.to_string()), Ok(named));
+    }
+
+    #[test]
+    fn read_max_route_duration() {
+        let rows = concat!("0 0 0 0 0 1000 0\n", "1 8 0 5 0 1000 0\n",);
+        let plain = format!("MAX ROUTE DURATION: 480\n3 100\n{rows}");
+        let solomon = format!(
+            "C101\nMAX ROUTE DURATION: 480\n\nVEHICLE\nNUMBER     CAPACITY\n  3    100\n\nCUSTOMER\n{rows}"
+        );
+
+        for s in [plain, solomon] {
+            let inst = Instance::from_str(&s).unwrap();
+            assert_eq!(inst.max_route_duration, Some(480));
+            assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst));
+        }
+
+        let mut inst = Instance::from_str(&format!("3 100\n{rows}")).unwrap();
+        assert_eq!(inst.max_route_duration, None);
+        inst.max_route_duration = Some(0);
+        assert!(inst.check_sanity().is_err());
+    }
+
+    #[test]
+    fn read_decimal_coordinates() {
+        let point = Point::from_str("1 0.5 -2.25 3 4 5 6").unwrap();
+        assert_eq!((point.x, point.y, point.demand), (0.5, -2.25, 3));
+        assert!(Point::from_str("1 0.5 2 3.5 4 5 6").is_err());
+
+        let inst = Instance::from_str(concat!(
+            "3 100\n",
+            "0 0.5 0 0 0 1000 0\n",
+            "1 3.5 4 5 0 1000 0\n",
+        ))
+        .unwrap();
+        assert_eq!(inst.distance(0, 1), fl(5));
+        assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst));
+    }
+
+    #[test]
+    fn distance_matrix() {
+        let inst = Instance::from_str(concat!(
+            "3 100\n",
+            "0 0 0 0 0 1000 0\n",
+            "1 3 0 5 0 1000 0\n",
+            "2 3 4 5 0 1000 0\n",
+        ))
+        .unwrap();
+
+        let matrix = inst.distance_matrix();
+        assert_eq!(
+            matrix,
+            vec![
+                vec![fl(0), fl(3), fl(5)],
+                vec![fl(3), fl(0), fl(4)],
+                vec![fl(5), fl(4), fl(0)],
+            ]
+        );
+        assert_eq!(inst.distance(2, 0), fl(5));
+    }
+
+    #[test]
+    fn nearest_neighbors() {
+        let inst = Instance::from_str(concat!(
+            "3 100\n",
+            "0 0 0 0 0 1000 0\n",
+            "1 3 0 5 0 1000 0\n",
+            "2 3 4 5 0 1000 0\n",
+            "3 0 1 5 0 1000 0\n",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            inst.nearest_neighbors(2),
+            vec![vec![3, 1], vec![0, 3], vec![1, 3], vec![0, 1]]
+        );
+        assert_eq!(inst.nearest_neighbors(5)[0], vec![3, 1, 2]);
+        assert_eq!(inst.nearest_neighbors(0), vec![Vec::<usize>::new(); 4]);
+    }
+
+    #[test]
+    fn read_min_vehicles() {
+        let rows = concat!("0 0 0 0 0 1000 0\n", "1 8 0 5 0 1000 0\n",);
+        let plain = format!("MAX ROUTE DURATION: 480\nMIN VEHICLES: 2\n3 100\n{rows}");
+        let solomon = format!(
+            "C101\nMIN VEHICLES: 2\n\nVEHICLE\nNUMBER     CAPACITY\n  3    100\n\nCUSTOMER\n{rows}"
+        );
+
+        for s in [plain, solomon] {
+            let inst = Instance::from_str(&s).unwrap();
+            assert_eq!(inst.min_vehicles, Some(2));
+            assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst));
+        }
+
+        assert!(Instance::from_str(&format!("MIN VEHICLES: 4\n3 100\n{rows}")).is_err());
+        assert!(matches!(
+            Instance::from_str(&format!("MIN VEHICLES: 99999999999\n3 100\n{rows}")),
+            Err(VerifyError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn read_open_instance() {
+        let instance = concat!(
+            "3 100\n",
+            "0 0 0 0 0 10 0\n",
+            "1 8 0 5 0 1000 0\n",
+            "\n",
+            "OPEN\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+
+        assert!(inst.open_routes);
+        assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst));
+        // the point can be reached, but the depot closes before the vehicle
+        // could return
+        assert!(Instance::from_str(&instance.replace("OPEN\n", "")).is_err());
+    }
+
+    #[test]
+    fn read_soft_windows() {
+        let instance = concat!(
+            "3 100\n",
+            "0 0 0 0 0 1000 0\n",
+            "1 1 0 5 0 10 0\n",
+            "\n",
+            "SOFT WINDOWS: 2.5\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+
+        assert!(inst.soft_windows);
+        assert_eq!(inst.penalty_per_unit, 2.5);
+        assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst));
+        assert!(Instance::from_str(&instance.replace("2.5", "-1")).is_err());
+    }
+
+    #[test]
+    fn read_vehicle_capacities() {
+        let instance = concat!(
+            "3 100\n",
+            "0 0 0 0 0 1000 0\n",
+            "1 1 0 5 0 1000 0\n",
+            "\n",
+            "VEHICLE CAPACITIES: 100 50 20\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+
+        assert_eq!(inst.vehicle_capacities, vec![100, 50, 20]);
+        assert_eq!(inst.vehicle_capacity(1, 0), 50);
+        assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst.clone()));
+        assert_eq!(Instance::from_json(&inst.to_json()), Ok(inst));
+        assert!(Instance::from_str(&instance.replace(" 20\n", "\n")).is_err());
+        assert!(matches!(
+            Instance::from_str(&instance.replace(" 20\n", " 99999999999\n")),
+            Err(VerifyError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn read_multi_depot_instance() {
+        let instance = concat!(
+            "md_test\n",
+            "3 100\n",
+            "0 0 0 0 0 1000 0\n",
+            "1 10 0 0 0 1000 0\n",
+            "2 1 0 5 0 1000 0\n",
+            "3 11 0 5 0 1000 0\n",
+            "\n",
+            "DEPOTS:\n",
+            "0 10\n",
+            "1 6\n",
+            "VEHICLE DEPOTS: 0 1 0\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+
+        assert_eq!(inst.depots, vec![0, 1]);
+        assert_eq!(inst.depot_capacities, vec![10, 6]);
+        assert_eq!(inst.vehicle_depot_assignment, Some(vec![0, 1, 0]));
+        assert_eq!(inst.vehicle_depot(1), Some(1));
+        assert_eq!(inst.depot_capacity(1), 6);
+        assert!(inst.is_depot(1));
+        assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst.clone()));
+
+        let mut broken = inst.clone();
+        broken.vehicle_depot_assignment = Some(vec![0, 2, 0]);
+        assert!(broken.check_sanity().is_err());
+        broken.vehicle_depot_assignment = Some(vec![0, 1]);
+        assert!(broken.check_sanity().is_err());
+
+        let without_capacities = instance.replace("0 10\n", "0\n");
+        assert!(Instance::from_str(&without_capacities).is_err());
+        for bad in [
+            instance.replace("1 6\n", "-1 6\n"),
+            instance.replace("0 1 0\n", "0 -1 0\n"),
+        ] {
+            assert!(matches!(
+                Instance::from_str(&bad),
+                Err(VerifyError::ParseError(_))
+            ));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn generated_instances_round_trip(
+            name in "[a-z][a-z0-9_]{0,10}",
+            num_customers in (1usize..15).prop_map(|n| n * 2),
+            seed: u64,
+            pdp: bool,
+            shift in -50i32..=50,
+        ) {
+            let mut inst = generated(seed, num_customers, pdp);
+            inst.name = name;
+            // moves the points into negative coordinates as well
+            for pt in inst.pts.iter_mut() {
+                pt.x += shift as f64;
+                pt.y += shift as f64;
+            }
+
+            prop_assert_eq!(Instance::from_str(&inst.to_string()), Ok(inst));
+        }
+    }
+
+    #[test]
+    fn instance_families() {
+        let family = |name: &str| InstanceFamily::from_name(name);
+
+        assert_eq!(family("C101"), InstanceFamily::C1);
+        assert_eq!(family("rc2_4_10"), InstanceFamily::RC2);
+        assert_eq!(family("r1_10_1"), InstanceFamily::R1);
+        assert_eq!(family("LC1_8_7"), InstanceFamily::LC1);
+        assert_eq!(family("lrc201"), InstanceFamily::LRC2);
+        assert_eq!(family("lr1_2_1"), InstanceFamily::LR1);
+        assert_eq!(family("c3_2_1"), InstanceFamily::Unknown);
+        assert_eq!(family("c1"), InstanceFamily::Unknown);
+        assert_eq!(family("x-n101-k25"), InstanceFamily::Unknown);
+        assert_eq!(InstanceFamily::from_str("rc1"), Ok(InstanceFamily::RC1));
+        assert_eq!(InstanceFamily::RC1.to_string(), "RC1");
+        assert!(InstanceFamily::from_str("c3").is_err());
+    }
+
+    #[test]
+    fn manhattan_distance() {
+        let a = Point::from_str("0 1 2 0 0 10 0").unwrap();
+        let b = Point::from_str("1 4 -2 0 0 10 0").unwrap();
+
+        assert_eq!(a.dist_mode(&b, DistanceMode::Manhattan), fl(7));
+        assert_eq!(a.dist_mode(&b, DistanceMode::Euclidean), fl(5));
+        assert_eq!(
+            DistanceMode::from_str("Manhattan"),
+            Ok(DistanceMode::Manhattan)
+        );
+        assert!(DistanceMode::from_str("taxicab").is_err());
+    }
+
+    #[test]
+    fn distances_of_large_coordinates() {
+        // the squares are far beyond i32, they are computed with rug floats
+        let a = Point::from_str("0 0 0 0 0 10 0").unwrap();
+        let b = Point::from_str("1 1200000000 1600000000 0 0 10 0").unwrap();
+        assert_eq!(a.dist(&b), fl(2_000_000_000));
+        assert_eq!(a.dist_mode(&b, DistanceMode::Manhattan), flf64(2.8e9));
+
+        let half = i32::MAX / 2;
+        let c = Point::from_str(&format!("0 {} {} 0 0 10 0", -half, -half)).unwrap();
+        let d = Point::from_str(&format!("1 {half} {half} 0 0 10 0")).unwrap();
+        let side = flf64(2.0 * half as f64);
+        assert_eq!(c.dist(&d), (side.clone().square() * 2u32).sqrt());
+    }
+
+    #[test]
+    fn rounding_modes() {
+        let val = flf64(2.345678);
+
+        assert_eq!(RoundingMode::None.apply(val.clone()), val);
+        assert_eq!(
+            RoundingMode::RoundToNearest(2).apply(val.clone()),
+            fl(235) / 100
+        );
+        assert_eq!(RoundingMode::Truncate(2).apply(val.clone()), fl(234) / 100);
+        assert_eq!(RoundingMode::Ceiling(1).apply(val.clone()), fl(24) / 10);
+        assert_eq!(RoundingMode::Truncate(0).apply(val), fl(2));
+    }
+
+    #[test]
+    fn feasibility_with_zero_capacity() {
+        let vrp = concat!(
+            "1 10\n",
+            "0 0 0 0 0 100 0\n",
+            "1 1 0 0 0 100 0\n",
+            "2 2 0 0 0 100 0\n",
+        );
+        let mut inst = Instance::from_str(vrp).unwrap();
+        assert_eq!(inst.check_feasibility(), Ok(()));
+
+        inst.max_capacity = 0;
+        assert_eq!(
+            inst.check_feasibility(),
+            Err(vec![VerifyError::InvalidInstance(
+                "vehicle capacities have to be positive, but one is 0".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn read_invalid_max_route_duration() {
+        let rows = concat!("0 0 0 0 0 1000 0\n", "1 8 0 5 0 1000 0\n",);
+
+        assert_eq!(
+            Instance::from_str(&format!("MAX ROUTE DURATION: 99999999999\n3 100\n{rows}")),
+            Err(VerifyError::ParseError(
+                "can't parse maximum route duration `99999999999': number too large to fit in target type"
+                    .to_string()
+            ))
+        );
+        assert_eq!(
+            Instance::from_str(&format!("MAX ROUTE DURATION: -5\n3 100\n{rows}")),
+            Err(VerifyError::ParseError(
+                "maximum route duration can't be negative, it is -5".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn hashes_of_instances() {
+        use std::collections::{hash_map::DefaultHasher, HashMap};
+
+        let hash = |inst: &Instance| {
+            let mut hasher = DefaultHasher::new();
+            inst.hash(&mut hasher);
+            hasher.finish()
+        };
+        let inst = generated(0, 100, false);
+
+        assert_eq!(hash(&inst), hash(&inst.clone()));
+
+        // only `==` tells instances with moved points apart
+        let mut moved = inst.clone();
+        moved.pts[1].x += 1.0;
+        assert_ne!(inst, moved);
+        assert_eq!(hash(&inst), hash(&moved));
+
+        let mut other = inst.clone();
+        other.pts[1].demand += 1;
+        assert_ne!(hash(&inst), hash(&other));
+
+        let mut seen = HashMap::new();
+        seen.insert(inst.clone(), "generated");
+        assert_eq!(seen.get(&inst.clone()), Some(&"generated"));
+        assert_eq!(seen.get(&other), None);
+    }
+
+    #[test]
+    fn builder() {
+        let mut builder = InstanceBuilder::default();
+        builder
+            .name("tiny")
+            .vehicles(2)
+            .capacity(10)
+            .add_depot(0.0, 0.0, 0, 100, 0)
+            .add_customer(3.0, 0.0, 4, 0, 50, 1);
+        let inst = builder.build().unwrap();
+        assert_eq!((inst.name.as_str(), inst.vehicles), ("tiny", 2));
+        assert_eq!(inst.pts[1].id, 1);
+        assert!(!inst.is_pdp && inst.depots.is_empty());
+
+        let stop = |x| Stop {
+            x,
+            y: 0.0,
+            due: 100,
+            ..Default::default()
+        };
+
+        let mut builder = InstanceBuilder::default();
+        builder
+            .vehicles(1)
+            .capacity(10)
+            .add_depot(0.0, 0.0, 0, 100, 0)
+            .add_pickup_delivery(5, stop(1.0), stop(2.0));
+        let inst = builder.build().unwrap();
+        assert!(inst.is_pdp);
+        assert_eq!(
+            inst.pts
+                .iter()
+                .map(|p| p.pickup_delivery)
+                .collect::<Vec<_>>(),
+            vec![Some((0, 0)), Some((0, 2)), Some((1, 0))]
+        );
+        assert_eq!(inst.pts[2].demand, -5);
+
+        assert!(InstanceBuilder::default().build().is_err());
+    }
+
+    #[test]
+    fn subsamples() {
+        let inst = generated(0, 20, false);
+        let sample = inst.subsample(5, 7).unwrap();
+        assert_eq!(sample.pts.len(), 6);
+        assert_eq!(sample.pts[0], inst.pts[0]);
+        assert!(sample
+            .pts
+            .iter()
+            .enumerate()
+            .all(|(i, pt)| pt.id == i as i32));
+        assert_eq!(sample, inst.subsample(5, 7).unwrap());
+        assert_eq!(inst.subsample(100, 7).unwrap(), inst);
+        assert!(inst.subsample(0, 7).is_err());
+
+        // pairs are taken whole and stay consistent, check_sanity makes sure
+        let inst = generated(0, 20, true);
+        let sample = inst.subsample(3, 7).unwrap();
+        assert_eq!(sample.pts.len(), 5);
+        assert!(sample.is_pdp);
+    }
+
+    #[test]
+    fn merging() {
+        let a = generated(1, 4, false);
+        let mut b = generated(2, 6, false);
+        b.max_capacity += 100;
+        let merged = a.merge(&b).unwrap();
+        assert_eq!(merged.name, "gen_1+gen_2");
+        assert_eq!(
+            (merged.vehicles, merged.max_capacity),
+            (a.vehicles + b.vehicles, a.max_capacity)
+        );
+        assert_eq!(merged.pts.len(), 11);
+        assert_eq!(merged.pts[..5], a.pts[..]);
+        assert_eq!((merged.pts[5].id, merged.pts[5].x), (5, b.pts[1].x));
+
+        let pdp = generated(3, 4, true);
+        assert!(a.merge(&pdp).is_err());
+        let merged = pdp.merge(&generated(4, 2, true)).unwrap();
+        assert_eq!(merged.pts[5].pickup_delivery, Some((0, 6)));
+        assert_eq!(merged.pts[6].pickup_delivery, Some((5, 0)));
+    }
+
+    // a generated instance, the generator defaults are used for the rest
+    fn generated(seed: u64, num_customers: usize, pdp: bool) -> Instance {
+        crate::generator::InstanceGenerator {
+            num_customers,
+            seed,
+            pdp,
+            ..Default::default()
+        }
+        .generate()
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod sanity_props {
+    use super::*;
+    use proptest::collection::{vec, SizeRange};
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+
+    const CAPACITY: i32 = 10;
+
+    impl Arbitrary for Point {
+        // coordinates are taken from the range, the time window is wide enough
+        // to reach the point from anywhere within it
+        type Parameters = SizeRange;
+        type Strategy = BoxedStrategy<Point>;
+
+        fn arbitrary_with(range: SizeRange) -> Self::Strategy {
+            let (lo, hi) = (range.start() as i32, range.end_incl() as i32);
+            let span = hi - lo;
+            (lo..=hi, lo..=hi, 0..=CAPACITY, 0..=100, 0..=span, 0..=10)
+                .prop_map(move |(x, y, demand, start, slack, service)| Point {
+                    id: 0,
+                    x: x.into(),
+                    y: y.into(),
+                    demand,
+                    start,
+                    due: start + 2 * span + slack,
+                    service,
+                    pickup_delivery: None,
+                    pickup_delivery_chain: None,
+                })
+                .boxed()
+        }
+    }
+
+    // instances that satisfy every invariant `check_sanity` looks at
+    fn sane_instance(pdp: bool) -> impl Strategy<Value = Instance> {
+        vec(any_with::<Point>((0..=100).into()), 3..30).prop_map(move |mut pts| {
+            if pdp && pts.len() % 2 == 0 {
+                pts.pop();
+            }
+            for (i, pt) in pts.iter_mut().enumerate() {
+                pt.id = i as i32;
+            }
+            pts[0] = Point {
+                demand: 0,
+                start: 0,
+                due: 1000,
+                service: 0,
+                pickup_delivery: pdp.then_some((0, 0)),
+                ..pts[0].clone()
+            };
+            if pdp {
+                for i in (1..pts.len()).step_by(2) {
+                    pts[i].pickup_delivery = Some((0, i as i32 + 1));
+                    pts[i + 1].pickup_delivery = Some((i as i32, 0));
+                    pts[i + 1].demand = -pts[i].demand;
+                }
+            }
+
+            Instance {
+                name: "prop".to_string(),
+                vehicles: pts.len() as i32,
+                max_capacity: CAPACITY,
+                pts,
+                is_pdp: pdp,
+                distances: Default::default(),
+                max_route_duration: None,
+                min_vehicles: None,
+                open_routes: false,
+                vehicle_capacities: vec![],
+                depots: vec![],
+                depot_capacities: vec![],
+                vehicle_depot_assignment: None,
+                soft_windows: false,
+                penalty_per_unit: 0.0,
+            }
+        })
+    }
+
+    fn any_sane_instance() -> impl Strategy<Value = Instance> {
+        any::<bool>().prop_flat_map(sane_instance)
+    }
+
+    proptest! {
+        #[test]
+        fn sane_instances_pass(inst in any_sane_instance()) {
+            prop_assert_eq!(inst.check_sanity(), Ok(()));
+        }
+
+        #[test]
+        fn non_sequential_ids_fail(
+            mut inst in any_sane_instance(),
+            idx: Index,
+            shift in any::<i32>().prop_filter("ids have to change", |&s| s != 0),
+        ) {
+            let i = idx.index(inst.pts.len());
+            inst.pts[i].id = (i as i32).wrapping_add(shift);
+
+            prop_assert_eq!(
+                inst.check_sanity(),
+                Err(VerifyError::InvalidInstance(format!(
+                    "points [{i}] do not have correct ids"
+                )))
+            );
+        }
+
+        #[test]
+        fn demand_over_capacity_fails(
+            mut inst in sane_instance(false),
+            idx: Index,
+            excess in 1..1000,
+        ) {
+            let i = idx.index(inst.pts.len());
+            inst.pts[i].demand = inst.max_capacity + excess;
+
+            prop_assert_eq!(
+                inst.check_sanity(),
+                Err(VerifyError::InvalidInstance(format!(
+                    "point {i} can not be visited because its demands are greater than vehicle capacity"
+                )))
+            );
+        }
+
+        #[test]
+        fn pdp_demands_of_the_same_sign_fail(
+            mut inst in sane_instance(true),
+            idx: Index,
+            delivery in 1..=CAPACITY,
+        ) {
+            let pickup = 2 * idx.index(inst.pts.len() / 2) + 1;
+            inst.pts[pickup + 1].demand = delivery;
+
+            prop_assert_eq!(
+                inst.check_sanity(),
+                Err(VerifyError::InvalidInstance(format!(
+                    "point {pickup} demands {} does not sum to 0 with ther pdp pair {} demands {delivery}",
+                    inst.pts[pickup].demand,
+                    pickup + 1,
+                )))
+            );
+        }
+
+        #[test]
+        fn unreachable_customers_fail(
+            mut inst in any_sane_instance(),
+            idx: Index,
+            offset in 1..100,
+        ) {
+            let c = 1 + idx.index(inst.pts.len() - 1);
+            inst.pts[c].x = inst.pts[0].x + offset as f64;
+            inst.pts[c].y = inst.pts[0].y;
+            inst.pts[c].start = 0;
+            inst.pts[c].due = offset - 1;
+
+            prop_assert_eq!(
+                inst.check_sanity(),
+                Err(VerifyError::InvalidInstance(format!(
+                    "earliest possible arrival ({}) from depot to point {c} is after the points due time {}",
+                    fl(offset),
+                    offset - 1
+                )))
+            );
+        }
+    }
 }
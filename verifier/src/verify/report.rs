@@ -0,0 +1,197 @@
+use super::instance::Precision;
+use super::solution::Solution;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteResult {
+    pub route_id: usize,
+    pub distance: rug::Float,
+    pub max_load: i32,
+    // load after servicing each customer
+    pub load_profile: Vec<i32>,
+    pub arrival_times: Vec<rug::Float>,
+    pub departure_times: Vec<rug::Float>,
+    pub slack_times: Vec<rug::Float>,
+    pub backward_slack_times: Vec<rug::Float>,
+}
+
+// routes without customers have no slack
+impl RouteResult {
+    pub fn min_slack(&self) -> Option<&rug::Float> {
+        self.slack_times.iter().min_by(|a, b| a.total_cmp(b))
+    }
+
+    pub fn max_slack(&self) -> Option<&rug::Float> {
+        self.slack_times.iter().max_by(|a, b| a.total_cmp(b))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerificationReport {
+    pub total_distance: rug::Float,
+    pub route_results: Vec<RouteResult>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftVerificationResult {
+    pub distance: rug::Float,
+    pub penalty: f64,
+    pub penalty_violations: usize,
+}
+
+// a verified solution, solutions with fewer routes come first and then the
+// shorter ones, solutions are equal when their routes and distances are
+#[derive(Debug, Clone)]
+pub struct RankedSolution {
+    pub solution: Solution,
+    pub routes: usize,
+    pub distance: rug::Float,
+}
+
+impl RankedSolution {
+    pub fn from_verification(solution: Solution, report: VerificationReport) -> Self {
+        RankedSolution {
+            routes: solution.routes.len(),
+            distance: report.total_distance,
+            solution,
+        }
+    }
+}
+
+impl Ord for RankedSolution {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.routes
+            .cmp(&other.routes)
+            .then_with(|| self.distance.total_cmp(&other.distance))
+    }
+}
+
+impl PartialOrd for RankedSolution {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for RankedSolution {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RankedSolution {}
+
+// quotes a field as described in RFC 4180
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl VerificationReport {
+    pub fn new(route_results: Vec<RouteResult>) -> Self {
+        let prec = route_results
+            .first()
+            .map_or(Precision::STANDARD.0, |r| r.distance.prec());
+        let mut total_distance = rug::Float::new(prec);
+        for r in route_results.iter() {
+            total_distance += &r.distance;
+        }
+
+        VerificationReport {
+            total_distance,
+            route_results,
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = "route_id,distance,num_customers,max_load,time_slack_min,time_slack_max\r\n"
+            .to_string();
+        let field = |slack: Option<&rug::Float>| slack.map_or(String::new(), |s| s.to_string());
+        for r in self.route_results.iter() {
+            csv += &[
+                r.route_id.to_string(),
+                r.distance.to_string(),
+                r.arrival_times.len().to_string(),
+                r.max_load.to_string(),
+                field(r.min_slack()),
+                field(r.max_slack()),
+            ]
+            .map(|f| csv_field(&f))
+            .join(",");
+            csv += "\r\n";
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::fl;
+
+    #[test]
+    fn csv() {
+        let report = VerificationReport::new(vec![RouteResult {
+            route_id: 1,
+            distance: fl(4),
+            max_load: 6,
+            load_profile: vec![6, 4],
+            arrival_times: vec![fl(1), fl(12)],
+            departure_times: vec![fl(11), fl(22)],
+            slack_times: vec![fl(9), fl(3588)],
+            backward_slack_times: vec![fl(9), fl(14)],
+        }]);
+
+        assert_eq!(
+            report.to_csv(),
+            format!(
+                "route_id,distance,num_customers,max_load,time_slack_min,time_slack_max\r\n1,{},2,6,{},{}\r\n",
+                fl(4),
+                fl(9),
+                fl(3588)
+            )
+        );
+
+        let empty = RouteResult {
+            slack_times: vec![],
+            ..report.route_results[0].clone()
+        };
+        assert_eq!(
+            VerificationReport::new(vec![empty]).to_csv().lines().nth(1),
+            Some(format!("1,{},2,6,,", fl(4)).as_str())
+        );
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn ranking() {
+        let ranked = |routes: usize, distance| RankedSolution {
+            solution: Solution {
+                routes: vec![vec![]; routes],
+                ..Default::default()
+            },
+            routes,
+            distance: fl(distance),
+        };
+
+        let mut pool = vec![ranked(3, 10), ranked(2, 30), ranked(2, 20), ranked(3, 5)];
+        pool.sort();
+        let order: Vec<_> = pool
+            .iter()
+            .map(|r| (r.routes, r.distance.to_f64()))
+            .collect();
+        assert_eq!(order, vec![(2, 20.0), (2, 30.0), (3, 5.0), (3, 10.0)]);
+        assert_eq!(ranked(2, 20), ranked(2, 20));
+
+        let sol = Solution {
+            routes: vec![vec![1], vec![2]],
+            ..Default::default()
+        };
+        let report = VerificationReport::new(vec![]);
+        let ranked = RankedSolution::from_verification(sol.clone(), report);
+        assert_eq!((ranked.routes, ranked.solution), (2, sol));
+    }
+}
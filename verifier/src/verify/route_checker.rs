@@ -0,0 +1,290 @@
+// feasibility of routes for solvers that build them a customer at a time, a
+// route starts at the first depot and only has to return once it is finalized
+use super::instance::{Distances, Instance};
+
+// every step only looks at the last point, its departure time, the load so
+// far and the customers and pickups already on the route
+pub struct RouteChecker<'a> {
+    inst: &'a Instance,
+    depot: usize,
+    capacity: i32,
+    route: Vec<usize>,
+    // departure from the last point of the route and the load after it
+    time: rug::Float,
+    load: i32,
+    distance: rug::Float,
+    visited: Vec<bool>,
+    // pickups on the route whose deliveries are not on it yet
+    open_pickups: Vec<usize>,
+}
+
+impl<'a> RouteChecker<'a> {
+    pub fn new(inst: &'a Instance) -> Self {
+        let depot = inst.depots()[0];
+        let pt = &inst.pts[depot];
+        let prec = inst.precision();
+
+        RouteChecker {
+            inst,
+            depot,
+            capacity: inst.depot_capacity(depot),
+            route: vec![],
+            time: prec.fl(pt.start + pt.service),
+            load: 0,
+            distance: prec.fl(0),
+            visited: vec![false; inst.pts.len()],
+            open_pickups: vec![],
+        }
+    }
+
+    pub fn route(&self) -> &[usize] {
+        &self.route
+    }
+
+    pub fn time(&self) -> &rug::Float {
+        &self.time
+    }
+
+    pub fn load(&self) -> i32 {
+        self.load
+    }
+
+    // without the return to the depot
+    pub fn current_distance(&self) -> rug::Float {
+        self.distance.clone()
+    }
+
+    fn last(&self) -> usize {
+        self.route.last().copied().unwrap_or(self.depot)
+    }
+
+    // departure time and load after visiting `p` next
+    fn visit(&self, p: usize) -> Result<(rug::Float, i32), String> {
+        let pt = self
+            .inst
+            .pts
+            .get(p)
+            .ok_or_else(|| format!("point {p} is not in the instance"))?;
+        if self.inst.is_depot(p) {
+            return Err(format!("point {p} is a depot"));
+        }
+        if self.visited[p] {
+            return Err(format!("customer {p} is already on the route"));
+        }
+        if let Some((pickup, 0)) = pt.pickup_delivery {
+            if pickup != 0 && !self.open_pickups.contains(&(pickup as usize)) {
+                return Err(format!("delivery {p} comes before its pickup {pickup}"));
+            }
+        }
+
+        // late arrivals are only penalized with soft windows
+        let time = self.time.clone() + self.inst.between(self.last(), p);
+        if !self.inst.soft_windows && time > pt.due {
+            return Err(format!(
+                "arrival {time} at point {} is after its due time {}",
+                pt.id, pt.due
+            ));
+        }
+
+        let load = self.load + pt.demand;
+        if load < 0 {
+            return Err(format!("load {load} after point {} is negative", pt.id));
+        }
+        if load > self.capacity {
+            return Err(format!(
+                "load {load} after point {} exceeds the capacity {}",
+                pt.id, self.capacity
+            ));
+        }
+
+        let mut time = time.max(&self.inst.precision().fl(pt.start));
+        time += pt.service;
+        Ok((time, load))
+    }
+
+    // appends the customer when the route stays feasible, otherwise the route
+    // is left as it was
+    pub fn try_append(&mut self, customer_id: usize) -> Result<&mut Self, String> {
+        let (time, load) = self.visit(customer_id)?;
+        self.distance += self.inst.between(self.last(), customer_id);
+        self.time = time;
+        self.load = load;
+        self.route.push(customer_id);
+        self.visited[customer_id] = true;
+        match self.inst.pts[customer_id].pickup_delivery {
+            Some((0, delivery)) if delivery != 0 => self.open_pickups.push(customer_id),
+            Some((pickup, 0)) if pickup != 0 => self.open_pickups.retain(|&p| p != pickup as usize),
+            _ => {}
+        }
+        Ok(self)
+    }
+
+    pub fn can_append(&self, customer_id: usize) -> bool {
+        self.visit(customer_id).is_ok()
+    }
+
+    // the finished route, the vehicle has to be back at the depot in time
+    // (unless the routes are open) and within the longest route duration
+    pub fn finalize(&self) -> Result<Vec<usize>, String> {
+        if self.route.is_empty() {
+            return Err("the route is empty".to_string());
+        }
+        if let Some(pickup) = self.open_pickups.first() {
+            return Err(format!("pickup {pickup} is not followed by its delivery"));
+        }
+
+        let depot = &self.inst.pts[self.depot];
+        let mut time = self.time.clone();
+        if !self.inst.open_routes {
+            time += self.inst.between(self.last(), self.depot);
+            if !self.inst.soft_windows && time > depot.due {
+                return Err(format!(
+                    "return {time} to the depot is after its due time {}",
+                    depot.due
+                ));
+            }
+        }
+
+        if let Some(limit) = self.inst.max_route_duration {
+            let duration = time - (depot.start + depot.service);
+            if duration > limit {
+                return Err(format!(
+                    "duration {duration} of the route exceeds the limit {limit}"
+                ));
+            }
+        }
+
+        Ok(self.route.clone())
+    }
+}
+
+impl Instance {
+    // time windows, capacity and pickup and delivery order of a route that is
+    // not finished yet, the return to the depot is not checked
+    pub fn is_feasible_route(&self, partial_route: &[usize]) -> Result<(), String> {
+        let mut checker = RouteChecker::new(self);
+        for &p in partial_route {
+            checker.try_append(p)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn instance() -> Instance {
+        Instance::from_str(concat!(
+            "tiny\n",
+            "\n",
+            "VEHICLE\n",
+            "NUMBER CAPACITY\n",
+            "2 5\n",
+            "\n",
+            "CUSTOMER\n",
+            "CUST NO.  XCOORD.    YCOORD.    DEMAND   READY TIME  DUE DATE   SERVICE TIME\n",
+            "\n",
+            "0 0 0 0 0 100 0\n",
+            "1 3 0 3 0 10 1\n",
+            "2 3 4 3 0 20 0\n",
+            "3 0 4 1 0 5 0\n",
+        ))
+        .unwrap()
+    }
+
+    fn pdp_instance() -> Instance {
+        Instance::from_str(concat!(
+            "2 10\n",
+            "0 0 0 0 0 100 0 0 0\n",
+            "1 10 0 5 0 100 10 0 2\n",
+            "2 20 0 -5 0 100 0 1 0\n",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn partial_routes() {
+        let inst = instance();
+        assert_eq!(inst.is_feasible_route(&[]), Ok(()));
+        assert_eq!(inst.is_feasible_route(&[1]), Ok(()));
+        assert_eq!(inst.is_feasible_route(&[3, 1]), Ok(()));
+        assert!(inst
+            .is_feasible_route(&[1, 2])
+            .unwrap_err()
+            .contains("capacity"));
+        assert!(inst
+            .is_feasible_route(&[1, 3])
+            .unwrap_err()
+            .contains("due time"));
+        assert!(inst.is_feasible_route(&[7]).is_err());
+    }
+
+    #[test]
+    fn extending_a_route() {
+        let inst = instance();
+        let mut checker = RouteChecker::new(&inst);
+        assert!(checker.finalize().is_err());
+
+        checker.try_append(1).unwrap();
+        assert_eq!((checker.time().to_f64(), checker.load()), (4.0, 3));
+        assert_eq!(checker.current_distance().to_f64(), 3.0);
+
+        assert!(!checker.can_append(2));
+        assert!(checker.try_append(3).is_err());
+        assert_eq!(checker.route(), &[1]);
+        assert_eq!((checker.time().to_f64(), checker.load()), (4.0, 3));
+
+        assert_eq!(checker.finalize(), Ok(vec![1]));
+
+        let mut late = inst.clone();
+        late.pts[0].due = 6;
+        assert!(RouteChecker::new(&late)
+            .try_append(1)
+            .unwrap()
+            .finalize()
+            .unwrap_err()
+            .contains("depot"));
+    }
+    #[test]
+    fn pickups_and_deliveries() {
+        let inst = pdp_instance();
+        let mut checker = RouteChecker::new(&inst);
+        assert!(!checker.can_append(2));
+
+        checker.try_append(1).unwrap();
+        assert!(checker.finalize().unwrap_err().contains("pickup 1"));
+        assert_eq!(checker.try_append(2).unwrap().finalize(), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn soft_windows() {
+        let mut inst = instance();
+        inst.pts[0].due = 6;
+        inst.soft_windows = true;
+
+        let mut checker = RouteChecker::new(&inst);
+        checker.try_append(1).unwrap().try_append(3).unwrap();
+        assert_eq!(checker.finalize(), Ok(vec![1, 3]));
+    }
+    #[test]
+    fn routes_visit_every_customer_once() {
+        let inst = instance();
+        assert!(inst
+            .is_feasible_route(&[3, 3])
+            .unwrap_err()
+            .contains("already on the route"));
+        assert!(inst
+            .is_feasible_route(&[3, 0])
+            .unwrap_err()
+            .contains("depot"));
+
+        let pdp = pdp_instance();
+        assert_eq!(pdp.is_feasible_route(&[1, 2]), Ok(()));
+        assert_eq!(
+            pdp.is_feasible_route(&[2, 1]),
+            Err("delivery 2 comes before its pickup 1".to_string())
+        );
+    }
+}
@@ -1,5 +1,4 @@
-use chrono;
-use itertools;
+use super::VerifyError;
 use pest::Parser;
 use pest_derive::Parser;
 use serde::{Deserialize, Serialize};
@@ -12,22 +11,235 @@ pub struct SolutionParser;
 
 #[serde_with::serde_as]
 #[derive(Default, Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Solution {
     pub instance_name: String,
     pub routes: Vec<Vec<usize>>,
+    // index of the vehicle used by every route, routes use vehicles in order
+    // without it
+    #[serde(default)]
+    pub vehicle_assignments: Option<Vec<usize>>,
+    #[serde(default)]
+    pub authors: Option<String>,
+    #[serde(default)]
+    pub reference: Option<String>,
+}
+
+// vehicle -> trips -> customers
+#[derive(Default, Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct MultiTripSolution {
+    pub instance_name: String,
+    pub vehicle_routes: Vec<Vec<Vec<usize>>>,
+}
+
+impl MultiTripSolution {
+    // every trip becomes a route assigned to its vehicle, empty trips are dropped
+    pub fn to_solution(&self) -> Solution {
+        let (routes, vehicles) = self
+            .vehicle_routes
+            .iter()
+            .enumerate()
+            .flat_map(|(vehicle, trips)| trips.iter().map(move |trip| (trip.clone(), vehicle)))
+            .filter(|(trip, _)| !trip.is_empty())
+            .unzip();
+
+        Solution {
+            instance_name: self.instance_name.clone(),
+            routes,
+            vehicle_assignments: Some(vehicles),
+            ..Default::default()
+        }
+    }
+}
+
+// routes added a customer at a time, adding a customer without a started
+// route starts one
+#[derive(Default, Debug, Clone)]
+pub struct SolutionBuilder {
+    instance_name: String,
+    routes: Vec<Vec<usize>>,
+    current_route: Option<Vec<usize>>,
+}
+
+impl SolutionBuilder {
+    pub fn new(instance_name: &str) -> Self {
+        SolutionBuilder {
+            instance_name: instance_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // a route that is still open is ended first
+    pub fn start_route(&mut self) -> &mut Self {
+        self.end_route();
+        self.current_route = Some(vec![]);
+        self
+    }
+
+    pub fn add_customer(&mut self, id: usize) -> &mut Self {
+        self.current_route.get_or_insert_with(Vec::new).push(id);
+        self
+    }
+
+    // empty routes are dropped
+    pub fn end_route(&mut self) -> &mut Self {
+        if let Some(route) = self.current_route.take().filter(|r| !r.is_empty()) {
+            self.routes.push(route);
+        }
+        self
+    }
+
+    fn check_ended(&self) -> Result<(), VerifyError> {
+        match self.current_route {
+            Some(_) => Err(VerifyError::UnfinishedRoute {
+                route_id: self.routes.len() + 1,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn build(self) -> Result<Solution, VerifyError> {
+        self.check_ended()?;
+        Ok(self.build_unchecked())
+    }
+
+    // an open route is kept as the last one
+    pub fn build_unchecked(mut self) -> Solution {
+        self.end_route();
+        Solution {
+            instance_name: self.instance_name,
+            routes: self.routes,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct OrToolsRoute {
+    vehicle: usize,
+    route: Vec<usize>,
+}
+
+impl Solution {
+    // routes may start and end at the depot and unused vehicles have empty
+    // routes, neither is a part of a sintef solution
+    pub fn from_ortools_json(s: &str) -> Result<Self, VerifyError> {
+        let mut vehicles: Vec<OrToolsRoute> = serde_json::from_str(s)
+            .map_err(|e| VerifyError::ParseError(format!("Solution parsing error: {e}")))?;
+        vehicles.sort_by_key(|v| v.vehicle);
+
+        let routes = vehicles
+            .into_iter()
+            .map(|v| {
+                let start = v
+                    .route
+                    .iter()
+                    .position(|&p| p != 0)
+                    .unwrap_or(v.route.len());
+                let end = v
+                    .route
+                    .iter()
+                    .rposition(|&p| p != 0)
+                    .map_or(start, |e| e + 1);
+                v.route[start..end].to_vec()
+            })
+            .filter(|route| !route.is_empty())
+            .collect();
+
+        Ok(Solution {
+            routes,
+            ..Default::default()
+        })
+    }
+
+    pub fn to_ortools_json(&self) -> String {
+        let routes = self
+            .routes
+            .iter()
+            .zip(crate::verify::route_vehicle_ids(self))
+            .map(|(route, vehicle)| OrToolsRoute {
+                vehicle,
+                route: route.clone(),
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&routes).unwrap()
+    }
+
+    // route order does not matter, so equivalent solutions have the same
+    // canonical form; routes without an assignment keep the vehicle they
+    // would use in order
+    pub fn canonical(&self) -> Solution {
+        let mut routes = self.routes.iter().enumerate().collect::<Vec<_>>();
+        routes.sort_by_key(|&(_, route)| route);
+        Solution {
+            instance_name: self.instance_name.clone(),
+            routes: routes.iter().map(|&(_, route)| route.clone()).collect(),
+            vehicle_assignments: self.vehicle_assignments.as_ref().map(|a| {
+                routes
+                    .iter()
+                    .map(|&(i, _)| a.get(i).copied().unwrap_or(i))
+                    .collect()
+            }),
+            authors: self.authors.clone(),
+            reference: self.reference.clone(),
+        }
+    }
+
+    // only the routes and vehicle assignments are hashed, the instance name is
+    // often missing or written differently; FNV-1a over the route lengths and
+    // points keeps the value the same across platforms and Rust releases,
+    // unlike std's hashers
+    pub fn stable_hash(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        let sol = self.canonical();
+        // no route is that long, so the assignments can't be taken for one
+        let assignments = sol
+            .vehicle_assignments
+            .iter()
+            .flat_map(|a| std::iter::once(usize::MAX).chain(a.iter().copied()));
+        sol.routes
+            .iter()
+            .flat_map(|route| std::iter::once(route.len()).chain(route.iter().copied()))
+            .chain(assignments)
+            .flat_map(|n| (n as u64).to_le_bytes())
+            .fold(OFFSET, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+            })
+    }
+
+    pub fn to_binary(&self) -> Vec<u8> {
+        super::binary::encode(self)
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, VerifyError> {
+        super::binary::decode(bytes)
+    }
 }
 
 impl Display for Solution {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Instance name: {}\n", self.instance_name.to_uppercase())?;
-        write!(f, "Authors: \n")?;
-        write!(f, "Date: {}\n", chrono::Local::now().format("%Y-%m-%d"))?;
-        write!(f, "Reference: \n")?;
-        write!(f, "Solution\n")?;
+        // the same normalization as in from_str, so that displayed solutions
+        // parse back to the same value
+        let mut name = self.instance_name.to_lowercase();
+        name.retain(|c| !c.is_whitespace());
+        writeln!(f, "Instance name: {name}")?;
+        writeln!(
+            f,
+            "Authors: {}",
+            self.authors.as_deref().unwrap_or_default()
+        )?;
+        writeln!(f, "Date: {}", chrono::Local::now().format("%Y-%m-%d"))?;
+        writeln!(
+            f,
+            "Reference: {}",
+            self.reference.as_deref().unwrap_or_default()
+        )?;
+        writeln!(f, "Solution")?;
         for (i, route) in self.routes.iter().enumerate() {
-            write!(
+            writeln!(
                 f,
-                "Route {}: {}\n",
+                "Route {}: {}",
                 i + 1,
                 itertools::join(route.iter().map(|x| x.to_string()), " ")
             )?;
@@ -37,30 +249,55 @@ impl Display for Solution {
 }
 
 impl FromStr for Solution {
-    type Err = String;
+    type Err = VerifyError;
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         let parsed = SolutionParser::parse(Rule::file, input)
-            .map_err(|x| format!("Solution parsing error: {x}"))?
+            .map_err(|x| match unrecognized_instance_name(input) {
+                Some(name) => VerifyError::ParseError(format!(
+                    "Instance name '{name}' is not a recognized SINTEF instance name format; expected pattern like 'rc1_4_10'"
+                )),
+                None => VerifyError::ParseError(format!("Solution parsing error: {x}")),
+            })?
             .next()
             .unwrap();
 
+        Ok(Solution::from_parsed(parsed))
+    }
+}
+
+impl Solution {
+    // accepts any instance name, e.g. of a custom benchmark, while `from_str`
+    // only accepts names like the ones of the standard benchmarks
+    pub fn from_str_lenient(input: &str) -> Result<Self, VerifyError> {
+        let parsed = SolutionParser::parse(Rule::file_lenient, input)
+            .map_err(|x| VerifyError::ParseError(format!("Solution parsing error: {x}")))?
+            .next()
+            .unwrap();
+
+        Ok(Solution::from_parsed(parsed))
+    }
+
+    fn from_parsed(parsed: pest::iterators::Pair<Rule>) -> Self {
         let mut instance_name: String = "".to_string();
+        let mut authors = None;
+        let mut reference = None;
 
         let mut routes: Vec<Vec<usize>> = vec![];
 
         for r in parsed.into_inner() {
             match r.as_rule() {
-                Rule::instance_name => {
+                Rule::instance_name | Rule::instance_name_loose => {
                     let mut s = r.as_span().as_str().to_owned().to_lowercase();
                     s.retain(|c| !c.is_whitespace());
                     instance_name = s;
                 }
+                Rule::authors => authors = header_text(r),
+                Rule::reference => reference = header_text(r),
                 Rule::route => {
                     routes.push(
                         r.as_span()
                             .as_str()
                             .split_whitespace()
-                            .into_iter()
                             .map(|c| c.parse().unwrap_or_default())
                             .collect(),
                     );
@@ -68,17 +305,72 @@ impl FromStr for Solution {
                 _ => unreachable!(),
             }
         }
-        Ok(Solution {
+        Solution {
             instance_name,
             routes,
-        })
+            vehicle_assignments: None,
+            authors,
+            reference,
+        }
     }
 }
 
+// the name in the first line when it has characters the grammar does not
+// allow, pest only points at the offending column
+fn unrecognized_instance_name(input: &str) -> Option<&str> {
+    let line = input.trim_start_matches('\u{feff}').lines().next()?;
+    let (key, name) = line.split_once(':')?;
+    let key = key.split_whitespace().collect::<String>().to_lowercase();
+    if key != "instance" && key != "instancename" {
+        return None;
+    }
+
+    let name = name.trim();
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c.is_whitespace();
+    (!name.is_empty() && !name.chars().all(allowed)).then_some(name)
+}
+
+// empty header fields are missing
+fn header_text(field: pest::iterators::Pair<Rule>) -> Option<String> {
+    let text = field.into_inner().next().unwrap().as_str().trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn builder() {
+        let mut builder = SolutionBuilder::new("c101");
+        builder
+            .start_route()
+            .add_customer(1)
+            .add_customer(2)
+            .end_route();
+        builder.add_customer(3);
+        assert_eq!(
+            builder.clone().build(),
+            Err(VerifyError::UnfinishedRoute { route_id: 2 })
+        );
+        assert_eq!(
+            builder.clone().build_unchecked(),
+            Solution {
+                instance_name: "c101".to_string(),
+                routes: vec![vec![1, 2], vec![3]],
+                ..Default::default()
+            }
+        );
+
+        // starting a route ends the open one and empty routes are dropped
+        builder.start_route().add_customer(4);
+        builder.start_route().end_route();
+        assert_eq!(
+            builder.build().unwrap().routes,
+            vec![vec![1, 2], vec![3], vec![4]]
+        );
+    }
+
     #[test]
     fn read_gh_solution() {
         let sol_str = concat!(
@@ -107,6 +399,7 @@ mod tests {
                     vec![7],
                     vec![8, 9, 10, 11, 12],
                 ],
+                ..Default::default()
             }
         );
     }
@@ -139,6 +432,7 @@ mod tests {
                     vec![7],
                     vec![8, 9, 10, 11, 12],
                 ],
+                ..Default::default()
             }
         );
     }
@@ -148,16 +442,84 @@ mod tests {
         let sol = Solution {
             instance_name: "LC1_8_7".to_string(),
             routes: vec![vec![7, 8], vec![9, 10, 11], vec![5, 4, 3, 2, 1], vec![6]],
+            ..Default::default()
         };
         let today = chrono::Local::now().format("%Y-%m-%d");
         assert_eq!(
             sol.to_string(),
             format!(
-            "Instance name: LC1_8_7\nAuthors: \nDate: {today}\nReference: \nSolution\nRoute 1: 7 8\nRoute 2: 9 10 11\nRoute 3: 5 4 3 2 1\nRoute 4: 6\n"
+            "Instance name: lc1_8_7\nAuthors: \nDate: {today}\nReference: \nSolution\nRoute 1: 7 8\nRoute 2: 9 10 11\nRoute 3: 5 4 3 2 1\nRoute 4: 6\n"
             )
         );
     }
 
+    #[test]
+    fn authors_and_reference_are_kept() {
+        let sol = Solution {
+            instance_name: "c1_2_1".to_string(),
+            routes: vec![vec![1, 2]],
+            authors: Some("A. Author, B. Author".to_string()),
+            reference: Some("doi:10.1000/182".to_string()),
+            ..Default::default()
+        };
+        let text = sol.to_string();
+        assert!(text.contains("Authors: A. Author, B. Author\n"));
+        assert!(text.contains("Reference: doi:10.1000/182\n"));
+        assert_eq!(Solution::from_str(&text), Ok(sol));
+    }
+
+    #[test]
+    fn unrecognized_instance_names_are_reported() {
+        let sol_str = concat!(
+            "Instance name: my.instance\n",
+            "Authors: \n",
+            "Date:\n",
+            "Reference: \n",
+            "Solution\n",
+            "Route 1: 1 2 3\n",
+        );
+
+        assert_eq!(
+            Solution::from_str(sol_str),
+            Err(VerifyError::ParseError(
+                "Instance name 'my.instance' is not a recognized SINTEF instance name format; expected pattern like 'rc1_4_10'".to_string()
+            ))
+        );
+
+        // other problems keep the error from the parser
+        let err = Solution::from_str(
+            &sol_str
+                .replace("my.instance", "c1_2_1")
+                .replace("Route 1", "Rout 1"),
+        );
+        assert!(
+            matches!(err, Err(VerifyError::ParseError(e)) if e.starts_with("Solution parsing error"))
+        );
+    }
+
+    #[test]
+    fn lenient_instance_names() {
+        let sol_str = concat!(
+            "Instance name: my.instance+v2\n",
+            "Authors: \n",
+            "Date:\n",
+            "Reference: \n",
+            "Solution\n",
+            "Route 1: 1 2 3\n",
+        );
+
+        assert!(Solution::from_str(sol_str).is_err());
+        let sol = Solution::from_str_lenient(sol_str).unwrap();
+        assert_eq!(sol.instance_name, "my.instance+v2");
+        assert_eq!(sol.routes, vec![vec![1, 2, 3]]);
+
+        let strict = sol_str.replace("my.instance+v2", "RC1_4_10");
+        assert_eq!(
+            Solution::from_str_lenient(&strict),
+            Solution::from_str(&strict)
+        );
+    }
+
     #[test]
     fn whitespaces_are_ok() {
         let sol_str = concat!(
@@ -186,6 +548,7 @@ mod tests {
                     vec![7],
                     vec![8, 9, 10, 11, 12],
                 ],
+                ..Default::default()
             }
         );
     }
@@ -218,6 +581,7 @@ mod tests {
                     vec![7],
                     vec![8, 9, 10, 11, 12],
                 ],
+                ..Default::default()
             }
         );
     }
@@ -242,6 +606,7 @@ mod tests {
             Solution {
                 instance_name: "rc1_4_10".to_string(),
                 routes: vec![vec![1, 2, 3],],
+                ..Default::default()
             }
         );
     }
@@ -266,6 +631,7 @@ mod tests {
             Solution {
                 instance_name: "".to_string(),
                 routes: vec![vec![1, 2, 3],],
+                ..Default::default()
             }
         );
     }
@@ -290,6 +656,9 @@ mod tests {
             Solution {
                 instance_name: "".to_string(),
                 routes: vec![vec![1, 2, 3],],
+                authors: Some("my pet hamster".to_string()),
+                reference: Some("中文范例文本نص مثال عربي".to_string()),
+                ..Default::default()
             }
         );
     }
@@ -301,18 +670,119 @@ mod tests {
                 for s in (2..=10).step_by(2) {
                     for i in 1..=10 {
                         let inst = format!("{pdp}{t}_{s}_{i}");
-                        assert_eq!(
-                            true,
-                            SolutionParser::parse(Rule::instance_name, &inst).is_ok()
-                        );
+                        assert!(SolutionParser::parse(Rule::instance_name, &inst).is_ok());
                         let inst = format!("{pdp}{t}_{s}_{i}").to_uppercase();
-                        assert_eq!(
-                            true,
-                            SolutionParser::parse(Rule::instance_name, &inst).is_ok()
-                        );
+                        assert!(SolutionParser::parse(Rule::instance_name, &inst).is_ok());
                     }
                 }
             }
         }
     }
+
+    #[test]
+    fn display_round_trip() {
+        let fixtures = [
+            concat!(
+                "Instance name: LR2_8_1\n",
+                "Authors: \n",
+                "Date:\n",
+                "Reference: \n",
+                "Solution\n",
+                "Route 1: 1 2 3\n",
+                "Route 2: 4 5 6\n",
+            ),
+            concat!(
+                "Instance name\t:  \t   rc 1_4_10\n",
+                "Authors : \n",
+                "Date:\n",
+                "Reference    : \n",
+                "Solution\n",
+                "Route 0: 1   2     3\n",
+                "Route: 4\t5 6\n",
+            ),
+            concat!(
+                "Instance name:\n",
+                "Authors: my pet hamster\n",
+                "Date: whatever 2023-13-72\n",
+                "Reference: \n",
+                "Solution\n",
+                "Route 0: 1 2 3\n",
+            ),
+        ];
+
+        for fixture in fixtures {
+            let sol = Solution::from_str(fixture).unwrap();
+            assert_eq!(Solution::from_str(&sol.to_string()), Ok(sol));
+        }
+
+        let sol = Solution {
+            instance_name: "LC1_8_7".to_string(),
+            routes: vec![vec![7, 8], vec![6]],
+            ..Default::default()
+        };
+        let parsed = Solution::from_str(&sol.to_string()).unwrap();
+        assert_eq!(parsed.instance_name, "lc1_8_7");
+        assert_eq!(parsed.to_string(), sol.to_string());
+    }
+
+    #[test]
+    fn canonical_and_hash() {
+        let sol = Solution {
+            instance_name: "lc1_2_1".to_string(),
+            routes: vec![vec![5, 6], vec![1, 2, 3], vec![4]],
+            ..Default::default()
+        };
+        let reordered = Solution {
+            instance_name: "LC1_2_1".to_string(),
+            routes: vec![vec![4], vec![5, 6], vec![1, 2, 3]],
+            ..Default::default()
+        };
+        let different = Solution {
+            instance_name: "lc1_2_1".to_string(),
+            routes: vec![vec![5, 6], vec![2, 1, 3], vec![4]],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            sol.canonical().routes,
+            vec![vec![1, 2, 3], vec![4], vec![5, 6]]
+        );
+        assert_eq!(sol.canonical(), sol.canonical().canonical());
+        assert_eq!(sol.stable_hash(), reordered.stable_hash());
+        assert_eq!(sol.stable_hash(), 12634946595864045122);
+        let assigned = Solution {
+            vehicle_assignments: Some(vec![0, 1, 2]),
+            ..sol.clone()
+        };
+        assert_eq!(
+            assigned.canonical().vehicle_assignments,
+            Some(vec![1, 2, 0])
+        );
+        let short = Solution {
+            vehicle_assignments: Some(vec![2]),
+            ..sol.clone()
+        };
+        assert_eq!(short.canonical().vehicle_assignments, Some(vec![1, 2, 2]));
+        assert_ne!(sol.stable_hash(), different.stable_hash());
+        assert_ne!(sol.stable_hash(), assigned.stable_hash());
+        assert_ne!(assigned.stable_hash(), short.stable_hash());
+    }
+
+    #[test]
+    fn ortools_json() {
+        let json = r#"[
+            {"vehicle": 2, "route": [0, 5, 6, 0]},
+            {"vehicle": 0, "route": [0, 1, 2, 3, 0]},
+            {"vehicle": 1, "route": [0, 0]}
+        ]"#;
+
+        let sol = Solution::from_ortools_json(json).unwrap();
+        assert_eq!(sol.routes, vec![vec![1, 2, 3], vec![5, 6]]);
+        assert_eq!(
+            sol.to_ortools_json(),
+            r#"[{"vehicle":0,"route":[1,2,3]},{"vehicle":1,"route":[5,6]}]"#
+        );
+        assert_eq!(Solution::from_ortools_json(&sol.to_ortools_json()), Ok(sol));
+        assert!(Solution::from_ortools_json("{}").is_err());
+    }
 }
@@ -0,0 +1,144 @@
+use super::instance::{coordinate_range, Instance, Point};
+use super::solution::Solution;
+use itertools::Itertools;
+use std::fmt::Write;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 600.0;
+const MARGIN: f64 = 20.0;
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// maps instance coordinates to the viewport, keeping the aspect ratio and
+// pointing the y axis up
+struct Viewport {
+    min_x: f64,
+    min_y: f64,
+    scale: f64,
+}
+
+impl Viewport {
+    fn new(pts: &[Point]) -> Self {
+        let (min_x, max_x) = coordinate_range(pts, |pt| pt.x);
+        let (min_y, max_y) = coordinate_range(pts, |pt| pt.y);
+        let span_x = (max_x - min_x).max(1.0);
+        let span_y = (max_y - min_y).max(1.0);
+
+        Viewport {
+            min_x,
+            min_y,
+            scale: ((WIDTH - 2.0 * MARGIN) / span_x).min((HEIGHT - 2.0 * MARGIN) / span_y),
+        }
+    }
+
+    fn map(&self, pt: &Point) -> (f64, f64) {
+        (
+            MARGIN + (pt.x - self.min_x) * self.scale,
+            HEIGHT - MARGIN - (pt.y - self.min_y) * self.scale,
+        )
+    }
+}
+
+impl Instance {
+    pub fn to_svg(&self, solution: Option<&Solution>) -> String {
+        let view = Viewport::new(&self.pts);
+        let mut svg = String::new();
+
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}">"#
+        )
+        .unwrap();
+        writeln!(svg, "<title>{}</title>", escape(&self.name)).unwrap();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+
+        if let Some(sol) = solution {
+            let routes = sol.routes.len().max(1);
+            for (i, route) in sol.routes.iter().enumerate() {
+                let points = std::iter::once(&0)
+                    .chain(route.iter())
+                    .chain(std::iter::once(&0))
+                    .filter_map(|&p| self.pts.get(p))
+                    .map(|pt| {
+                        let (x, y) = view.map(pt);
+                        format!("{x:.1},{y:.1}")
+                    })
+                    .join(" ");
+                writeln!(
+                    svg,
+                    r#"<polyline points="{points}" fill="none" stroke="hsl({}, 70%, 45%)" stroke-width="1.5"/>"#,
+                    i * 360 / routes
+                )
+                .unwrap();
+            }
+        }
+
+        for (i, pt) in self.pts.iter().enumerate() {
+            let (x, y) = view.map(pt);
+            if i == 0 {
+                writeln!(
+                    svg,
+                    r#"<rect x="{:.1}" y="{:.1}" width="10" height="10" fill="black"/>"#,
+                    x - 5.0,
+                    y - 5.0
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    svg,
+                    r#"<circle cx="{x:.1}" cy="{y:.1}" r="3" fill="white" stroke="black"/>"#
+                )
+                .unwrap();
+            }
+            writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" font-size="8">{}</text>"#,
+                x + 4.0,
+                y - 4.0,
+                pt.id
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn render() {
+        let instance = concat!(
+            "2 10\n",
+            "0 0 0 0 0 100 0\n",
+            "1 10 0 5 0 100 0\n",
+            "2 0 10 5 0 100 0\n",
+        );
+        let inst = Instance::from_str(instance).unwrap();
+        let sol = Solution {
+            instance_name: String::new(),
+            routes: vec![vec![1], vec![2]],
+            ..Default::default()
+        };
+
+        let svg = inst.to_svg(Some(&sol));
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert_eq!(svg.matches("<text").count(), 3);
+        assert!(svg.contains(r#"<polyline points="20.0,580.0 580.0,580.0 20.0,580.0""#));
+        assert!(svg.contains("hsl(180, 70%, 45%)"));
+
+        assert!(!inst.to_svg(None).contains("<polyline"));
+    }
+}